@@ -0,0 +1,3199 @@
+// Profile, cookie file and config handling that doesn't touch egui, so it can be tested on its own
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, UpdateKind};
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const ROBLOSECURITY: &str = ".ROBLOSECURITY";
+
+const MIN_TOKEN_LENGTH: usize = 100;
+
+// Deleted profiles are moved here as "<unix seconds>__<original file name>"
+pub const TRASH_DIR: &str = ".trash";
+// Archived profiles keep their file name and settings but are left out of every scan
+pub const ARCHIVE_DIR: &str = "archived";
+
+// Waits between attempts when the copy hits a transient error; keeps the UI stall under half a second
+const COPY_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+];
+
+// Records the last profile switched to, since Sober rotating the session breaks content comparison
+const ACTIVE_PROFILE_MARKER: &str = ".active_profile";
+
+// Other accounts' cookies, encrypted while a shared machine is handed over
+const VAULT_FILE: &str = ".sober-vault";
+const VAULT_KDF_ROUNDS: u32 = 100_000;
+
+// The manager's own files in the config dir
+const CONFIG_DIR_NAME: &str = "sober-cookie-manager";
+const CONFIG_FILE: &str = "config.json";
+const CONFIG_BACKUP_FILE: &str = "config.bak";
+const DIRECTORY_HISTORY_FILE: &str = "directory.txt";
+const PROFILES_LISTING_FILE: &str = "profiles.json";
+pub const LOG_FILE_NAME: &str = "sober-cookie-manager.log";
+const PROFILES_LISTING_VERSION: u32 = 1;
+
+// The file Sober reads its session from, and the fallback for directories where nothing else is found
+pub const LIVE_COOKIE_FILE: &str = "cookies";
+// Live file names other clients are known to read, looked for in this order
+const KNOWN_LIVE_COOKIE_FILES: [&str; 2] = [LIVE_COOKIE_FILE, "cookies.txt"];
+
+// sober-switch://<profile> links switch to that profile
+pub const URL_SCHEME: &str = "sober-switch";
+const MAX_LINK_PROFILE_LENGTH: usize = 64;
+
+const SOBER_APP_ID: &str = "org.vinegarhq.Sober";
+
+pub const DEFAULT_COOKIE_DIRECTORY: &str = "~/.var/app/org.vinegarhq.Sober/data/sober/";
+const SOBER_CONFIG_FILE: &str = "~/.var/app/org.vinegarhq.Sober/config/sober/config.json";
+const SOBER_CONFIG_DATA_KEYS: [&str; 3] = ["data_directory", "data_dir", "data_path"];
+
+// directory.txt keeps this many recently used directories, most recent first
+const MAX_RECENT_DIRECTORIES: usize = 8;
+
+// Switches kept in the config's history, most recent first
+const MAX_SWITCH_HISTORY: usize = 50;
+
+// What went wrong in a file or profile operation; only turned into text where it's shown
+#[derive(Debug)]
+pub enum AppError {
+    DirectoryNotFound(PathBuf),
+    ProfileNotFound(String),
+    InvalidInput(&'static str),
+    NameTaken(String),
+    AliasTaken { alias: String, owner: String },
+    AlreadyExists(PathBuf),
+    CopyFailed { source: PathBuf, target: PathBuf, error: io::Error },
+    BackupFailed { target: PathBuf, error: io::Error },
+    ImageDecode { path: PathBuf, error: image::ImageError },
+    ConfigWrite { path: PathBuf, error: io::Error },
+    InvalidCookie { profile: String, reason: String },
+    SwitchNotVerified(String),
+    WrongPassword,
+    VaultCorrupt(String),
+    Io { action: &'static str, path: PathBuf, error: io::Error },
+}
+
+impl AppError {
+    pub fn io(action: &'static str, path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+        move |error| Self::Io { action, path, error }
+    }
+    
+    fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Self::CopyFailed { error, .. }
+            | Self::BackupFailed { error, .. }
+            | Self::ConfigWrite { error, .. }
+            | Self::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+    
+    // Locked files and interrupted syscalls usually clear up on their own; anything else won't
+    fn is_transient(&self) -> bool {
+        match self.io_error().map(io::Error::kind) {
+            Some(io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted | io::ErrorKind::TimedOut) => true,
+            // Windows reports a file held open by another process as access denied
+            Some(io::ErrorKind::PermissionDenied) => cfg!(windows),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DirectoryNotFound(path) => write!(f, "Directory {} does not exist", path.display()),
+            Self::ProfileNotFound(name) => write!(f, "Profile '{}' no longer exists", name),
+            Self::InvalidInput(reason) => f.write_str(reason),
+            Self::NameTaken(name) => write!(f, "A profile named '{}' already exists", name),
+            Self::AliasTaken { alias, owner } => write!(f, "Alias '{}' is already used by {}", alias, owner),
+            Self::AlreadyExists(path) => write!(f, "{} already exists", path.display()),
+            Self::CopyFailed { source, target, error } => {
+                write!(f, "Failed to copy {} to {}: {}", source.display(), target.display(), error)
+            }
+            Self::BackupFailed { target, error } => {
+                write!(f, "Failed to back up the live cookies file to {}: {}", target.display(), error)
+            }
+            Self::ImageDecode { path, error } => write!(f, "Could not load image {}: {}", path.display(), error),
+            Self::ConfigWrite { path, error } => write!(f, "Could not write {}: {}", path.display(), error),
+            Self::InvalidCookie { profile, reason } => write!(f, "The {} cookie file can't be used: {}", profile, reason),
+            Self::SwitchNotVerified(profile) => {
+                write!(f, "Copied the {} profile, but the live cookies file doesn't match it", profile)
+            }
+            Self::WrongPassword => f.write_str("Wrong password"),
+            Self::VaultCorrupt(reason) => write!(f, "The locked profiles can't be read: {}", reason),
+            Self::Io { action, path, error } => write!(f, "Failed to {} {}: {}", action, path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ImageDecode { error, .. } => Some(error),
+            _ => self.io_error().map(|e| e as &(dyn std::error::Error + 'static)),
+        }
+    }
+}
+
+pub struct CookieFile {
+    pub profile_name: String,
+    // May not be valid UTF-8; only ever shown through to_string_lossy
+    pub file_name: OsString,
+    pub size: u64,
+}
+
+// One line of directory.txt: "<unix seconds>\t<path>"
+#[derive(Clone)]
+pub struct RecentDirectory {
+    pub path: String,
+    pub used_at: u64,
+}
+
+// Proposed rename of a legacy cookie_<name> file; skipped if the target already exists
+pub struct LegacyRename {
+    pub from: String,
+    pub to: String,
+    pub conflict: bool,
+}
+
+// One file of a batch rename preview, with the reason it can't be renamed if any
+pub struct BatchRename {
+    pub from: String,
+    pub to: String,
+    pub problem: Option<String>,
+}
+
+// What to do with the live cookies file before a switch overwrites it
+pub enum BackupPlan {
+    Skip,
+    InDirectory,
+    To(PathBuf),
+}
+
+// How a switch treats the file it replaces
+pub struct SwitchOptions {
+    pub backup: BackupPlan,
+    // Leave the new live file read-only
+    pub freeze: bool,
+    // Write the live file with LF line endings; the profile's own file is left as it is
+    pub normalize_line_endings: bool,
+    // The file the directory's client reads its session from
+    pub live_file: String,
+}
+
+impl SwitchOptions {
+    // Command line flags win over the config
+    pub fn new(config: &Config, directory: &Path, no_backup: bool, backup_dir: Option<&Path>) -> Self {
+        Self {
+            backup: backup_plan(config, no_backup, backup_dir),
+            freeze: config.freeze_live_cookies,
+            normalize_line_endings: config.normalize_line_endings,
+            live_file: config.live_cookie_file(directory),
+        }
+    }
+}
+
+pub enum SwitchOutcome {
+    AlreadyActive,
+    // A backup directory that couldn't be used is reported but doesn't stop the switch
+    Switched { backup_warning: Option<String> },
+}
+
+pub struct TrashEntry {
+    pub file_name: String,
+    pub original_file: String,
+    pub profile_name: String,
+    pub deleted_at: u64,
+}
+
+// Outcome of the optional session check after auto-launching Sober
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LoginResult {
+    pub ok: bool,
+    // Username on success, the reason otherwise
+    pub detail: String,
+    pub checked_at: u64,
+}
+
+// Per-profile settings, keyed by profile name in config.json
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ProfileConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub favorite: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    // Switching away from a locked active profile asks for confirmation
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    // Drawn as a stripe across the top of the avatar
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_tag: Option<[u8; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_login: Option<LoginResult>,
+    // Cached so each profile is only looked up once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    // Unix time after which the profile is flagged for attention
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remind_at: Option<u64>,
+    // In-window chord that switches to the profile, e.g. "Ctrl+Shift+1"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hotkey: Option<String>,
+    // sha256 of the cookie file as last written or accepted here
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    // Framing of the avatar image; None is a plain centre crop
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop: Option<AvatarCrop>,
+    // Short label drawn on the avatar when names are overlaid; empty uses the initials
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub nickname: String,
+    // Unix time the profile was first seen in a scan; unlike the file's mtime it survives session rotation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<u64>,
+    // Places offered in the profile's menu; picking one switches and launches straight into it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub games: Vec<GameShortcut>,
+    // Pinned avatar emoji; empty takes one from the palette by position
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub emoji: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct GameShortcut {
+    pub label: String,
+    pub place_id: u64,
+}
+
+impl GameShortcut {
+    // Handed to Sober so it joins the place rather than opening the home page
+    pub fn launch_url(&self) -> String {
+        format!("roblox://experiences/start?placeId={}", self.place_id)
+    }
+}
+
+// "Label = place; ..." where each place is an ID or a roblox.com/games/<id> link
+pub fn parse_game_shortcuts(input: &str) -> Result<Vec<GameShortcut>, &'static str> {
+    let mut shortcuts = Vec::new();
+    for entry in input.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (label, place) = entry.split_once('=').ok_or("Write each game as Label = place ID or game link")?;
+        let label = label.trim();
+        if label.is_empty() {
+            return Err("Every game needs a label");
+        }
+        let place_id = parse_place_id(place.trim()).ok_or("Use a numeric place ID or a roblox.com/games/… link")?;
+        shortcuts.push(GameShortcut { label: label.to_string(), place_id });
+    }
+    Ok(shortcuts)
+}
+
+pub fn format_game_shortcuts(shortcuts: &[GameShortcut]) -> String {
+    shortcuts.iter().map(|game| format!("{} = {}", game.label, game.place_id)).collect::<Vec<_>>().join("; ")
+}
+
+fn parse_place_id(place: &str) -> Option<u64> {
+    let id = match place.split_once("/games/") {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+        None => place,
+    };
+    id.parse().ok().filter(|&id| id > 0)
+}
+
+pub const MAX_AVATAR_ZOOM: f32 = 4.0;
+
+// Zoom of at least 1 into the largest centred square, moved by offsets in -1..=1 per axis
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct AvatarCrop {
+    pub zoom: f32,
+    pub offset: [f32; 2],
+}
+
+impl Default for AvatarCrop {
+    fn default() -> Self {
+        Self { zoom: 1.0, offset: [0.0, 0.0] }
+    }
+}
+
+impl AvatarCrop {
+    // The [min, max] texture coordinates to draw so an image of this size fills a square
+    pub fn uv_rect(&self, width: f32, height: f32) -> [[f32; 2]; 2] {
+        let (width, height) = (width.max(1.0), height.max(1.0));
+        let side = width.min(height) / self.zoom.clamp(1.0, MAX_AVATAR_ZOOM);
+        let span = [side / width, side / height];
+        
+        let mut uv = [[0.0; 2]; 2];
+        for axis in 0..2 {
+            // An offset of ±1 moves the crop right up to that edge of the image
+            let centre = 0.5 + self.offset[axis].clamp(-1.0, 1.0) * (1.0 - span[axis]) / 2.0;
+            uv[0][axis] = centre - span[axis] / 2.0;
+            uv[1][axis] = centre + span[axis] / 2.0;
+        }
+        uv
+    }
+}
+
+// Bump when the config layout changes and add a step to Config::migrate
+const CONFIG_VERSION: u32 = 2;
+
+// Where profile names go: under each avatar, or as a short nickname over it
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NamePlacement {
+    #[default]
+    Below,
+    OverlayTop,
+    OverlayBottom,
+}
+
+// What happens to a name too long for the space under its avatar
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NameOverflow {
+    #[default]
+    Ellipsis,
+    // Up to two lines, the second still cut off with an ellipsis
+    Wrap,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundStyle {
+    #[default]
+    Solid,
+    Gradient,
+    Image,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GridAlignment {
+    #[default]
+    Center,
+    Left,
+}
+
+// Favorites still come first in either order
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    #[default]
+    Name,
+    NewestAdded,
+}
+
+// What clicking a profile in the grid does
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchMode {
+    #[default]
+    Immediate,
+    // The first click selects, a second click on the selected profile switches
+    DoubleClick,
+    Confirm,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub background: BackgroundStyle,
+    pub gradient_top: [u8; 3],
+    pub gradient_bottom: [u8; 3],
+    // Darkens gradient/image backgrounds so text and avatars stay readable
+    pub background_dim: f32,
+    // Window and title bar fill, also the base of the widget visuals
+    pub panel: [u8; 3],
+    // Selection, hover and highlight color
+    pub accent: [u8; 3],
+    // Title bar logo; empty looks for Sober_logo.png in the usual places
+    pub logo_path: String,
+    pub name_placement: NamePlacement,
+    pub name_overflow: NameOverflow,
+    // Dark rounded backdrop behind overlaid nicknames
+    pub name_pill: bool,
+    // Corner radius as a fraction of the avatar size, 0.5 being a circle
+    pub avatar_rounding: f32,
+    pub avatar_border_width: f32,
+    // Faint label with the profile's hotkey in an avatar corner
+    pub hotkey_hints: bool,
+}
+
+pub const MAX_AVATAR_BORDER_WIDTH: f32 = 6.0;
+
+pub const MAX_POLL_INTERVAL_SECS: u32 = 60;
+
+pub const DEFAULT_EMOJI_PALETTE: &str = "🦆 🐱 🐶 🐸 🐨 🦊 🐰 🐼 🦁 🐯";
+const FALLBACK_EMOJI: &str = "👤";
+
+// Unpinned emojis go by position, so they move whenever a profile is added or removed
+pub fn palette_emoji(palette: &[&str], index: usize) -> String {
+    match palette.len() {
+        0 => FALLBACK_EMOJI.to_string(),
+        len => palette[index % len].to_string(),
+    }
+}
+
+// A random emoji for each of `count` profiles, all different while the palette has enough
+pub fn shuffled_emojis(palette: &[&str], count: usize) -> Vec<String> {
+    if palette.is_empty() {
+        return vec![FALLBACK_EMOJI.to_string(); count];
+    }
+    let mut emojis = Vec::with_capacity(count);
+    while emojis.len() < count {
+        let mut round = palette.to_vec();
+        for i in (1..round.len()).rev() {
+            round.swap(i, OsRng.next_u32() as usize % (i + 1));
+        }
+        emojis.extend(round.into_iter().map(str::to_string));
+    }
+    emojis.truncate(count);
+    emojis
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            background: BackgroundStyle::Solid,
+            gradient_top: [45, 70, 100],
+            gradient_bottom: [20, 28, 40],
+            background_dim: 0.3,
+            panel: [32, 47, 64],
+            accent: [70, 120, 180],
+            logo_path: String::new(),
+            name_placement: NamePlacement::Below,
+            name_overflow: NameOverflow::Ellipsis,
+            name_pill: true,
+            avatar_rounding: 0.1,
+            avatar_border_width: 2.0,
+            hotkey_hints: false,
+        }
+    }
+}
+
+// Missing fields fall back to Config::default(), so new settings don't need a migration step
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub version: u32,
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    pub theme: ThemeConfig,
+    // Left-aligned grids fill each row as far as the window allows
+    pub grid_alignment: GridAlignment,
+    pub sort_mode: SortMode,
+    // Space-separated emojis handed out to profiles without a pinned one
+    pub emoji_palette: String,
+    // Show the profile dropdown under the grid even when the grid fits
+    pub profile_dropdown: bool,
+    // Move the window ourselves while dragging, for compositors that ignore StartDrag
+    pub manual_window_drag: bool,
+    // Deleted profiles older than this are purged from the trash
+    pub trash_retention_days: u32,
+    pub switch_mode: SwitchMode,
+    // Leave the live cookies file read-only after a switch so nothing rotates the session
+    pub freeze_live_cookies: bool,
+    // Store newly created profiles as cookies_<name>.txt.gz
+    pub compress_new_profiles: bool,
+    // Convert CRLF to LF in the live cookies file when switching
+    pub normalize_line_endings: bool,
+    // Copy the live cookies file to <live file>.bak before each switch; an empty
+    // backup_dir keeps the backup in the cookie directory
+    pub backup_before_switch: bool,
+    pub backup_dir: String,
+    // Other files that get a copy of the live cookies on every switch, one path each
+    pub mirror_targets: Vec<String>,
+    // The live file name each cookie directory's client reads, detected or chosen per directory
+    pub cookie_targets: BTreeMap<PathBuf, String>,
+    // Start Sober after every successful switch
+    pub auto_launch: bool,
+    pub launch_command: String,
+    // Ask Roblox whether the session actually logged in shortly after launching
+    pub check_login_after_launch: bool,
+    // Ask Roblox whose session the live file holds right after each switch
+    pub confirm_identity_after_switch: bool,
+    // Feedback for switches made while the window is in the background
+    pub notify_on_switch: bool,
+    pub sound_on_switch: bool,
+    // Refuse to switch while Sober runs, since it only reads the cookie file on startup
+    pub require_sober_closed: bool,
+    // Warn on launch when the live cookies file has no usable token, optionally asking Roblox too
+    pub check_session_on_startup: bool,
+    pub check_session_online: bool,
+    // System-wide chord that pops up the quick switch overlay
+    pub quick_switch_hotkey_enabled: bool,
+    pub quick_switch_hotkey: String,
+    // How often config.json and the live cookies file are checked for changes made outside the app
+    pub poll_interval_secs: u32,
+    // Set once the first-run welcome screen has been dismissed
+    pub onboarding_complete: bool,
+    // Look up each valid profile's Roblox username during Verify All
+    pub fetch_usernames: bool,
+    // Coloured initials for profiles without an image, optionally saved as <name>.png
+    pub initials_avatars: bool,
+    pub save_initials_avatars: bool,
+    // Unlocks features that expose session tokens
+    pub developer_mode: bool,
+    // Keep the cookie directory as it is until unpinned
+    pub directory_pinned: bool,
+    // Browse without writing anything: no switches, edits or settings changes
+    pub read_only: bool,
+    // Offer to lock every other profile away after switching to shared_profile
+    pub shared_machine: bool,
+    pub shared_profile: String,
+    // Thin always-on-top strip of avatars instead of the full window
+    pub mini_mode: bool,
+    // Keep the full window above other windows too
+    pub pin_on_top: bool,
+    // Show a tray icon and make ✕ hide the window to it instead of quitting
+    pub close_to_tray: bool,
+    // Last geometry of each mode, restored when switching between them
+    pub full_window: Option<WindowGeometry>,
+    pub mini_window: Option<WindowGeometry>,
+    pub switch_history: Vec<SwitchRecord>,
+    // The profiles as they were when the window last closed, compared against on startup
+    pub last_snapshot: Option<DirectorySnapshot>,
+    // Initials avatars the manager saved itself, with their checksums so a replaced image is kept
+    pub generated_avatars: BTreeMap<PathBuf, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct DirectorySnapshot {
+    pub directory: PathBuf,
+    // Profile name to the sha256 of its cookie file
+    pub files: BTreeMap<String, String>,
+}
+
+#[derive(Default, PartialEq, Debug)]
+pub struct SnapshotDiff {
+    pub updated: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl DirectorySnapshot {
+    pub fn capture(directory: &Path, live_file: &str) -> Self {
+        let files = scan_cookie_files(directory, live_file)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|file| Some((file.profile_name, file_checksum(&directory.join(&file.file_name)).ok()?)))
+            .collect();
+        Self { directory: directory.to_path_buf(), files }
+    }
+    
+    pub fn diff(&self, current: &DirectorySnapshot) -> SnapshotDiff {
+        let mut diff = SnapshotDiff::default();
+        for (name, checksum) in &current.files {
+            match self.files.get(name) {
+                Some(previous) if previous != checksum => diff.updated += 1,
+                Some(_) => {}
+                None => diff.added += 1,
+            }
+        }
+        diff.removed = self.files.keys().filter(|name| !current.files.contains_key(*name)).count();
+        diff
+    }
+}
+
+impl SnapshotDiff {
+    // e.g. "2 profiles updated, 1 added, 1 removed"; None when nothing changed
+    pub fn summary(&self) -> Option<String> {
+        let parts: Vec<String> = [(self.updated, "updated"), (self.added, "added"), (self.removed, "removed")]
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .enumerate()
+            .map(|(i, (count, what))| match (i, count) {
+                (0, 1) => format!("1 profile {}", what),
+                (0, _) => format!("{} profiles {}", count, what),
+                _ => format!("{} {}", count, what),
+            })
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+pub const IDLE_FRAME_WINDOW_SECS: f64 = 10.0;
+
+// Frames painted with nothing to show for them, counted over fixed windows, so anything that
+// keeps requesting repaints shows up as a number instead of a busy core
+#[derive(Default)]
+pub struct IdleFrameCounter {
+    window_start: f64,
+    current: u32,
+    pub last_window: Option<u32>,
+}
+
+impl IdleFrameCounter {
+    // `time` is egui's input time in seconds; returns the count of a window once it has closed
+    pub fn record(&mut self, time: f64, idle: bool) -> Option<u32> {
+        let mut finished = None;
+        if time - self.window_start >= IDLE_FRAME_WINDOW_SECS {
+            finished = Some(self.current);
+            self.last_window = finished;
+            self.window_start = time;
+            self.current = 0;
+        }
+        if idle {
+            self.current += 1;
+        }
+        finished
+    }
+}
+
+// An idle window only wakes for the poll timer, plus the odd frame egui adds after one
+pub fn idle_frame_budget(poll_interval: Duration) -> u32 {
+    2 * (IDLE_FRAME_WINDOW_SECS / poll_interval.as_secs_f64()).ceil() as u32 + 2
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SwitchRecord {
+    pub profile: String,
+    pub switched_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct WindowGeometry {
+    pub pos: [f32; 2],
+    pub size: [f32; 2],
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            profiles: BTreeMap::new(),
+            theme: ThemeConfig::default(),
+            grid_alignment: GridAlignment::Center,
+            sort_mode: SortMode::Name,
+            emoji_palette: DEFAULT_EMOJI_PALETTE.to_string(),
+            profile_dropdown: false,
+            switch_mode: SwitchMode::Immediate,
+            manual_window_drag: false,
+            trash_retention_days: 30,
+            freeze_live_cookies: false,
+            compress_new_profiles: false,
+            normalize_line_endings: false,
+            backup_before_switch: true,
+            backup_dir: String::new(),
+            mirror_targets: Vec::new(),
+            cookie_targets: BTreeMap::new(),
+            auto_launch: false,
+            launch_command: "flatpak run org.vinegarhq.Sober".to_string(),
+            check_login_after_launch: false,
+            confirm_identity_after_switch: false,
+            notify_on_switch: false,
+            sound_on_switch: false,
+            require_sober_closed: false,
+            check_session_on_startup: false,
+            check_session_online: false,
+            quick_switch_hotkey_enabled: false,
+            quick_switch_hotkey: "ctrl+alt+s".to_string(),
+            poll_interval_secs: 2,
+            onboarding_complete: false,
+            fetch_usernames: false,
+            initials_avatars: false,
+            save_initials_avatars: false,
+            developer_mode: false,
+            directory_pinned: false,
+            read_only: false,
+            shared_machine: false,
+            shared_profile: String::new(),
+            mini_mode: false,
+            pin_on_top: false,
+            close_to_tray: false,
+            full_window: None,
+            mini_window: None,
+            switch_history: Vec::new(),
+            last_snapshot: None,
+            generated_avatars: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        get_config_dir().join(CONFIG_FILE)
+    }
+    
+    pub fn mirror_targets(&self) -> Vec<PathBuf> {
+        self.mirror_targets.iter().map(|target| target.trim()).filter(|target| !target.is_empty()).map(expand_path).collect()
+    }
+    
+    // The directory's own target if one was saved, otherwise whichever known live file it holds
+    pub fn live_cookie_file(&self, directory: &Path) -> String {
+        match self.cookie_targets.get(directory) {
+            Some(target) if !target.trim().is_empty() => target.trim().to_string(),
+            _ => detect_live_cookie_file(directory).unwrap_or(LIVE_COOKIE_FILE).to_string(),
+        }
+    }
+    
+    pub fn emoji_palette(&self) -> Vec<&str> {
+        self.emoji_palette.split_whitespace().collect()
+    }
+    
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_secs.clamp(1, MAX_POLL_INTERVAL_SECS) as u64)
+    }
+    
+    // A config that can't be read is moved to config.bak and replaced by defaults, with a warning;
+    // in read-only mode it is left where it is and a migrated config isn't written back
+    pub fn load(read_only: bool) -> (Self, Option<String>) {
+        let path = Self::path();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => return (Self::default(), None),
+        };
+        
+        match Self::parse(&contents) {
+            Ok((config, migrated)) => {
+                if migrated && !read_only && !config.read_only {
+                    if let Err(e) = config.save() {
+                        log::error!("Failed to save migrated config: {}", e);
+                    }
+                }
+                (config, None)
+            }
+            Err(e) if read_only => (Self::default(), Some(format!("Config is invalid ({}); using defaults", e))),
+            Err(e) => {
+                let backup_path = path.with_file_name(CONFIG_BACKUP_FILE);
+                let warning = match fs::rename(&path, &backup_path) {
+                    Ok(()) => format!(
+                        "Config was invalid ({}); moved it to {} and started from defaults",
+                        e,
+                        backup_path.display()
+                    ),
+                    Err(rename_err) => format!(
+                        "Config was invalid ({}) and could not be backed up: {}",
+                        e, rename_err
+                    ),
+                };
+                (Self::default(), Some(warning))
+            }
+        }
+    }
+    
+    // Returns the parsed config and whether it was upgraded from an older version
+    fn parse(contents: &str) -> Result<(Self, bool), String> {
+        let value: serde_json::Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+        if !value.is_object() {
+            return Err("expected a JSON object".to_string());
+        }
+        
+        let (value, migrated) = Self::migrate(value)?;
+        let mut config: Self = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        config.validate();
+        
+        Ok((config, migrated))
+    }
+    
+    fn migrate(mut value: serde_json::Value) -> Result<(serde_json::Value, bool), String> {
+        let mut version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+        
+        if version > CONFIG_VERSION {
+            return Err(format!(
+                "config version {} is newer than this build supports ({})",
+                version, CONFIG_VERSION
+            ));
+        }
+        
+        let migrated = version < CONFIG_VERSION;
+        while version < CONFIG_VERSION {
+            match version {
+                // Version 0 predates the version field; its layout is otherwise identical to version 1
+                0 => {}
+                // Version 2 added the first-run welcome screen, which existing users have no need for
+                1 => value["onboarding_complete"] = true.into(),
+                _ => {}
+            }
+            version += 1;
+        }
+        
+        value["version"] = version.into();
+        Ok((value, migrated))
+    }
+    
+    // Clean up hand-edited values that deserialize fine but make no sense
+    fn validate(&mut self) {
+        self.profiles.retain(|name, _| !name.trim().is_empty());
+        
+        for profile in self.profiles.values_mut() {
+            profile.aliases.retain(|alias| !alias.trim().is_empty());
+        }
+    }
+    
+    pub fn record_switch(&mut self, profile_name: &str) {
+        let switched_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        self.switch_history.insert(0, SwitchRecord { profile: profile_name.to_string(), switched_at });
+        self.switch_history.truncate(MAX_SWITCH_HISTORY);
+    }
+    
+    pub fn save(&self) -> Result<(), AppError> {
+        self.save_to(&Self::path())
+    }
+    
+    // Unix time of the profile's most recent switch in the kept history
+    pub fn last_used(&self, profile_name: &str) -> Option<u64> {
+        self.switch_history.iter().find(|record| record.profile == profile_name).map(|record| record.switched_at)
+    }
+    
+    fn save_to(&self, path: &Path) -> Result<(), AppError> {
+        serde_json::to_string_pretty(self)
+            .map_err(io::Error::other)
+            .and_then(|contents| write_config_file(path, &contents))
+            .map_err(|error| AppError::ConfigWrite { path: path.to_path_buf(), error })
+    }
+    
+    pub fn modified() -> Option<SystemTime> {
+        fs::metadata(Self::path()).and_then(|m| m.modified()).ok()
+    }
+
+}
+
+// profiles.json in the config dir, rewritten whenever profiles are loaded or switched so other tools
+// can poll it; fields are only ever added, anything else bumps the version
+#[derive(Serialize)]
+pub struct ProfilesListing {
+    pub version: u32,
+    pub directory: PathBuf,
+    pub profiles: Vec<ListedProfile>,
+}
+
+#[derive(Serialize)]
+pub struct ListedProfile {
+    pub name: String,
+    // File name inside the directory, e.g. cookies_main.txt
+    pub file: String,
+    pub display_name: String,
+    pub active: bool,
+    // Unix time of the last switch to it, if still in the history
+    pub last_used: Option<u64>,
+}
+
+impl ProfilesListing {
+    pub fn new(directory: &Path, profiles: Vec<ListedProfile>) -> Self {
+        Self { version: PROFILES_LISTING_VERSION, directory: directory.to_path_buf(), profiles }
+    }
+    
+    pub fn write(&self) -> io::Result<()> {
+        self.write_to(&get_config_dir().join(PROFILES_LISTING_FILE))
+    }
+    
+    fn write_to(&self, path: &Path) -> io::Result<()> {
+        write_config_file(path, &serde_json::to_string_pretty(self).map_err(io::Error::other)?)
+    }
+}
+
+pub const DIRECTORY_CONFIG_FILE: &str = ".sober-profiles.json";
+
+// Optional profile metadata stored inside a cookie directory, overriding the global config's profiles
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DirectoryConfig {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+impl DirectoryConfig {
+    pub fn new(profiles: BTreeMap<String, ProfileConfig>) -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            profiles,
+        }
+    }
+    
+    // Ok(None) when the directory has no file of its own
+    pub fn load(directory: &Path) -> Result<Option<Self>, String> {
+        let path = directory.join(DIRECTORY_CONFIG_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Could not read {}: {}", path.display(), e)),
+        };
+        
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("Ignoring {}: {}", path.display(), e))
+    }
+    
+    pub fn save(&self, directory: &Path) -> Result<(), AppError> {
+        let path = directory.join(DIRECTORY_CONFIG_FILE);
+        serde_json::to_string_pretty(self)
+            .map_err(io::Error::other)
+            .and_then(|contents| write_config_file(&path, &contents))
+            .map_err(|error| AppError::ConfigWrite { path, error })
+    }
+}
+
+// Held for every write of the manager's own files, so concurrent saves can't interleave
+static CONFIG_WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+// Write through a temporary file and rename it over the target, so readers (and a crash
+// halfway through) only ever see the old contents or the new ones
+fn write_config_file(path: &Path, contents: &str) -> io::Result<()> {
+    let _guard = CONFIG_WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(temp_name);
+    
+    let result = fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        fs::remove_file(&temp).ok();
+    }
+    result
+}
+
+// The most recent saved directory that still exists, else the default; when every saved
+// directory is gone but the default works, directory.txt is repaired and a notice returned
+pub fn load_saved_directory() -> (PathBuf, Option<String>) {
+    let history = load_directory_history();
+    if let Some(path) = history
+        .iter()
+        .map(|entry| expand_path(&entry.path))
+        .find(|path| path.exists())
+    {
+        return (path, None);
+    }
+    
+    let default = default_cookie_directory();
+    let Some(stale) = history.first() else {
+        return (default, None);
+    };
+    if !default.is_dir() {
+        return (default, None);
+    }
+    
+    let notice = format!(
+        "Saved directory {} no longer exists; switched to {} and updated directory.txt",
+        stale.path,
+        default.display()
+    );
+    record_directory(&default);
+    (default, Some(notice))
+}
+
+// Sober's data directory: the one its own config.json names, else its flatpak sandbox's
+// XDG_DATA_HOME when a flatpak override moves it, else the usual flatpak location
+pub fn default_cookie_directory() -> PathBuf {
+    let configured = fs::read_to_string(expand_path(SOBER_CONFIG_FILE)).ok().and_then(|contents| sober_config_data_directory(&contents));
+    if let Some(directory) = configured.filter(|directory| directory.is_dir()) {
+        return directory;
+    }
+    
+    let roots: Vec<PathBuf> = [dirs::data_dir().map(|dir| dir.join("flatpak")), Some(PathBuf::from("/var/lib/flatpak"))]
+        .into_iter()
+        .flatten()
+        .collect();
+    
+    // Sober's own overrides beat global ones, and the user's beat the system's
+    [SOBER_APP_ID, "global"]
+        .iter()
+        .flat_map(|name| roots.iter().map(move |root| root.join("overrides").join(name)))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .find_map(|contents| flatpak_data_home(&contents))
+        .map(|data_home| data_home.join("sober"))
+        .filter(|directory| directory.is_dir())
+        .unwrap_or_else(|| expand_path(DEFAULT_COOKIE_DIRECTORY))
+}
+
+// A data directory set in Sober's config.json, under any of the keys it might be stored as
+fn sober_config_data_directory(contents: &str) -> Option<PathBuf> {
+    let config: serde_json::Value = serde_json::from_str(contents).ok()?;
+    SOBER_CONFIG_DATA_KEYS
+        .iter()
+        .filter_map(|key| config.get(key)?.as_str())
+        .map(str::trim)
+        .find(|value| !value.is_empty())
+        .map(expand_path)
+}
+
+// XDG_DATA_HOME from the [Environment] group of a flatpak override keyfile
+fn flatpak_data_home(contents: &str) -> Option<PathBuf> {
+    let mut in_environment = false;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_environment = line == "[Environment]";
+        } else if let Some(value) = line.strip_prefix("XDG_DATA_HOME=").filter(|_| in_environment) {
+            return Some(expand_path(value.trim())).filter(|_| !value.trim().is_empty());
+        }
+    }
+    None
+}
+
+// Where a running window listens for forwarded links
+pub fn instance_socket_path() -> PathBuf {
+    dirs::runtime_dir().unwrap_or_else(get_config_dir).join("sober-cookie-manager.sock")
+}
+
+// Set once at startup in portable mode, before anything reads the config dir
+static PORTABLE_CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub const PORTABLE_MARKER_FILE: &str = "portable.txt";
+
+// --portable, or a portable.txt next to the executable, keeps everything in a folder beside it
+pub fn portable_config_dir(executable: &Path, requested: bool) -> Option<PathBuf> {
+    let beside = executable.parent()?;
+    (requested || beside.join(PORTABLE_MARKER_FILE).is_file()).then(|| beside.join(CONFIG_DIR_NAME))
+}
+
+pub fn enable_portable_mode(config_dir: PathBuf) {
+    PORTABLE_CONFIG_DIR.set(config_dir).ok();
+}
+
+pub fn is_portable() -> bool {
+    PORTABLE_CONFIG_DIR.get().is_some()
+}
+
+pub fn get_config_dir() -> PathBuf {
+    if let Some(config_dir) = PORTABLE_CONFIG_DIR.get() {
+        fs::create_dir_all(config_dir).ok();
+        return config_dir.clone();
+    }
+    let mut config_dir = dirs::config_dir().unwrap_or_else(|| {
+        env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+    });
+    config_dir.push(CONFIG_DIR_NAME);
+    fs::create_dir_all(&config_dir).ok();
+    config_dir
+}
+
+fn get_config_file_path() -> PathBuf {
+    get_config_dir().join(DIRECTORY_HISTORY_FILE)
+}
+
+// Older versions stored a single bare path, which reads as a one-entry history
+pub fn load_directory_history() -> Vec<RecentDirectory> {
+    let Ok(contents) = fs::read_to_string(get_config_file_path()) else {
+        return Vec::new();
+    };
+    
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let entry = line
+                .split_once('\t')
+                .and_then(|(used_at, path)| Some((used_at.parse().ok()?, path)));
+            match entry {
+                Some((used_at, path)) => RecentDirectory { path: path.to_string(), used_at },
+                None => RecentDirectory { path: line.to_string(), used_at: 0 },
+            }
+        })
+        .collect()
+}
+
+pub const LOGO_FILE_NAME: &str = "Sober_logo.png";
+
+// Where to look for the logo, in order: the configured file, next to the executable, the config
+// dir, then the working directory (as when run with cargo run)
+pub fn logo_candidates(configured: &str) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if !configured.trim().is_empty() {
+        candidates.push(expand_path(configured.trim()));
+    }
+    if let Some(executable_dir) = env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        candidates.push(executable_dir.join(LOGO_FILE_NAME));
+    }
+    candidates.push(get_config_dir().join(LOGO_FILE_NAME));
+    candidates.push(PathBuf::from(LOGO_FILE_NAME));
+    candidates
+}
+
+// Expand a leading ~/ and resolve relative paths against the working directory
+pub fn expand_path(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home_dir) = dirs::home_dir() {
+            return home_dir.join(rest);
+        }
+    }
+    
+    let path = PathBuf::from(path);
+    if path.is_relative() {
+        if let Ok(current_dir) = env::current_dir() {
+            return current_dir.join(path);
+        }
+    }
+    path
+}
+
+// Flatpak apps keep their data under ~/.var/app/<app id>/
+pub fn is_flatpak_path(path: &Path) -> bool {
+    let components: Vec<_> = path.components().map(|c| c.as_os_str()).collect();
+    components.windows(2).any(|pair| pair[0] == ".var" && pair[1] == "app")
+}
+
+// Per-directory profile metadata wins over the global config when present
+pub fn profile_configs<'a>(config: &'a Config, directory_config: Option<&'a DirectoryConfig>) -> &'a BTreeMap<String, ProfileConfig> {
+    match directory_config {
+        Some(directory_config) => &directory_config.profiles,
+        None => &config.profiles,
+    }
+}
+
+pub fn record_directory(directory: &Path) -> Vec<RecentDirectory> {
+    let current = directory.to_string_lossy().to_string();
+    let used_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    
+    let mut history = load_directory_history();
+    history.retain(|entry| expand_path(&entry.path) != directory);
+    history.insert(0, RecentDirectory { path: current, used_at });
+    history.truncate(MAX_RECENT_DIRECTORIES);
+    
+    let contents: String = history
+        .iter()
+        .map(|entry| format!("{}\t{}\n", entry.used_at, entry.path))
+        .collect();
+    if let Err(e) = write_config_file(&get_config_file_path(), &contents) {
+        log::error!("Failed to save directory preference: {}", e);
+    }
+    history
+}
+
+// Extract the profile name from "cookies_<name>" or "cookies_<name>.txt", ignoring case in the
+// prefix and suffix; callers keep the original file name for file operations
+pub fn profile_name_from_file(file_name: &str) -> Option<String> {
+    if is_operational_file(file_name) || is_manager_file(file_name) {
+        return None;
+    }
+    
+    // A compressed profile is named like any other with .gz on the end
+    let file_name = if is_compressed(file_name) {
+        &file_name[..file_name.len() - ".gz".len()]
+    } else {
+        file_name
+    };
+    let lower = file_name.to_ascii_lowercase();
+    if !lower.starts_with("cookies_") {
+        return None;
+    }
+    
+    let rest = &file_name["cookies_".len()..];
+    if lower.ends_with(".txt") {
+        Some(rest[..rest.len() - ".txt".len()].to_string())
+    } else if !rest.contains('.') {
+        Some(rest.to_string())
+    } else {
+        None
+    }
+}
+
+// The live file, its backups and temporary copies; never profiles, whatever the naming rules allow
+fn is_operational_file(file_name: &str) -> bool {
+    let lower = file_name.to_ascii_lowercase();
+    let live = KNOWN_LIVE_COOKIE_FILES.iter().any(|name| lower == *name || lower == format!("{name}.tmp"));
+    live || is_live_backup(&lower)
+}
+
+// A backup is named after the live file it copies, e.g. cookies.bak or cookies.txt.bak
+fn is_live_backup(lower: &str) -> bool {
+    KNOWN_LIVE_COOKIE_FILES.iter().any(|name| lower.starts_with(&format!("{name}.bak")))
+}
+
+// The first known live file name that exists in the directory
+pub fn detect_live_cookie_file(directory: &Path) -> Option<&'static str> {
+    KNOWN_LIVE_COOKIE_FILES.into_iter().find(|name| directory.join(name).is_file())
+}
+
+// Config, history, marker, log and socket files and their temporary copies, wherever they are
+fn is_manager_file(file_name: &str) -> bool {
+    let lower = file_name.to_ascii_lowercase();
+    let own = [
+        CONFIG_FILE,
+        CONFIG_BACKUP_FILE,
+        DIRECTORY_HISTORY_FILE,
+        PROFILES_LISTING_FILE,
+        DIRECTORY_CONFIG_FILE,
+        ACTIVE_PROFILE_MARKER,
+        VAULT_FILE,
+    ];
+    own.iter().any(|name| lower == *name || (lower.starts_with(&format!("{}.", name)) && lower.ends_with(".tmp")))
+        || lower.starts_with("sober-cookie-manager.")
+}
+
+// The cookie directory is, contains or sits inside the config dir, so the two sets of files mix
+pub fn overlaps_config_dir(directory: &Path, config_dir: &Path) -> bool {
+    let directory = directory.canonicalize().unwrap_or_else(|_| directory.to_path_buf());
+    let config_dir = config_dir.canonicalize().unwrap_or_else(|_| config_dir.to_path_buf());
+    directory.starts_with(&config_dir) || config_dir.starts_with(&directory)
+}
+
+// Scan a directory for cookies_* files, sorted by profile name; the directory's live file and its
+// temporary copy are never profiles, even when the target is named like one
+pub fn scan_cookie_files(directory: &Path, live_file: &str) -> io::Result<Vec<CookieFile>> {
+    let mut cookie_files = Vec::new();
+    let live_temp = format!("{live_file}.tmp");
+    
+    // Names that aren't UTF-8 get a lossy profile name, but keep their real file name
+    for entry in fs::read_dir(directory)?.flatten() {
+        let file_name = entry.file_name();
+        let lossy = file_name.to_string_lossy();
+        if lossy == live_file || lossy == live_temp {
+            continue;
+        }
+        if let Some(profile_name) = profile_name_from_file(&lossy) {
+            cookie_files.push(CookieFile {
+                profile_name,
+                file_name,
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            });
+        }
+    }
+    
+    // Sort alphabetically, ignoring case
+    cookie_files.sort_by_key(|file| file.profile_name.to_lowercase());
+    
+    Ok(cookie_files)
+}
+
+// Sober's client runs as `sober` inside the flatpak; the launcher is `flatpak run org.vinegarhq.Sober`
+pub fn sober_running() -> bool {
+    let mut system = sysinfo::System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cmd(UpdateKind::OnlyIfNotSet),
+    );
+    let own = sysinfo::get_current_pid().ok();
+    system
+        .processes()
+        .iter()
+        .any(|(pid, process)| Some(*pid) != own && is_sober_process(process.name(), process.cmd()))
+}
+
+fn is_sober_process(name: &OsStr, cmd: &[OsString]) -> bool {
+    name.eq_ignore_ascii_case("sober") || cmd.iter().any(|arg| arg == SOBER_APP_ID)
+}
+
+// "today", "yesterday" or "N days ago", counting whole days back from now
+pub fn days_ago(secs: u64, now: u64) -> String {
+    match now.saturating_sub(secs) / (24 * 60 * 60) {
+        0 => "today".to_string(),
+        1 => "yesterday".to_string(),
+        days => format!("{} days ago", days),
+    }
+}
+
+pub fn file_checksum(path: &Path) -> io::Result<String> {
+    Ok(to_hex(&Sha256::digest(fs::read(path)?)))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+pub fn profile_image_path(directory: &Path, profile_name: &str) -> PathBuf {
+    directory.join(format!("{}.png", profile_name.to_lowercase()))
+}
+
+// Side of a generated initials avatar
+const INITIALS_AVATAR_SIZE: u32 = 128;
+
+const INITIALS_COLORS: [[u8; 3]; 8] = [
+    [217, 87, 74],
+    [226, 140, 52],
+    [196, 160, 40],
+    [92, 160, 82],
+    [52, 150, 140],
+    [66, 120, 200],
+    [123, 96, 196],
+    [190, 84, 150],
+];
+
+// 5x7 bitmaps, one row per byte with the leftmost pixel in bit 4
+const INITIALS_GLYPHS: [(char, [u8; 7]); 37] = [
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]),
+];
+
+// Up to two letters, from the first two words of the name ("main_alt" is "MA")
+pub fn profile_initials(profile_name: &str) -> String {
+    let initials: String = profile_name
+        .split(['_', '-', ' ', '.'])
+        .filter_map(|word| word.chars().map(|c| c.to_ascii_uppercase()).find(|c| INITIALS_GLYPHS.iter().any(|(g, _)| g == c)))
+        .take(2)
+        .collect();
+    if initials.is_empty() {
+        "?".to_string()
+    } else {
+        initials
+    }
+}
+
+// White initials on a colour picked from the name, so a profile always gets the same one
+pub fn initials_avatar(profile_name: &str) -> image::RgbaImage {
+    let hash = profile_name.bytes().fold(0u32, |hash, b| hash.wrapping_mul(31).wrapping_add(b as u32));
+    let [r, g, b] = INITIALS_COLORS[hash as usize % INITIALS_COLORS.len()];
+    let mut avatar = image::RgbaImage::from_pixel(INITIALS_AVATAR_SIZE, INITIALS_AVATAR_SIZE, image::Rgba([r, g, b, 255]));
+    
+    let initials: Vec<[u8; 7]> = profile_initials(profile_name)
+        .chars()
+        .filter_map(|c| INITIALS_GLYPHS.iter().find(|(g, _)| *g == c).map(|(_, rows)| *rows))
+        .collect();
+    // Glyphs are 5 cells wide with one cell between them
+    let cells_wide = initials.len() as u32 * 6 - 1;
+    let scale = (INITIALS_AVATAR_SIZE * 3 / 5 / cells_wide).min(INITIALS_AVATAR_SIZE / 2 / 7);
+    let left = (INITIALS_AVATAR_SIZE - cells_wide * scale) / 2;
+    let top = (INITIALS_AVATAR_SIZE - 7 * scale) / 2;
+    
+    for (i, rows) in initials.iter().enumerate() {
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..5 {
+                if bits & (0b10000 >> column) == 0 {
+                    continue;
+                }
+                let x = left + (i as u32 * 6 + column) * scale;
+                let y = top + row as u32 * scale;
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        avatar.put_pixel(x + dx, y + dy, image::Rgba([255, 255, 255, 255]));
+                    }
+                }
+            }
+        }
+    }
+    avatar
+}
+
+// Keep the .txt and .gz suffixes (or lack of them) of an existing cookie file
+pub fn cookie_file_name(profile_name: &str, like: impl AsRef<OsStr>) -> String {
+    let like = like.as_ref().to_string_lossy().to_ascii_lowercase();
+    let gz = if is_compressed(&like) { ".gz" } else { "" };
+    if like.trim_end_matches(".gz").ends_with(".txt") {
+        format!("cookies_{}.txt{}", profile_name, gz)
+    } else {
+        format!("cookies_{}{}", profile_name, gz)
+    }
+}
+
+fn is_compressed(file_name: impl AsRef<OsStr>) -> bool {
+    file_name.as_ref().to_string_lossy().to_ascii_lowercase().ends_with(".gz")
+}
+
+// Contents of a cookie file, decompressed if it is a .gz profile
+pub fn read_cookie_file(path: &Path) -> io::Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    if !path.file_name().is_some_and(is_compressed) {
+        return Ok(bytes);
+    }
+    
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+pub fn read_cookie_text(path: &Path) -> io::Result<String> {
+    String::from_utf8(read_cookie_file(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_compressed(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut encoder = flate2::write::GzEncoder::new(fs::File::create(path)?, flate2::Compression::default());
+    encoder.write_all(contents)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+// Trashed cookie files, newest first
+pub fn list_trash(directory: &Path) -> Vec<TrashEntry> {
+    let Ok(entries) = fs::read_dir(directory.join(TRASH_DIR)) else {
+        return Vec::new();
+    };
+    
+    let mut trash: Vec<TrashEntry> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_str()?.to_string();
+            let (deleted_at, original_file) = file_name.split_once("__")?;
+            let profile_name = profile_name_from_file(original_file)?;
+            Some(TrashEntry {
+                deleted_at: deleted_at.parse().ok()?,
+                original_file: original_file.to_string(),
+                profile_name,
+                file_name,
+            })
+        })
+        .collect();
+    
+    trash.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+    trash
+}
+
+pub fn restore_from_trash(directory: &Path, entry: &TrashEntry) -> Result<(), AppError> {
+    let trash = directory.join(TRASH_DIR);
+    let target = directory.join(&entry.original_file);
+    if target.exists() {
+        return Err(AppError::AlreadyExists(target));
+    }
+    
+    fs::rename(trash.join(&entry.file_name), &target).map_err(AppError::io("restore", &target))?;
+    
+    let image_name = format!("{}.png", entry.profile_name.to_lowercase());
+    let trashed_image = trash.join(format!("{}__{}", entry.deleted_at, image_name));
+    if trashed_image.exists() && !directory.join(&image_name).exists() {
+        fs::rename(trashed_image, directory.join(&image_name)).ok();
+    }
+    
+    Ok(())
+}
+
+pub fn list_archived(directory: &Path) -> Vec<CookieFile> {
+    scan_cookie_files(&directory.join(ARCHIVE_DIR), LIVE_COOKIE_FILE).unwrap_or_default()
+}
+
+pub fn archive_profile(directory: &Path, cookie_file: &OsStr) -> Result<(), AppError> {
+    let archive = directory.join(ARCHIVE_DIR);
+    fs::create_dir_all(&archive).map_err(AppError::io("create", &archive))?;
+    let target = archive.join(cookie_file);
+    if target.exists() {
+        return Err(AppError::AlreadyExists(target));
+    }
+    let path = directory.join(cookie_file);
+    fs::rename(&path, &target).map_err(AppError::io("archive", &path))
+}
+
+// Refused while another profile has taken the name in the meantime
+pub fn unarchive_profile(directory: &Path, file: &CookieFile) -> Result<(), AppError> {
+    // Only Sober's own live file is left out, so a live target named like a profile keeps its name
+    let existing = scan_cookie_files(directory, LIVE_COOKIE_FILE).unwrap_or_default();
+    if existing.iter().any(|other| other.profile_name.eq_ignore_ascii_case(&file.profile_name)) {
+        return Err(AppError::NameTaken(file.profile_name.clone()));
+    }
+    let target = directory.join(&file.file_name);
+    if target.exists() {
+        return Err(AppError::AlreadyExists(target));
+    }
+    fs::rename(directory.join(ARCHIVE_DIR).join(&file.file_name), &target).map_err(AppError::io("unarchive", &target))
+}
+
+// Permanently remove trash entries older than the retention period, returning their profile names
+pub fn purge_trash(directory: &Path, retention_days: u32) -> Vec<String> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let max_age = u64::from(retention_days) * 24 * 60 * 60;
+    let trash = directory.join(TRASH_DIR);
+    
+    let mut purged = Vec::new();
+    for entry in list_trash(directory) {
+        if now.saturating_sub(entry.deleted_at) < max_age {
+            continue;
+        }
+        
+        if fs::remove_file(trash.join(&entry.file_name)).is_ok() {
+            let image = trash.join(format!("{}__{}.png", entry.deleted_at, entry.profile_name.to_lowercase()));
+            fs::remove_file(image).ok();
+            log::info!("Purged {} from the trash", entry.original_file);
+            purged.push(entry.profile_name);
+        }
+    }
+    purged
+}
+
+pub fn format_profile_name(name: &str) -> String {
+    // Convert snake_case or kebab-case to Title Case
+    name.replace(['_', '-'], " ")
+        .split_whitespace()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().chain(chars.as_str().to_lowercase().chars()).collect(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Extract the .ROBLOSECURITY value from a Netscape cookie jar, a "name=value" list or a bare token
+pub fn parse_roblosecurity(contents: &str) -> Option<String> {
+    for line in contents.lines() {
+        let line = line.trim();
+        
+        // Netscape format: domain, flag, path, secure, expiry, name, value
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() == 7 && fields[5] == ROBLOSECURITY {
+            return Some(fields[6].trim().to_string());
+        }
+        
+        for pair in line.split(';') {
+            if let Some((name, value)) = pair.split_once('=') {
+                if name.trim() == ROBLOSECURITY {
+                    return Some(value.trim().to_string());
+                }
+            }
+        }
+    }
+    
+    let trimmed = contents.trim();
+    if trimmed.starts_with("_|WARNING:") {
+        return Some(trimmed.to_string());
+    }
+    
+    None
+}
+
+// Check that some text holds a plausible .ROBLOSECURITY token, explaining why not otherwise
+pub fn validate_cookie(contents: &str) -> Result<String, String> {
+    if contents.trim().is_empty() {
+        return Err("it is empty".to_string());
+    }
+    
+    let token = parse_roblosecurity(contents)
+        .ok_or_else(|| format!("no {} value was found", ROBLOSECURITY))?;
+    
+    if token.is_empty() {
+        return Err(format!("the {} value is empty", ROBLOSECURITY));
+    }
+    if token.chars().any(char::is_whitespace) {
+        return Err("the token contains whitespace".to_string());
+    }
+    if token.len() < MIN_TOKEN_LENGTH {
+        return Err(format!("the token is only {} characters long", token.len()));
+    }
+    
+    Ok(token)
+}
+
+// How two cookie files relate; the same token means the same logged-in session
+#[derive(Debug, PartialEq)]
+pub enum CookieComparison {
+    Identical,
+    SameToken,
+    OnlyTokenDiffers,
+    Different,
+}
+
+impl CookieComparison {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Identical => "The files are identical",
+            Self::SameToken => "Same .ROBLOSECURITY token: these are the same logged-in account",
+            Self::OnlyTokenDiffers => "The files only differ in their .ROBLOSECURITY token",
+            Self::Different => "The files differ entirely",
+        }
+    }
+}
+
+pub fn compare_cookie_files(first: &Path, second: &Path) -> io::Result<CookieComparison> {
+    let first = read_cookie_file(first)?;
+    let second = read_cookie_file(second)?;
+    if first == second {
+        return Ok(CookieComparison::Identical);
+    }
+    
+    let first = String::from_utf8_lossy(&first);
+    let second = String::from_utf8_lossy(&second);
+    let (Some(first_token), Some(second_token)) = (parse_roblosecurity(&first), parse_roblosecurity(&second)) else {
+        return Ok(CookieComparison::Different);
+    };
+    if !first_token.is_empty() && first_token == second_token {
+        return Ok(CookieComparison::SameToken);
+    }
+    if first.replace(&first_token, "") == second.replace(&second_token, "") {
+        return Ok(CookieComparison::OnlyTokenDiffers);
+    }
+    Ok(CookieComparison::Different)
+}
+
+// Render a token as a Netscape cookie jar that Sober can read
+pub fn format_cookie_file(token: &str) -> String {
+    let expiry = SystemTime::now()
+        .checked_add(Duration::from_secs(365 * 24 * 60 * 60))
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    
+    format_cookie_jar(token, expiry)
+}
+
+fn format_cookie_jar(token: &str, expiry: u64) -> String {
+    format!(
+        "# Netscape HTTP Cookie File\n.roblox.com\tTRUE\t/\tTRUE\t{}\t{}\t{}\n",
+        expiry, ROBLOSECURITY, token
+    )
+}
+
+// One cookie as exported by the Cookie-Editor browser extension
+#[derive(Deserialize)]
+struct EditorCookie {
+    name: String,
+    value: String,
+    #[serde(default)]
+    domain: String,
+    #[serde(rename = "expirationDate", default)]
+    expiration_date: Option<f64>,
+}
+
+// A cookie jar holding the .ROBLOSECURITY cookie of a Cookie-Editor JSON export (an array of cookies)
+pub fn parse_cookie_editor_json(contents: &str) -> Result<String, String> {
+    let cookies: Vec<serde_json::Value> = serde_json::from_str(contents.trim())
+        .map_err(|e| format!("this is not a Cookie-Editor JSON export, which is an array of cookies ({})", e))?;
+    let cookie = cookies
+        .into_iter()
+        .filter_map(|value| serde_json::from_value::<EditorCookie>(value).ok())
+        .find(|cookie| cookie.name == ROBLOSECURITY)
+        .ok_or_else(|| format!("the export has no {} cookie; export it while on roblox.com", ROBLOSECURITY))?;
+    
+    let domain = cookie.domain.trim_start_matches('.');
+    if !domain.is_empty() && domain != "roblox.com" && !domain.ends_with(".roblox.com") {
+        return Err(format!("its {} cookie is for {}, not roblox.com", ROBLOSECURITY, cookie.domain));
+    }
+    let token = validate_cookie(&format!("{}={}", ROBLOSECURITY, cookie.value))
+        .map_err(|reason| format!("its {} cookie can't be used: {}", ROBLOSECURITY, reason))?;
+    
+    // Session cookies have no expiry of their own
+    Ok(match cookie.expiration_date {
+        Some(expiry) if expiry > 0.0 => format_cookie_jar(&token, expiry as u64),
+        _ => format_cookie_file(&token),
+    })
+}
+
+// Profile names end up in filenames, so keep them to a safe character set
+pub fn validate_new_profile_name(directory: &Path, name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("Profile name cannot be empty"));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(AppError::InvalidInput("Profile name may only contain letters, numbers, '_' and '-'"));
+    }
+    
+    // Only Sober's own live file is left out, so a live target named like a profile keeps its name
+    let existing = scan_cookie_files(directory, LIVE_COOKIE_FILE).unwrap_or_default();
+    if existing.iter().any(|file| file.profile_name.eq_ignore_ascii_case(name)) {
+        return Err(AppError::NameTaken(name.to_string()));
+    }
+    
+    Ok(name.to_string())
+}
+
+// A live target is joined onto the cookie directory and overwritten on every switch, so only a
+// bare file name that isn't a profile or one of the manager's own files is accepted
+pub fn validate_live_file_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("The live file name cannot be empty"));
+    }
+    if name.contains(['/', '\\']) || name == "." || name.contains("..") {
+        return Err(AppError::InvalidInput("The live file name must be a plain file name, without a path"));
+    }
+    if profile_name_from_file(name).is_some() {
+        return Err(AppError::InvalidInput("That name would be read as a profile, not the live file"));
+    }
+    if is_manager_file(name) {
+        return Err(AppError::InvalidInput("That name is one of the manager's own files"));
+    }
+    
+    Ok(name.to_string())
+}
+
+// The profile named by a sober-switch:// link; any page can open one, so anything odd is refused
+pub fn parse_switch_url(url: &str) -> Result<String, String> {
+    let rest = url
+        .trim()
+        .split_once(':')
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(URL_SCHEME))
+        .map(|(_, rest)| rest)
+        .ok_or_else(|| format!("not a {}:// link", URL_SCHEME))?;
+    let rest = rest.strip_prefix("//").unwrap_or(rest);
+    let rest = rest.split(['?', '#']).next().unwrap_or_default().trim_end_matches('/');
+    
+    let name = String::from_utf8(percent_decode(rest)?).map_err(|_| "the profile name is not valid UTF-8".to_string())?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("the link does not name a profile".to_string());
+    }
+    if name.chars().count() > MAX_LINK_PROFILE_LENGTH {
+        return Err("the profile name is too long".to_string());
+    }
+    if name.starts_with('.') || !name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ' ')) {
+        return Err("the profile name contains characters that are not allowed".to_string());
+    }
+    
+    Ok(name.to_string())
+}
+
+// sober-switch://<profile>?all asks for the profile in every directory that has it
+pub fn wants_broadcast(url: &str) -> bool {
+    url.split_once('?')
+        .map(|(_, query)| query.split(['&', '#']).any(|part| matches!(part, "all" | "all=1" | "all=true")))
+        .unwrap_or(false)
+}
+
+fn percent_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.chars();
+    while let Some(c) = iter.next() {
+        if c != '%' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        let high = iter.next().and_then(|c| c.to_digit(16));
+        let low = iter.next().and_then(|c| c.to_digit(16));
+        match (high, low) {
+            (Some(high), Some(low)) => bytes.push((high * 16 + low) as u8),
+            _ => return Err("the link has a malformed %-escape".to_string()),
+        }
+    }
+    Ok(bytes)
+}
+
+// Desktop entry that makes this executable the handler for sober-switch:// links
+pub fn url_handler_desktop_entry(executable: &Path) -> String {
+    // Quoted-argument escapes, with their backslashes escaped again as the spec requires
+    let mut quoted = String::new();
+    for c in executable.to_string_lossy().chars() {
+        match c {
+            '"' | '`' | '$' => {
+                quoted.push_str("\\\\");
+                quoted.push(c);
+            }
+            '\\' => quoted.push_str("\\\\\\\\"),
+            '%' => quoted.push_str("%%"),
+            c => quoted.push(c),
+        }
+    }
+    format!(
+        "[Desktop Entry]\nType=Application\nName=Sober profile switch\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+        quoted, URL_SCHEME
+    )
+}
+
+// Rename where possible, copying and removing when the two paths are on different filesystems
+pub fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = fs::copy(from, to) {
+                let _ = fs::remove_file(to);
+                return Err(e);
+            }
+            fs::remove_file(from)
+        }
+        result => result,
+    }
+}
+
+// Overwrite the live cookies file with a profile's cookie file
+fn switch_cookie_file(directory: &Path, cookie_file: impl AsRef<OsStr>, options: &SwitchOptions) -> Result<(), AppError> {
+    let cookie_file = cookie_file.as_ref();
+    let mut delays = COPY_RETRY_DELAYS.iter();
+    loop {
+        match try_switch_cookie_file(directory, cookie_file, options) {
+            Err(e) if e.is_transient() => match delays.next() {
+                Some(delay) => {
+                    log::warn!("Copying {} failed ({}), retrying in {:?}", cookie_file.to_string_lossy(), e, delay);
+                    thread::sleep(*delay);
+                }
+                None => return Err(e),
+            },
+            result => return result,
+        }
+    }
+}
+
+fn backup_plan(config: &Config, no_backup: bool, backup_dir: Option<&Path>) -> BackupPlan {
+    if no_backup || !config.backup_before_switch {
+        return BackupPlan::Skip;
+    }
+    
+    match backup_dir {
+        Some(dir) => BackupPlan::To(dir.to_path_buf()),
+        None if config.backup_dir.trim().is_empty() => BackupPlan::InDirectory,
+        None => BackupPlan::To(expand_path(config.backup_dir.trim())),
+    }
+}
+
+// Copy the live cookies file to <live file>.bak; a backup directory that can't be written falls
+// back to the cookie directory, and the reason is returned as a warning
+fn backup_live_cookies(directory: &Path, live_file: &str, plan: &BackupPlan) -> Result<Option<String>, AppError> {
+    let live = directory.join(live_file);
+    if matches!(plan, BackupPlan::Skip) || !live.exists() {
+        return Ok(None);
+    }
+    
+    let mut warning = None;
+    let backup_dir = match plan {
+        BackupPlan::To(dir) => match ensure_writable_dir(dir) {
+            Ok(()) => dir.clone(),
+            Err(e) => {
+                warning = Some(format!(
+                    "Backup directory {} is not writable ({}), backed up to {} instead",
+                    dir.display(),
+                    e,
+                    directory.display()
+                ));
+                directory.to_path_buf()
+            }
+        },
+        _ => directory.to_path_buf(),
+    };
+    
+    // The copy inherits a frozen live file's read-only bit
+    let backup = backup_dir.join(format!("{live_file}.bak"));
+    let result = if backup.exists() { set_read_only(&backup, false) } else { Ok(()) };
+    result
+        .and_then(|()| fs::copy(&live, &backup))
+        .map_err(|error| AppError::BackupFailed { target: backup, error })?;
+    
+    Ok(warning)
+}
+
+fn ensure_writable_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let probe = dir.join(".sober-write-test");
+    fs::write(&probe, b"")?;
+    fs::remove_file(&probe)
+}
+
+fn try_switch_cookie_file(directory: &Path, cookie_file: &OsStr, options: &SwitchOptions) -> Result<(), AppError> {
+    let target = directory.join(&options.live_file);
+    let source = directory.join(cookie_file);
+    // Written beside the live file and renamed over it, so Sober never reads a half-copied file
+    let temp = directory.join(format!("{}.tmp", options.live_file));
+    
+    let copy = || -> io::Result<()> {
+        // Sober only reads the plain cookies file, so compressed profiles are unpacked into it
+        if options.normalize_line_endings {
+            fs::write(&temp, normalize_line_endings(&read_cookie_file(&source)?))?;
+        } else if is_compressed(cookie_file) {
+            fs::write(&temp, read_cookie_file(&source)?)?;
+        } else {
+            fs::copy(&source, &temp)?;
+        }
+        
+        // A frozen file from an earlier switch has to be writable again before it can be replaced
+        if target.exists() {
+            set_read_only(&target, false)?;
+        }
+        if let Err(e) = fs::rename(&temp, &target) {
+            fs::remove_file(&temp).ok();
+            return Err(e);
+        }
+        
+        if options.freeze {
+            set_read_only(&target, true)?;
+        }
+        Ok(())
+    };
+    copy().map_err(|error| AppError::CopyFailed { source: source.clone(), target: target.clone(), error })
+}
+
+// Put back a session held in memory, backed up and frozen like a switch; no profile file holds it,
+// so the active marker goes
+pub fn restore_live_session(directory: &Path, contents: &[u8], options: &SwitchOptions) -> Result<Option<String>, AppError> {
+    let backup_warning = backup_live_cookies(directory, &options.live_file, &options.backup)?;
+    let target = directory.join(&options.live_file);
+    let contents = if options.normalize_line_endings { normalize_line_endings(contents) } else { contents.to_vec() };
+    replace_file(&target, &contents, options.freeze).map_err(AppError::io("write", &target))?;
+    
+    fs::remove_file(directory.join(ACTIVE_PROFILE_MARKER)).ok();
+    Ok(backup_warning)
+}
+
+// Copy the live cookies to each extra target after a switch, e.g. another client sharing the
+// accounts; every target is tried, and the primary live file itself is skipped
+pub fn mirror_live_cookies(directory: &Path, live_file: &str, targets: &[PathBuf], freeze: bool) -> Vec<(PathBuf, Result<(), AppError>)> {
+    let live = directory.join(live_file);
+    let contents = fs::read(&live);
+    let canonical_live = live.canonicalize().unwrap_or_else(|_| live.clone());
+    
+    targets
+        .iter()
+        .filter(|target| target.canonicalize().map_or(true, |canonical| canonical != canonical_live))
+        .map(|target| {
+            let result = match &contents {
+                Ok(contents) => replace_file(target, contents, freeze).map_err(AppError::io("write", target)),
+                Err(e) => Err(AppError::Io { action: "read", path: live.clone(), error: io::Error::new(e.kind(), e.to_string()) }),
+            };
+            (target.clone(), result)
+        })
+        .collect()
+}
+
+// Write through a temp file beside the target, so nothing ever reads half a cookie file
+fn replace_file(target: &Path, contents: &[u8], freeze: bool) -> io::Result<()> {
+    let file_name = target.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+    let temp = target.with_file_name(format!("{}.tmp", file_name.to_string_lossy()));
+    
+    fs::write(&temp, contents)?;
+    if target.exists() {
+        set_read_only(target, false)?;
+    }
+    if let Err(e) = fs::rename(&temp, target) {
+        fs::remove_file(&temp).ok();
+        return Err(e);
+    }
+    if freeze {
+        set_read_only(target, true)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_read_only(path: &Path, read_only: bool) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    
+    let mode = if read_only { 0o444 } else { 0o644 };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_read_only(path: &Path, read_only: bool) -> io::Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(read_only);
+    fs::set_permissions(path, permissions)
+}
+
+// CRLF (and stray CR) line endings from files that passed through Windows, as LF
+fn normalize_line_endings(contents: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(contents.len());
+    let mut bytes = contents.iter().peekable();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'\r' {
+            if bytes.peek() != Some(&&b'\n') {
+                normalized.push(b'\n');
+            }
+        } else {
+            normalized.push(byte);
+        }
+    }
+    normalized
+}
+
+// Back up the live cookies file, make the profile's cookie file live and mark it active;
+// a failed backup is returned as AppError::BackupFailed before anything is overwritten
+fn switch_profile(directory: &Path, profile_name: &str, cookie_file: impl AsRef<OsStr>, options: &SwitchOptions) -> Result<SwitchOutcome, AppError> {
+    let cookie_file = cookie_file.as_ref();
+    // Nothing to write (or back up) when the profile is already live
+    if is_live(directory, &options.live_file, cookie_file) {
+        write_active_marker(directory, profile_name);
+        return Ok(SwitchOutcome::AlreadyActive);
+    }
+    
+    let backup_warning = backup_live_cookies(directory, &options.live_file, &options.backup)?;
+    switch_cookie_file(directory, cookie_file, options)?;
+    write_active_marker(directory, profile_name);
+    Ok(SwitchOutcome::Switched { backup_warning })
+}
+
+// The one switch path for every entry point: find the profile's file, refuse one without a usable
+// token, switch to it and check that the live file really holds it afterwards
+pub fn apply_profile_by_name(directory: &Path, profile_name: &str, options: &SwitchOptions) -> Result<SwitchOutcome, AppError> {
+    let cookie_file = scan_cookie_files(directory, &options.live_file)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => AppError::DirectoryNotFound(directory.to_path_buf()),
+            _ => AppError::Io { action: "read", path: directory.to_path_buf(), error: e },
+        })?
+        .into_iter()
+        .find(|file| file.profile_name == profile_name)
+        .ok_or_else(|| AppError::ProfileNotFound(profile_name.to_string()))?
+        .file_name;
+    
+    let path = directory.join(&cookie_file);
+    let contents = read_cookie_text(&path).map_err(AppError::io("read", &path))?;
+    validate_cookie(&contents).map_err(|reason| AppError::InvalidCookie { profile: profile_name.to_string(), reason })?;
+    
+    let outcome = switch_profile(directory, profile_name, &cookie_file, options)?;
+    if !is_live(directory, &options.live_file, &cookie_file) {
+        return Err(AppError::SwitchNotVerified(profile_name.to_string()));
+    }
+    Ok(outcome)
+}
+
+// The directories, each listed once, that hold a profile of exactly this name
+pub fn directories_with_profile(directories: &[PathBuf], profile_name: &str, config: &Config) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = Vec::new();
+    for directory in directories {
+        let canonical = directory.canonicalize().unwrap_or_else(|_| directory.clone());
+        if found.iter().any(|seen| seen.canonicalize().unwrap_or_else(|_| seen.clone()) == canonical) {
+            continue;
+        }
+        let has_profile = scan_cookie_files(directory, &config.live_cookie_file(directory)).is_ok_and(|files| files.iter().any(|file| file.profile_name == profile_name));
+        if has_profile {
+            found.push(directory.clone());
+        }
+    }
+    found
+}
+
+// Apply one profile in several directories, each through its own live file; a shared backup
+// directory would only keep the last of their backups, so each directory backs up into itself instead
+pub fn broadcast_switch(directories: &[PathBuf], profile_name: &str, config: &Config, mut options: SwitchOptions) -> Vec<(PathBuf, Result<SwitchOutcome, AppError>)> {
+    if matches!(options.backup, BackupPlan::To(_)) {
+        options.backup = BackupPlan::InDirectory;
+    }
+    directories
+        .iter()
+        .map(|directory| {
+            options.live_file = config.live_cookie_file(directory);
+            (directory.clone(), apply_profile_by_name(directory, profile_name, &options))
+        })
+        .collect()
+}
+
+// Whether the live cookies file already holds this profile's contents; line endings don't count,
+// since the live copy may have been normalized
+pub fn is_live(directory: &Path, live_file: &str, cookie_file: impl AsRef<OsStr>) -> bool {
+    let Ok(live) = fs::read(directory.join(live_file)) else {
+        return false;
+    };
+    read_cookie_file(&directory.join(cookie_file.as_ref()))
+        .is_ok_and(|contents| contents == live || normalize_line_endings(&contents) == normalize_line_endings(&live))
+}
+
+pub fn write_active_marker(directory: &Path, profile_name: &str) {
+    if let Err(e) = fs::write(directory.join(ACTIVE_PROFILE_MARKER), profile_name) {
+        log::warn!("Failed to write {}: {}", ACTIVE_PROFILE_MARKER, e);
+    }
+}
+
+pub fn read_active_marker(directory: &Path) -> Option<String> {
+    let name = fs::read_to_string(directory.join(ACTIVE_PROFILE_MARKER)).ok()?;
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+// Everything the manager writes itself: directory history, settings, the profile listing, the
+// rotated log, per-directory markers and the initials avatars it saved, if still unchanged.
+// Cookie files are never on this list and are skipped even if one somehow matches
+pub fn reset_manager_files(config_dir: &Path, cookie_directory: &Path, generated_avatars: &BTreeMap<PathBuf, String>) -> Result<Vec<PathBuf>, AppError> {
+    let mut candidates = vec![
+        config_dir.join(DIRECTORY_HISTORY_FILE),
+        config_dir.join(CONFIG_FILE),
+        config_dir.join(CONFIG_BACKUP_FILE),
+        config_dir.join(PROFILES_LISTING_FILE),
+        config_dir.join(format!("{LOG_FILE_NAME}.1")),
+        cookie_directory.join(DIRECTORY_CONFIG_FILE),
+        cookie_directory.join(ACTIVE_PROFILE_MARKER),
+    ];
+    candidates.extend(
+        generated_avatars
+            .iter()
+            .filter(|(path, checksum)| file_checksum(path).is_ok_and(|current| &current == *checksum))
+            .map(|(path, _)| path.clone())
+    );
+    
+    let mut removed = Vec::new();
+    for path in candidates {
+        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        if profile_name_from_file(&name).is_some() || is_operational_file(&name) || !path.is_file() {
+            continue;
+        }
+        fs::remove_file(&path).map_err(AppError::io("remove", &path))?;
+        log::info!("Reset removed {}", path.display());
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+#[derive(Serialize, Deserialize)]
+struct Vault {
+    kdf_rounds: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+// A locked file, by its path relative to the cookie directory
+#[derive(Serialize, Deserialize)]
+struct VaultEntry {
+    path: String,
+    contents: Vec<u8>,
+}
+
+pub fn is_locked(directory: &Path) -> bool {
+    directory.join(VAULT_FILE).is_file()
+}
+
+// Every profile but keep, plus the live-file backups and deleted profiles, which hold sessions too
+fn lockable_files(directory: &Path, keep: &OsStr) -> Result<Vec<String>, AppError> {
+    let mut paths = Vec::new();
+    let entries = fs::read_dir(directory).map_err(AppError::io("scan", directory))?;
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        if file_name == keep || !entry.path().is_file() {
+            continue;
+        }
+        let lossy = file_name.to_string_lossy();
+        let session = profile_name_from_file(&lossy).is_some() || is_live_backup(&lossy.to_ascii_lowercase());
+        match file_name.to_str() {
+            Some(name) if session => paths.push(name.to_string()),
+            None if session => return Err(AppError::InvalidInput("Rename cookie files whose names aren't valid UTF-8 before locking")),
+            _ => {}
+        }
+    }
+    
+    for folder in [TRASH_DIR, ARCHIVE_DIR] {
+        if let Ok(entries) = fs::read_dir(directory.join(folder)) {
+            for entry in entries.flatten() {
+                if let (true, Some(name)) = (entry.path().is_file(), entry.file_name().to_str()) {
+                    paths.push(format!("{}/{}", folder, name));
+                }
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn vault_cipher(password: &str, salt: &[u8], rounds: u32) -> ChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, rounds, &mut key);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+// Zero the file's contents on disk before unlinking it, so the session isn't left in free blocks as-is
+fn shred_file(path: &Path) -> io::Result<()> {
+    let length = fs::metadata(path)?.len();
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    file.write_all(&vec![0; length as usize])?;
+    file.sync_all()?;
+    drop(file);
+    fs::remove_file(path)
+}
+
+// Encrypt every session except keep's into the vault, then shred the originals; returns how many
+pub fn lock_profiles(directory: &Path, keep: &OsStr, password: &str) -> Result<usize, AppError> {
+    lock_profiles_with_rounds(directory, keep, password, VAULT_KDF_ROUNDS)
+}
+
+fn lock_profiles_with_rounds(directory: &Path, keep: &OsStr, password: &str, rounds: u32) -> Result<usize, AppError> {
+    if password.is_empty() {
+        return Err(AppError::InvalidInput("Choose a password"));
+    }
+    let vault_path = directory.join(VAULT_FILE);
+    if vault_path.exists() {
+        return Err(AppError::InvalidInput("Other profiles are already locked; unlock them first"));
+    }
+    let paths = lockable_files(directory, keep)?;
+    if paths.is_empty() {
+        return Err(AppError::InvalidInput("There are no other profiles to lock"));
+    }
+    
+    let mut entries = Vec::new();
+    for path in &paths {
+        let full_path = directory.join(path);
+        let contents = fs::read(&full_path).map_err(AppError::io("read", &full_path))?;
+        entries.push(VaultEntry { path: path.clone(), contents });
+    }
+    let plaintext = serde_json::to_vec(&entries).map_err(|e| AppError::VaultCorrupt(e.to_string()))?;
+    
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = vault_cipher(password, &salt, rounds)
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| AppError::VaultCorrupt("encryption failed".to_string()))?;
+    let vault = Vault { kdf_rounds: rounds, salt: to_hex(&salt), nonce: to_hex(&nonce), ciphertext: to_hex(&ciphertext) };
+    let contents = serde_json::to_string(&vault).map_err(|e| AppError::VaultCorrupt(e.to_string()))?;
+    write_config_file(&vault_path, &contents).map_err(|error| AppError::ConfigWrite { path: vault_path.clone(), error })?;
+    
+    // Nothing is removed until the vault is known to open again
+    if let Err(e) = open_vault(directory, password) {
+        fs::remove_file(&vault_path).ok();
+        return Err(e);
+    }
+    for path in &paths {
+        let full_path = directory.join(path);
+        shred_file(&full_path).map_err(AppError::io("remove", &full_path))?;
+    }
+    log::info!("Locked {} files in {}", paths.len(), vault_path.display());
+    Ok(paths.len())
+}
+
+fn open_vault(directory: &Path, password: &str) -> Result<Vec<VaultEntry>, AppError> {
+    let path = directory.join(VAULT_FILE);
+    let contents = fs::read_to_string(&path).map_err(AppError::io("read", &path))?;
+    let vault: Vault = serde_json::from_str(&contents).map_err(|e| AppError::VaultCorrupt(e.to_string()))?;
+    let (Some(salt), Some(nonce), Some(ciphertext)) = (from_hex(&vault.salt), from_hex(&vault.nonce), from_hex(&vault.ciphertext)) else {
+        return Err(AppError::VaultCorrupt("it is not hex-encoded".to_string()));
+    };
+    if nonce.len() != 12 {
+        return Err(AppError::VaultCorrupt("the nonce has the wrong length".to_string()));
+    }
+    
+    let plaintext = vault_cipher(password, &salt, vault.kdf_rounds)
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| AppError::WrongPassword)?;
+    serde_json::from_slice(&plaintext).map_err(|e| AppError::VaultCorrupt(e.to_string()))
+}
+
+// Put the locked files back and remove the vault; refuses if any of them exists again
+pub fn unlock_profiles(directory: &Path, password: &str) -> Result<usize, AppError> {
+    let entries = open_vault(directory, password)?;
+    for entry in &entries {
+        if !Path::new(&entry.path).components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(AppError::VaultCorrupt(format!("'{}' is outside the cookie directory", entry.path)));
+        }
+        let path = directory.join(&entry.path);
+        if path.exists() {
+            return Err(AppError::AlreadyExists(path));
+        }
+    }
+    
+    for entry in &entries {
+        let path = directory.join(&entry.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(AppError::io("create", parent))?;
+        }
+        fs::write(&path, &entry.contents).map_err(AppError::io("write", &path))?;
+    }
+    let vault_path = directory.join(VAULT_FILE);
+    fs::remove_file(&vault_path).map_err(AppError::io("remove", &vault_path))?;
+    log::info!("Unlocked {} files in {}", entries.len(), directory.display());
+    Ok(entries.len())
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// Files named cookie_<name> (no s) that would be picked up as cookies_<name>
+pub fn find_legacy_cookie_files(directory: &Path) -> io::Result<Vec<LegacyRename>> {
+    let mut renames = Vec::new();
+    
+    for entry in fs::read_dir(directory)?.flatten() {
+        let Some(file_name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if !file_name.to_ascii_lowercase().starts_with("cookie_") {
+            continue;
+        }
+        
+        let to = format!("cookies_{}", &file_name["cookie_".len()..]);
+        if profile_name_from_file(&to).is_none() {
+            continue;
+        }
+        let conflict = directory.join(&to).exists();
+        renames.push(LegacyRename { from: file_name, to, conflict });
+    }
+    
+    renames.sort_by(|a, b| a.from.cmp(&b.from));
+    Ok(renames)
+}
+
+// Preview a find/replace over every cookies_* file name; only files that would change are listed
+pub fn plan_batch_rename(directory: &Path, live_file: &str, find: &str, replace: &str) -> io::Result<Vec<BatchRename>> {
+    let files = scan_cookie_files(directory, live_file)?;
+    // Names that aren't UTF-8 can't be edited as text, so they are left alone
+    let mut renames: Vec<BatchRename> = files
+        .iter()
+        .filter_map(|file| file.file_name.to_str())
+        .filter(|file_name| file_name.contains(find))
+        .map(|file_name| BatchRename { from: file_name.to_string(), to: file_name.replace(find, replace), problem: None })
+        .filter(|rename| rename.to != rename.from)
+        .collect();
+    
+    // Files that aren't being renamed keep their names, so results must not land on them either
+    let mut taken: HashMap<String, usize> = files
+        .iter()
+        .filter(|file| !renames.iter().any(|r| file.file_name == *r.from))
+        .map(|file| (file.profile_name.to_lowercase(), usize::MAX))
+        .collect();
+    
+    for i in 0..renames.len() {
+        let problem = match profile_name_from_file(&renames[i].to) {
+            None => Some("must start with cookies_ and end in .txt or nothing".to_string()),
+            Some(name) if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') => {
+                Some("the profile name may only contain letters, numbers, '_' and '-'".to_string())
+            }
+            Some(name) => match taken.insert(name.to_lowercase(), i) {
+                Some(usize::MAX) => Some("a profile with that name already exists".to_string()),
+                Some(other) => {
+                    renames[other].problem = Some(format!("collides with {}", renames[i].from));
+                    Some(format!("collides with {}", renames[other].from))
+                }
+                None => None,
+            },
+        };
+        renames[i].problem = problem;
+    }
+    
+    Ok(renames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    fn token() -> String {
+        format!("_|WARNING:-DO-NOT-SHARE-THIS.|_{}", "A".repeat(120))
+    }
+    
+    fn options(backup: BackupPlan, freeze: bool) -> SwitchOptions {
+        SwitchOptions { backup, freeze, normalize_line_endings: false, live_file: LIVE_COOKIE_FILE.to_string() }
+    }
+    
+    fn directory_with(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for (name, contents) in files {
+            fs::write(dir.path().join(name), contents).unwrap();
+        }
+        dir
+    }
+    
+    fn live(dir: &TempDir) -> String {
+        fs::read_to_string(dir.path().join("cookies")).unwrap()
+    }
+    
+    #[test]
+    fn profile_names_ignore_case_in_prefix_and_suffix() {
+        assert_eq!(profile_name_from_file("cookies_main").as_deref(), Some("main"));
+        assert_eq!(profile_name_from_file("Cookies_Alt.TXT").as_deref(), Some("Alt"));
+        assert_eq!(profile_name_from_file("COOKIES_trade.txt.GZ").as_deref(), Some("trade"));
+        assert_eq!(profile_name_from_file("cookies_main.bak"), None);
+        assert_eq!(profile_name_from_file("cookies"), None);
+        assert_eq!(profile_name_from_file("cookie_main"), None);
+    }
+    
+    #[test]
+    fn scan_finds_mixed_case_files_sorted_by_name() {
+        let dir = directory_with(&[
+            ("Cookies_Zed.TXT", "z"),
+            ("cookies_alt", "a"),
+            ("COOKIES_Main.txt", "m"),
+            ("cookies", "live"),
+            ("cookies.bak", "old"),
+            ("alt.png", ""),
+        ]);
+        
+        let files = scan_cookie_files(dir.path(), LIVE_COOKIE_FILE).unwrap();
+        let names: Vec<_> = files.iter().map(|f| (f.profile_name.as_str(), f.file_name.to_str().unwrap())).collect();
+        assert_eq!(names, [("alt", "cookies_alt"), ("Main", "COOKIES_Main.txt"), ("Zed", "Cookies_Zed.TXT")]);
+        assert_eq!(files[0].size, 1);
+    }
+    
+    #[test]
+    fn mixed_case_profiles_switch_from_their_original_file() {
+        let dir = directory_with(&[("Cookies_Main.TXT", &token()), ("COOKIES_alt", "other"), ("cookies", "before")]);
+        
+        apply_profile_by_name(dir.path(), "Main", &options(BackupPlan::Skip, false)).unwrap();
+        assert_eq!(live(&dir), token());
+        // The file keeps the name it was given, whatever the case of its prefix
+        assert!(is_live(dir.path(), LIVE_COOKIE_FILE, OsStr::new("Cookies_Main.TXT")));
+        assert!(dir.path().join("COOKIES_alt").exists());
+    }
+    
+    #[test]
+    fn operational_files_are_never_profiles() {
+        for name in ["cookies", "COOKIES", "cookies.tmp", "cookies.bak", "Cookies.BAK", "cookies.bak.1", "cookies.bak.gz", "cookies.txt.bak"] {
+            assert!(is_operational_file(name), "{}", name);
+            assert_eq!(profile_name_from_file(name), None, "{}", name);
+        }
+        
+        // A profile that merely has an operational-sounding name is still a profile
+        assert!(!is_operational_file("cookies_backup"));
+        assert_eq!(profile_name_from_file("cookies_backup").as_deref(), Some("backup"));
+    }
+    
+    #[test]
+    fn scan_skips_operational_files() {
+        let dir = directory_with(&[
+            ("cookies", "live"),
+            ("cookies.tmp", ""),
+            ("cookies.bak", ""),
+            ("cookies.bak.2", ""),
+            ("cookies_main", "main"),
+            ("cookies_backup.txt", "profile"),
+        ]);
+        
+        let files = scan_cookie_files(dir.path(), LIVE_COOKIE_FILE).unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.file_name.to_str().unwrap()).collect();
+        assert_eq!(names, ["cookies_backup.txt", "cookies_main"]);
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_file_names_are_scanned_and_switchable() {
+        use std::os::unix::ffi::OsStrExt;
+        
+        let dir = TempDir::new().unwrap();
+        let file_name = OsStr::from_bytes(b"cookies_caf\xe9.txt");
+        fs::write(dir.path().join(file_name), "latin-1").unwrap();
+        
+        let files = scan_cookie_files(dir.path(), LIVE_COOKIE_FILE).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].profile_name, "caf\u{FFFD}");
+        assert_eq!(files[0].file_name, file_name);
+        
+        switch_profile(dir.path(), &files[0].profile_name, &files[0].file_name, &options(BackupPlan::Skip, false)).unwrap();
+        assert_eq!(live(&dir), "latin-1");
+        assert!(is_live(dir.path(), LIVE_COOKIE_FILE, file_name));
+        
+        // Renames start from the real name and keep its suffix
+        assert_eq!(cookie_file_name("cafe", file_name), "cookies_cafe.txt");
+        assert!(plan_batch_rename(dir.path(), LIVE_COOKIE_FILE, "caf", "cafe").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn scan_of_missing_directory_fails() {
+        let dir = TempDir::new().unwrap();
+        let err = scan_cookie_files(&dir.path().join("gone"), LIVE_COOKIE_FILE).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+    
+    #[test]
+    fn format_profile_name_title_cases_words() {
+        assert_eq!(format_profile_name("main"), "Main");
+        assert_eq!(format_profile_name("alt_account-two"), "Alt Account Two");
+        assert_eq!(format_profile_name("SHOUTY__name"), "Shouty Name");
+        assert_eq!(format_profile_name("_"), "");
+    }
+    
+    #[test]
+    fn flatpak_paths_are_recognised() {
+        assert!(is_flatpak_path(&expand_path(DEFAULT_COOKIE_DIRECTORY)));
+        assert!(is_flatpak_path(Path::new("/home/me/.var/app/org.vinegarhq.Sober/data/sober")));
+        assert!(!is_flatpak_path(Path::new("/home/me/.local/share/sober")));
+        assert!(!is_flatpak_path(Path::new("/home/me/.var/application")));
+    }
+    
+    #[test]
+    fn cookie_file_name_keeps_suffixes() {
+        assert_eq!(cookie_file_name("new", "cookies_old"), "cookies_new");
+        assert_eq!(cookie_file_name("new", "cookies_old.TXT"), "cookies_new.txt");
+        assert_eq!(cookie_file_name("new", "cookies_old.gz"), "cookies_new.gz");
+        assert_eq!(cookie_file_name("new", "cookies_old.txt.gz"), "cookies_new.txt.gz");
+    }
+    
+    #[test]
+    fn switch_makes_profile_live_and_marks_it_active() {
+        let dir = directory_with(&[("cookies_main", "main"), ("cookies_alt.txt", "alt"), ("cookies", "main")]);
+        
+        let outcome = switch_profile(dir.path(), "alt", "cookies_alt.txt", &options(BackupPlan::Skip, false)).unwrap();
+        assert!(matches!(outcome, SwitchOutcome::Switched { backup_warning: None }));
+        assert_eq!(live(&dir), "alt");
+        assert_eq!(read_active_marker(dir.path()).as_deref(), Some("alt"));
+        assert!(!dir.path().join("cookies.bak").exists());
+        
+        let outcome = switch_profile(dir.path(), "alt", "cookies_alt.txt", &options(BackupPlan::Skip, false)).unwrap();
+        assert!(matches!(outcome, SwitchOutcome::AlreadyActive));
+    }
+    
+    #[test]
+    fn apply_by_name_validates_switches_and_verifies() {
+        let dir = directory_with(&[("cookies_main.txt", &token()), ("cookies_empty", ""), ("cookies", "before")]);
+        let options = options(BackupPlan::InDirectory, false);
+        
+        let outcome = apply_profile_by_name(dir.path(), "main", &options).unwrap();
+        assert!(matches!(outcome, SwitchOutcome::Switched { backup_warning: None }));
+        assert_eq!(live(&dir), token());
+        assert_eq!(fs::read_to_string(dir.path().join("cookies.bak")).unwrap(), "before");
+        assert!(!dir.path().join("cookies.tmp").exists());
+        assert!(matches!(apply_profile_by_name(dir.path(), "main", &options), Ok(SwitchOutcome::AlreadyActive)));
+        
+        // Neither an unusable cookie nor an unknown name touches the live file
+        let err = apply_profile_by_name(dir.path(), "empty", &options).err().unwrap();
+        assert!(matches!(err, AppError::InvalidCookie { .. }));
+        let err = apply_profile_by_name(dir.path(), "gone", &options).err().unwrap();
+        assert!(matches!(err, AppError::ProfileNotFound(_)));
+        assert_eq!(live(&dir), token());
+        assert_eq!(read_active_marker(dir.path()).as_deref(), Some("main"));
+    }
+    
+    #[test]
+    fn switch_can_normalize_line_endings() {
+        let dir = directory_with(&[("cookies_win", "a\r\nb\r\n"), ("cookies", "old")]);
+        let options = SwitchOptions { normalize_line_endings: true, ..options(BackupPlan::Skip, false) };
+        
+        switch_profile(dir.path(), "win", "cookies_win", &options).unwrap();
+        assert_eq!(live(&dir), "a\nb\n");
+        assert_eq!(fs::read_to_string(dir.path().join("cookies_win")).unwrap(), "a\r\nb\r\n");
+        assert!(is_live(dir.path(), LIVE_COOKIE_FILE, "cookies_win"));
+        assert!(matches!(switch_profile(dir.path(), "win", "cookies_win", &options), Ok(SwitchOutcome::AlreadyActive)));
+    }
+    
+    #[test]
+    fn switch_unpacks_compressed_profiles() {
+        let dir = TempDir::new().unwrap();
+        write_compressed(&dir.path().join("cookies_main.gz"), b"packed").unwrap();
+        
+        switch_profile(dir.path(), "main", "cookies_main.gz", &options(BackupPlan::Skip, false)).unwrap();
+        assert_eq!(live(&dir), "packed");
+        assert!(is_live(dir.path(), LIVE_COOKIE_FILE, "cookies_main.gz"));
+    }
+    
+    #[test]
+    fn frozen_live_file_can_still_be_replaced() {
+        let dir = directory_with(&[("cookies_main", "main"), ("cookies_alt", "alt")]);
+        
+        switch_profile(dir.path(), "main", "cookies_main", &options(BackupPlan::Skip, true)).unwrap();
+        assert!(fs::metadata(dir.path().join("cookies")).unwrap().permissions().readonly());
+        
+        switch_profile(dir.path(), "alt", "cookies_alt", &options(BackupPlan::Skip, false)).unwrap();
+        assert_eq!(live(&dir), "alt");
+        assert!(!fs::metadata(dir.path().join("cookies")).unwrap().permissions().readonly());
+    }
+    
+    #[test]
+    fn switch_of_missing_profile_fails_with_copy_error() {
+        let dir = directory_with(&[("cookies", "live")]);
+        
+        let err = switch_profile(dir.path(), "gone", "cookies_gone", &options(BackupPlan::Skip, false)).err().unwrap();
+        assert!(matches!(err, AppError::CopyFailed { .. }));
+        assert_eq!(read_active_marker(dir.path()), None);
+    }
+    
+    #[test]
+    fn backup_in_directory_keeps_previous_live_file() {
+        let dir = directory_with(&[("cookies_alt", "alt"), ("cookies", "before")]);
+        
+        switch_profile(dir.path(), "alt", "cookies_alt", &options(BackupPlan::InDirectory, false)).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("cookies.bak")).unwrap(), "before");
+        assert_eq!(live(&dir), "alt");
+    }
+    
+    #[test]
+    fn backup_to_other_directory_creates_it() {
+        let dir = directory_with(&[("cookies", "before")]);
+        let backup_dir = dir.path().join("backups");
+        
+        let warning = backup_live_cookies(dir.path(), LIVE_COOKIE_FILE, &BackupPlan::To(backup_dir.clone())).unwrap();
+        assert_eq!(warning, None);
+        assert_eq!(fs::read_to_string(backup_dir.join("cookies.bak")).unwrap(), "before");
+    }
+    
+    #[test]
+    fn unwritable_backup_directory_falls_back_with_warning() {
+        let dir = directory_with(&[("cookies_alt", "alt"), ("cookies", "before"), ("not-a-dir", "")]);
+        let plan = BackupPlan::To(dir.path().join("not-a-dir").join("backups"));
+        
+        let outcome = switch_profile(dir.path(), "alt", "cookies_alt", &options(plan, false)).unwrap();
+        let SwitchOutcome::Switched { backup_warning: Some(warning) } = outcome else {
+            panic!("expected a backup warning");
+        };
+        assert!(warning.contains("not writable"));
+        assert_eq!(fs::read_to_string(dir.path().join("cookies.bak")).unwrap(), "before");
+    }
+    
+    #[test]
+    fn backup_plan_prefers_command_line_flags() {
+        let mut config = Config { backup_before_switch: true, ..Config::default() };
+        
+        assert!(matches!(backup_plan(&config, true, None), BackupPlan::Skip));
+        assert!(matches!(backup_plan(&config, false, None), BackupPlan::InDirectory));
+        assert!(matches!(backup_plan(&config, false, Some(Path::new("/tmp/b"))), BackupPlan::To(dir) if dir == Path::new("/tmp/b")));
+        
+        config.backup_before_switch = false;
+        assert!(matches!(backup_plan(&config, false, Some(Path::new("/tmp/b"))), BackupPlan::Skip));
+    }
+    
+    #[test]
+    fn validate_cookie_accepts_known_formats() {
+        let token = token();
+        let netscape = format_cookie_file(&token);
+        
+        assert_eq!(validate_cookie(&netscape).unwrap(), token);
+        assert_eq!(validate_cookie(&format!("foo=bar; {}={}", ROBLOSECURITY, token)).unwrap(), token);
+        assert_eq!(validate_cookie(&format!("  {}\n", token)).unwrap(), token);
+    }
+    
+    #[test]
+    fn validate_cookie_explains_rejections() {
+        assert_eq!(validate_cookie("  ").unwrap_err(), "it is empty");
+        assert!(validate_cookie("hello").unwrap_err().contains("no .ROBLOSECURITY value"));
+        assert!(validate_cookie(".ROBLOSECURITY=short").unwrap_err().contains("only 5 characters"));
+    }
+    
+    #[test]
+    fn avatar_crop_defaults_to_a_centred_square() {
+        let crop = AvatarCrop::default();
+        assert_eq!(crop.uv_rect(200.0, 100.0), [[0.25, 0.0], [0.75, 1.0]]);
+        assert_eq!(crop.uv_rect(100.0, 100.0), [[0.0, 0.0], [1.0, 1.0]]);
+        
+        let moved = AvatarCrop { zoom: 1.0, offset: [1.0, 0.0] };
+        assert_eq!(moved.uv_rect(200.0, 100.0), [[0.5, 0.0], [1.0, 1.0]]);
+        
+        let zoomed = AvatarCrop { zoom: 2.0, offset: [-1.0, 0.0] };
+        assert_eq!(zoomed.uv_rect(100.0, 100.0), [[0.0, 0.25], [0.5, 0.75]]);
+    }
+    
+    #[test]
+    fn compare_cookie_files_looks_at_the_token() {
+        let jar = |token: &str| format!(".roblox.com\tTRUE\t/\tTRUE\t0\t{}\t{}\n", ROBLOSECURITY, token);
+        let other = format!("_|WARNING:-DO-NOT-SHARE-THIS.|_{}", "B".repeat(120));
+        let dir = directory_with(&[
+            ("a", &jar(&token())),
+            ("b", &jar(&token())),
+            ("c", &format!("# exported elsewhere\n{}", jar(&token()))),
+            ("d", &jar(&other)),
+            ("e", "not a cookie"),
+        ]);
+        let compare = |a: &str, b: &str| compare_cookie_files(&dir.path().join(a), &dir.path().join(b)).unwrap();
+        
+        assert_eq!(compare("a", "b"), CookieComparison::Identical);
+        assert_eq!(compare("a", "c"), CookieComparison::SameToken);
+        assert_eq!(compare("a", "d"), CookieComparison::OnlyTokenDiffers);
+        assert_eq!(compare("c", "d"), CookieComparison::Different);
+        assert_eq!(compare("a", "e"), CookieComparison::Different);
+    }
+    
+    #[test]
+    fn new_profile_names_are_checked_against_the_directory() {
+        let dir = directory_with(&[("cookies_Main.txt", "")]);
+        
+        assert_eq!(validate_new_profile_name(dir.path(), " alt-2 ").unwrap(), "alt-2");
+        assert!(matches!(validate_new_profile_name(dir.path(), ""), Err(AppError::InvalidInput(_))));
+        assert!(matches!(validate_new_profile_name(dir.path(), "a b"), Err(AppError::InvalidInput(_))));
+        assert!(matches!(validate_new_profile_name(dir.path(), "main"), Err(AppError::NameTaken(_))));
+    }
+    
+    #[test]
+    fn live_file_names_must_be_bare_and_not_profiles() {
+        assert_eq!(validate_live_file_name(" cookies.txt ").unwrap(), "cookies.txt");
+        for name in ["", "../../.bashrc", "sub/dir/cookies", "..", "cookies_main", "Cookies_Alt.txt", "config.json"] {
+            assert!(matches!(validate_live_file_name(name), Err(AppError::InvalidInput(_))), "{}", name);
+        }
+    }
+    
+    #[test]
+    fn config_parse_migrates_older_versions() {
+        let (config, migrated) = Config::parse(r#"{"version": 1, "profiles": {"main": {"favorite": true}}}"#).unwrap();
+        assert!(migrated);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert!(config.onboarding_complete);
+        assert!(config.profiles["main"].favorite);
+        
+        let (_, migrated) = Config::parse(&format!(r#"{{"version": {}}}"#, CONFIG_VERSION)).unwrap();
+        assert!(!migrated);
+    }
+    
+    #[test]
+    fn config_parse_rejects_unusable_files() {
+        assert!(Config::parse("[]").is_err());
+        assert!(Config::parse("{").is_err());
+        assert!(Config::parse(r#"{"version": 99}"#).err().unwrap().contains("newer"));
+    }
+    
+    #[test]
+    fn config_round_trips_through_json() {
+        let mut config = Config::default();
+        config.record_switch("main");
+        config.profiles.entry("main".to_string()).or_default().aliases = vec!["m".to_string()];
+        
+        let (parsed, migrated) = Config::parse(&serde_json::to_string(&config).unwrap()).unwrap();
+        assert!(!migrated);
+        assert_eq!(parsed.switch_history[0].profile, "main");
+        assert_eq!(parsed.profiles["main"].aliases, ["m"]);
+    }
+    
+    #[test]
+    fn directory_config_is_optional() {
+        let dir = TempDir::new().unwrap();
+        assert!(DirectoryConfig::load(dir.path()).unwrap().is_none());
+        
+        let mut profiles = BTreeMap::new();
+        profiles.insert("main".to_string(), ProfileConfig { notes: "hi".to_string(), ..Default::default() });
+        DirectoryConfig::new(profiles).save(dir.path()).unwrap();
+        assert_eq!(DirectoryConfig::load(dir.path()).unwrap().unwrap().profiles["main"].notes, "hi");
+    }
+    
+    #[test]
+    fn batch_rename_flags_collisions() {
+        let dir = directory_with(&[("cookies_old_a", ""), ("cookies_old_b", ""), ("cookies_new_a", ""), ("cookies_keep", "")]);
+        
+        let renames = plan_batch_rename(dir.path(), LIVE_COOKIE_FILE, "old_", "new_").unwrap();
+        let problems: Vec<_> = renames.iter().map(|r| (r.from.as_str(), r.problem.is_some())).collect();
+        assert_eq!(problems, [("cookies_old_a", true), ("cookies_old_b", false)]);
+        
+        let renames = plan_batch_rename(dir.path(), LIVE_COOKIE_FILE, "old_a", "new_b").unwrap();
+        assert!(renames[0].problem.is_none());
+        
+        let renames = plan_batch_rename(dir.path(), LIVE_COOKIE_FILE, "old_b", "keep").unwrap();
+        assert_eq!(renames[0].problem.as_deref(), Some("a profile with that name already exists"));
+        
+        // Two renames landing on the same name both get flagged
+        let dir = directory_with(&[("cookies_aa", ""), ("cookies_ab", "")]);
+        let renames = plan_batch_rename(dir.path(), LIVE_COOKIE_FILE, "a", "b").unwrap();
+        assert_eq!(renames[0].problem.as_deref(), Some("collides with cookies_ab"));
+        assert_eq!(renames[1].problem.as_deref(), Some("collides with cookies_aa"));
+    }
+    
+    #[test]
+    fn concurrent_config_saves_stay_valid() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.json");
+        
+        let writers: Vec<_> = (0..8)
+            .map(|n| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    for i in 0..50 {
+                        // Vary the length so a torn write would leave trailing garbage
+                        let config = Config { launch_command: "x".repeat(n * 100 + i), ..Config::default() };
+                        config.save_to(&path).unwrap();
+                    }
+                })
+            })
+            .collect();
+        
+        let reader = {
+            let path = path.clone();
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    if let Ok(contents) = fs::read_to_string(&path) {
+                        assert!(Config::parse(&contents).is_ok());
+                    }
+                }
+            })
+        };
+        
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        reader.join().unwrap();
+        
+        assert!(Config::parse(&fs::read_to_string(&path).unwrap()).is_ok());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1, "temporary files were left behind");
+    }
+    
+    #[test]
+    fn switch_urls_are_strictly_parsed() {
+        assert_eq!(parse_switch_url("sober-switch://main"), Ok("main".to_string()));
+        assert_eq!(parse_switch_url("SOBER-SWITCH://alt%20two/?from=bookmark"), Ok("alt two".to_string()));
+        assert_eq!(parse_switch_url("sober-switch:main"), Ok("main".to_string()));
+        
+        for bad in ["https://main", "sober-switch://", "sober-switch://../cookies", "sober-switch://a/b", "sober-switch://a%2", "sober-switch://%00x"] {
+            assert!(parse_switch_url(bad).is_err(), "{} was accepted", bad);
+        }
+    }
+    
+    #[test]
+    fn initials_come_from_the_first_two_words() {
+        assert_eq!(profile_initials("main_alt"), "MA");
+        assert_eq!(profile_initials("bob"), "B");
+        assert_eq!(profile_initials("-é-"), "?");
+        
+        let avatar = initials_avatar("main_alt");
+        assert_eq!(avatar.dimensions(), (INITIALS_AVATAR_SIZE, INITIALS_AVATAR_SIZE));
+        assert!(avatar.pixels().any(|p| p.0 == [255, 255, 255, 255]));
+        assert_eq!(avatar, initials_avatar("main_alt"));
+    }
+    
+    #[test]
+    fn config_dir_as_cookie_directory_has_no_spurious_profiles() {
+        let dir = directory_with(&[
+            ("config.json", "{}"),
+            ("config.bak", "{}"),
+            ("config.json.1234.tmp", "{}"),
+            ("directory.txt", "[]"),
+            (".sober-profiles.json", "{}"),
+            (".active_profile", "a"),
+            ("sober-cookie-manager.log", ""),
+            ("cookies_a", &token()),
+        ]);
+        
+        let names: Vec<String> = scan_cookie_files(dir.path(), LIVE_COOKIE_FILE).unwrap().into_iter().map(|f| f.profile_name).collect();
+        assert_eq!(names, ["a"]);
+        
+        assert!(overlaps_config_dir(dir.path(), dir.path()));
+        assert!(overlaps_config_dir(&dir.path().join("sub"), dir.path()));
+        assert!(!overlaps_config_dir(dir.path(), TempDir::new().unwrap().path()));
+    }
+    
+    #[test]
+    fn configured_logo_is_tried_first() {
+        let candidates = logo_candidates("/opt/fork/logo.png");
+        assert_eq!(candidates.first(), Some(&PathBuf::from("/opt/fork/logo.png")));
+        assert_eq!(candidates.last(), Some(&PathBuf::from(LOGO_FILE_NAME)));
+        assert_eq!(logo_candidates(" ").len(), candidates.len() - 1);
+    }
+    
+    #[test]
+    fn locking_moves_other_sessions_into_the_vault() {
+        let dir = directory_with(&[("cookies_a", &token()), ("cookies_b", "b"), ("cookies.bak", "old"), ("a.png", "")]);
+        fs::create_dir(dir.path().join(TRASH_DIR)).unwrap();
+        fs::write(dir.path().join(TRASH_DIR).join("1__cookies_c"), "c").unwrap();
+        
+        assert_eq!(lock_profiles_with_rounds(dir.path(), OsStr::new("cookies_a"), "secret", 10).unwrap(), 3);
+        assert!(is_locked(dir.path()));
+        assert!(dir.path().join("cookies_a").exists() && dir.path().join("a.png").exists());
+        assert!(!dir.path().join("cookies_b").exists() && !dir.path().join("cookies.bak").exists());
+        assert_eq!(scan_cookie_files(dir.path(), LIVE_COOKIE_FILE).unwrap().len(), 1);
+        
+        assert!(matches!(unlock_profiles(dir.path(), "wrong"), Err(AppError::WrongPassword)));
+        assert_eq!(unlock_profiles(dir.path(), "secret").unwrap(), 3);
+        assert!(!is_locked(dir.path()));
+        assert_eq!(fs::read_to_string(dir.path().join("cookies_b")).unwrap(), "b");
+        assert_eq!(fs::read_to_string(dir.path().join(TRASH_DIR).join("1__cookies_c")).unwrap(), "c");
+    }
+    
+    #[test]
+    fn cookie_editor_exports_become_cookie_jars() {
+        let export = format!(
+            r#"[{{"domain":".roblox.com","expirationDate":1900000000.5,"name":"RBXEventTrackerV2","value":"x"}},
+               {{"domain":".roblox.com","expirationDate":1900000000.5,"hostOnly":false,"name":".ROBLOSECURITY","path":"/","value":"{}"}}]"#,
+            token()
+        );
+        let jar = parse_cookie_editor_json(&export).unwrap();
+        assert_eq!(parse_roblosecurity(&jar), Some(token()));
+        assert!(jar.contains("\t1900000000\t"));
+        
+        assert!(parse_cookie_editor_json(r#"[{"name":"other","value":"x"}]"#).unwrap_err().contains("no .ROBLOSECURITY"));
+        assert!(parse_cookie_editor_json(r#"{"name":".ROBLOSECURITY"}"#).is_err());
+        let elsewhere = format!(r#"[{{"domain":"evil.example","name":".ROBLOSECURITY","value":"{}"}}]"#, token());
+        assert!(parse_cookie_editor_json(&elsewhere).is_err());
+    }
+    
+    #[test]
+    fn game_shortcuts_take_ids_and_links() {
+        let games = parse_game_shortcuts("Obby = 1818; Adopt Me = https://www.roblox.com/games/920587237/Adopt-Me?x=1;").unwrap();
+        assert_eq!(games, vec![
+            GameShortcut { label: "Obby".to_string(), place_id: 1818 },
+            GameShortcut { label: "Adopt Me".to_string(), place_id: 920587237 },
+        ]);
+        assert_eq!(format_game_shortcuts(&games), "Obby = 1818; Adopt Me = 920587237");
+        assert_eq!(games[0].launch_url(), "roblox://experiences/start?placeId=1818");
+        
+        assert!(parse_game_shortcuts("").unwrap().is_empty());
+        assert!(parse_game_shortcuts("Obby").is_err());
+        assert!(parse_game_shortcuts(" = 1818").is_err());
+        assert!(parse_game_shortcuts("Obby = abc").is_err());
+    }
+    
+    #[test]
+    fn snapshots_count_external_changes() {
+        let dir = directory_with(&[("cookies_a.txt", "1"), ("cookies_b.txt", "2"), ("cookies_c.txt", "3")]);
+        let before = DirectorySnapshot::capture(dir.path(), LIVE_COOKIE_FILE);
+        assert_eq!(before.diff(&before).summary(), None);
+        
+        fs::write(dir.path().join("cookies_a.txt"), "changed").unwrap();
+        fs::write(dir.path().join("cookies_b.txt"), "changed too").unwrap();
+        fs::remove_file(dir.path().join("cookies_c.txt")).unwrap();
+        fs::write(dir.path().join("cookies_d.txt"), "4").unwrap();
+        let diff = before.diff(&DirectorySnapshot::capture(dir.path(), LIVE_COOKIE_FILE));
+        assert_eq!(diff, SnapshotDiff { updated: 2, added: 1, removed: 1 });
+        assert_eq!(diff.summary().as_deref(), Some("2 profiles updated, 1 added, 1 removed"));
+        assert_eq!(SnapshotDiff { added: 1, ..SnapshotDiff::default() }.summary().as_deref(), Some("1 profile added"));
+    }
+    
+    #[test]
+    fn sober_processes_are_recognised() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert!(is_sober_process(OsStr::new("sober"), &[]));
+        assert!(is_sober_process(OsStr::new("flatpak"), &args(&["flatpak", "run", "org.vinegarhq.Sober"])));
+        // A path inside Sober's data directory is not Sober itself
+        assert!(!is_sober_process(OsStr::new("cookie_gui"), &args(&["cookie_gui", "--dir", "~/.var/app/org.vinegarhq.Sober/data/sober"])));
+    }
+    
+    #[test]
+    fn profiles_listing_has_a_stable_shape() {
+        let mut config = Config::default();
+        config.record_switch("main");
+        let dir = tempfile::tempdir().unwrap();
+        let listing = ProfilesListing::new(Path::new("/cookies"), vec![ListedProfile {
+            name: "main".to_string(),
+            file: "cookies_main.txt".to_string(),
+            display_name: "Main".to_string(),
+            active: true,
+            last_used: config.last_used("main"),
+        }]);
+        let path = dir.path().join(PROFILES_LISTING_FILE);
+        listing.write_to(&path).unwrap();
+        
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["version"], PROFILES_LISTING_VERSION);
+        assert_eq!(written["directory"], "/cookies");
+        let profile = &written["profiles"][0];
+        assert_eq!((&profile["name"], &profile["file"], &profile["active"]), (&"main".into(), &"cookies_main.txt".into(), &true.into()));
+        assert!(profile["last_used"].as_u64().is_some());
+        assert_eq!(config.last_used("other"), None);
+    }
+    
+    #[test]
+    fn days_ago_counts_whole_days() {
+        let day = 24 * 60 * 60;
+        assert_eq!(days_ago(10 * day, 10 * day + 60), "today");
+        assert_eq!(days_ago(10 * day, 11 * day + 60), "yesterday");
+        assert_eq!(days_ago(10 * day, 13 * day), "3 days ago");
+        // A clock that went backwards isn't "in the future"
+        assert_eq!(days_ago(10 * day, day), "today");
+    }
+    
+    #[test]
+    fn broadcasts_reach_every_directory_with_the_profile() {
+        let jar = format_cookie_file(&token());
+        let first = directory_with(&[("cookies_main.txt", &jar)]);
+        let second = directory_with(&[("cookies_main.txt", &jar), ("cookies", "old")]);
+        let other = directory_with(&[("cookies_alt.txt", &jar)]);
+        let listed = [first.path().to_path_buf(), second.path().to_path_buf(), first.path().to_path_buf(), other.path().to_path_buf()];
+        
+        let directories = directories_with_profile(&listed, "main", &Config::default());
+        assert_eq!(directories, vec![first.path().to_path_buf(), second.path().to_path_buf()]);
+        
+        let backups = TempDir::new().unwrap();
+        let results = broadcast_switch(&directories, "main", &Config::default(), options(BackupPlan::To(backups.path().to_path_buf()), false));
+        assert!(results.iter().all(|(_, result)| matches!(result, Ok(SwitchOutcome::Switched { .. }))));
+        assert_eq!((live(&first), live(&second)), (jar.clone(), jar.clone()));
+        assert_eq!(fs::read_to_string(second.path().join("cookies.bak")).unwrap(), "old");
+        assert!(!backups.path().join("cookies.bak").exists());
+        
+        assert!(wants_broadcast("sober-switch://main?all"));
+        assert!(!wants_broadcast("sober-switch://main?allx=1"));
+        assert!(!wants_broadcast("sober-switch://main"));
+    }
+    
+    #[test]
+    fn archived_profiles_leave_the_scan_until_unarchived() {
+        let dir = directory_with(&[("cookies_main.txt", "m"), ("cookies_old.txt", "o")]);
+        archive_profile(dir.path(), OsStr::new("cookies_old.txt")).unwrap();
+        let names = |files: Vec<CookieFile>| files.into_iter().map(|f| f.profile_name).collect::<Vec<_>>();
+        assert_eq!(names(scan_cookie_files(dir.path(), LIVE_COOKIE_FILE).unwrap()), ["main"]);
+        
+        let archived = list_archived(dir.path());
+        assert_eq!(names(list_archived(dir.path())), ["old"]);
+        
+        // A new profile of the same name blocks bringing the old one back
+        fs::write(dir.path().join("cookies_OLD"), "new").unwrap();
+        assert!(matches!(unarchive_profile(dir.path(), &archived[0]), Err(AppError::NameTaken(_))));
+        fs::remove_file(dir.path().join("cookies_OLD")).unwrap();
+        
+        unarchive_profile(dir.path(), &archived[0]).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("cookies_old.txt")).unwrap(), "o");
+        assert!(list_archived(dir.path()).is_empty());
+    }
+    
+    #[test]
+    fn flatpak_overrides_can_move_the_data_directory() {
+        let keyfile = "[Context]\nfilesystems=home;\nXDG_DATA_HOME=/not/here\n\n[Environment]\nXDG_DATA_HOME=/games/sober-data\n";
+        assert_eq!(flatpak_data_home(keyfile), Some(PathBuf::from("/games/sober-data")));
+        assert_eq!(flatpak_data_home("[Context]\nXDG_DATA_HOME=/not/here\n"), None);
+        assert_eq!(flatpak_data_home("[Environment]\nXDG_DATA_HOME=\n"), None);
+        // Leaving [Environment] for another group stops the search there
+        assert_eq!(flatpak_data_home("[Environment]\nHOME=/x\n[Session Bus Policy]\nXDG_DATA_HOME=/not/here\n"), None);
+    }
+    
+    #[test]
+    fn sober_config_can_name_the_data_directory() {
+        assert_eq!(sober_config_data_directory(r#"{"data_directory": "/games/sober"}"#), Some(PathBuf::from("/games/sober")));
+        assert_eq!(sober_config_data_directory(r#"{"data_dir": " ", "data_path": "/other"}"#), Some(PathBuf::from("/other")));
+        assert_eq!(sober_config_data_directory(r#"{"fflags": {}, "data_directory": 3}"#), None);
+        assert_eq!(sober_config_data_directory("not json"), None);
+    }
+    
+    #[test]
+    fn switches_are_mirrored_to_every_extra_target() {
+        let dir = directory_with(&[("cookies", "live")]);
+        let other = TempDir::new().unwrap();
+        let mirror = other.path().join("cookies");
+        let targets = vec![mirror.clone(), dir.path().join("cookies"), other.path().join("missing").join("cookies")];
+        
+        let results = mirror_live_cookies(dir.path(), LIVE_COOKIE_FILE, &targets, false);
+        // The live file itself isn't a mirror, and one bad target doesn't stop the others
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.is_err());
+        assert_eq!(fs::read_to_string(&mirror).unwrap(), "live");
+        assert!(!other.path().join("cookies.tmp").exists());
+    }
+    
+    #[test]
+    fn each_directory_switches_through_its_own_live_file() {
+        let jar = format_cookie_file(&token());
+        let dir = directory_with(&[("cookies_main.txt", &jar), ("cookies.txt", "old")]);
+        let mut config = Config::default();
+        assert_eq!(config.live_cookie_file(dir.path()), "cookies.txt");
+        assert_eq!(config.live_cookie_file(TempDir::new().unwrap().path()), LIVE_COOKIE_FILE);
+        // The detected live file is never offered as a profile
+        assert_eq!(scan_cookie_files(dir.path(), LIVE_COOKIE_FILE).unwrap().len(), 1);
+        
+        let options = SwitchOptions::new(&config, dir.path(), true, None);
+        apply_profile_by_name(dir.path(), "main", &options).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("cookies.txt")).unwrap(), jar);
+        assert!(!dir.path().join(LIVE_COOKIE_FILE).exists());
+        // Its backup is named after it, so directories sharing a backup folder don't collide
+        backup_live_cookies(dir.path(), "cookies.txt", &BackupPlan::InDirectory).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("cookies.txt.bak")).unwrap(), jar);
+        assert_eq!(scan_cookie_files(dir.path(), "cookies.txt").unwrap().len(), 1);
+        
+        // A saved target wins over detection
+        config.cookie_targets.insert(dir.path().to_path_buf(), "session".to_string());
+        assert_eq!(config.live_cookie_file(dir.path()), "session");
+    }
+    
+    #[test]
+    fn a_live_target_named_like_a_profile_is_not_scanned() {
+        let dir = directory_with(&[("cookies_main.txt", "m"), ("cookies_live", "l"), ("cookies_live.tmp", "t")]);
+        let mut config = Config::default();
+        config.cookie_targets.insert(dir.path().to_path_buf(), "cookies_live".to_string());
+        
+        let files = scan_cookie_files(dir.path(), &config.live_cookie_file(dir.path())).unwrap();
+        let names: Vec<String> = files.into_iter().map(|f| f.profile_name).collect();
+        assert_eq!(names, ["main"]);
+    }
+    
+    #[test]
+    fn shuffled_emojis_stay_distinct_while_the_palette_lasts() {
+        let config = Config::default();
+        let palette = config.emoji_palette();
+        assert_eq!(palette_emoji(&palette, 0), "🦆");
+        assert_eq!(palette_emoji(&palette, palette.len() + 1), "🐱");
+        assert_eq!(palette_emoji(&[], 3), FALLBACK_EMOJI);
+        
+        let emojis = shuffled_emojis(&palette, palette.len());
+        let distinct: std::collections::HashSet<&String> = emojis.iter().collect();
+        assert_eq!(distinct.len(), palette.len());
+        assert!(emojis.iter().all(|emoji| palette.contains(&emoji.as_str())));
+        
+        // More profiles than emojis reuses them, still drawn from the palette
+        assert_eq!(shuffled_emojis(&["🐸", "🐼"], 5).len(), 5);
+        assert_eq!(shuffled_emojis(&[], 2), vec![FALLBACK_EMOJI, FALLBACK_EMOJI]);
+    }
+    
+    #[test]
+    fn portable_mode_keeps_the_config_beside_the_executable() {
+        let stick = TempDir::new().unwrap();
+        let executable = stick.path().join("cookie_gui");
+        assert_eq!(portable_config_dir(&executable, false), None);
+        assert_eq!(portable_config_dir(&executable, true), Some(stick.path().join("sober-cookie-manager")));
+        
+        fs::write(stick.path().join(PORTABLE_MARKER_FILE), "").unwrap();
+        assert_eq!(portable_config_dir(&executable, false), Some(stick.path().join("sober-cookie-manager")));
+    }
+    
+    #[test]
+    fn polling_alone_stays_within_the_idle_frame_budget() {
+        let interval = Config::default().poll_interval();
+        let budget = idle_frame_budget(interval);
+        
+        // Woken only by the poll timer for a minute
+        let mut counter = IdleFrameCounter::default();
+        let mut windows = Vec::new();
+        for tick in 0..=30 {
+            windows.extend(counter.record(tick as f64 * interval.as_secs_f64(), true));
+        }
+        assert_eq!(windows.len(), 6);
+        assert!(windows.iter().all(|&count| count <= budget), "{:?} over {}", windows, budget);
+        
+        // Repainting every frame at 60 Hz is caught
+        let mut counter = IdleFrameCounter::default();
+        let spinning: Vec<u32> = (0..=60 * 20).filter_map(|frame| counter.record(frame as f64 / 60.0, true)).collect();
+        assert!(spinning.iter().all(|&count| count > budget));
+        
+        // Frames caused by input don't count
+        let mut counter = IdleFrameCounter::default();
+        let busy: Vec<u32> = (0..=60 * 20).filter_map(|frame| counter.record(frame as f64 / 60.0, false)).collect();
+        assert_eq!(busy, vec![0, 0]);
+        assert_eq!(counter.last_window, Some(0));
+    }
+    
+    #[test]
+    fn kept_sessions_are_restored_over_the_live_file() {
+        let dir = directory_with(&[("cookies", "throwaway"), ("cookies_main.txt", "main")]);
+        write_active_marker(dir.path(), "main");
+        
+        restore_live_session(dir.path(), b"kept", &options(BackupPlan::InDirectory, true)).unwrap();
+        assert_eq!(live(&dir), "kept");
+        assert_eq!(fs::read_to_string(dir.path().join("cookies.bak")).unwrap(), "throwaway");
+        assert_eq!(read_active_marker(dir.path()), None);
+        
+        // Frozen by the first restore, and still replaceable
+        restore_live_session(dir.path(), b"again", &options(BackupPlan::Skip, false)).unwrap();
+        assert_eq!(live(&dir), "again");
+    }
+    
+    #[test]
+    fn reset_keeps_cookie_files() {
+        let config_dir = directory_with(&[
+            ("directory.txt", "/tmp"),
+            ("config.json", "{}"),
+            ("profiles.json", "{}"),
+            ("sober-cookie-manager.log.1", "old"),
+            ("background.png", ""),
+        ]);
+        let dir = directory_with(&[
+            ("cookies", &token()),
+            ("cookies_main", &token()),
+            ("main.png", "mine"),
+            ("alt.png", "generated"),
+            ("user.png", "replaced"),
+            (DIRECTORY_CONFIG_FILE, "{}"),
+        ]);
+        write_active_marker(dir.path(), "main");
+        let checksum = |name: &str| file_checksum(&dir.path().join(name)).unwrap();
+        let generated = BTreeMap::from([
+            (dir.path().join("alt.png"), checksum("alt.png")),
+            // Replaced by the user since the manager saved it
+            (dir.path().join("user.png"), checksum("alt.png")),
+        ]);
+        
+        let removed = reset_manager_files(config_dir.path(), dir.path(), &generated).unwrap();
+        assert_eq!(removed.len(), 7);
+        for gone in ["config.json", "profiles.json", "sober-cookie-manager.log.1"] {
+            assert!(!config_dir.path().join(gone).exists(), "{} was kept", gone);
+        }
+        assert!(read_active_marker(dir.path()).is_none());
+        assert!(!dir.path().join("alt.png").exists());
+        for kept in ["cookies", "cookies_main", "main.png", "user.png"] {
+            assert!(dir.path().join(kept).exists(), "{} was removed", kept);
+        }
+        assert!(config_dir.path().join("background.png").exists());
+    }
+}
@@ -1,10 +1,484 @@
+mod core;
+
 use eframe::egui;
 use egui::{Color32, Vec2, Pos2, Rect, Rounding, Stroke, FontId, Align2, TextureHandle, ColorImage, TextureOptions};
+use global_hotkey::{hotkey::HotKey, GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
 use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::core::{
+    AppError, AvatarCrop, IdleFrameCounter, BackgroundStyle, BatchRename, Config, CookieComparison, DirectoryConfig, DirectorySnapshot, GameShortcut, GridAlignment, LegacyRename, ListedProfile, LoginResult, NameOverflow, NamePlacement,
+    ProfileConfig, ProfilesListing, RecentDirectory, ThemeConfig, WindowGeometry, DEFAULT_COOKIE_DIRECTORY,
+    DIRECTORY_CONFIG_FILE, LIVE_COOKIE_FILE, LOG_FILE_NAME, ROBLOSECURITY, URL_SCHEME, SortMode, SwitchMode, SwitchOptions, SwitchOutcome, MAX_AVATAR_BORDER_WIDTH, MAX_AVATAR_ZOOM, MAX_POLL_INTERVAL_SECS, TRASH_DIR,
+};
+
+const MAX_TOASTS: usize = 4;
+const TOAST_LIFETIME: Duration = Duration::from_secs(4);
+const TOAST_FADE: Duration = Duration::from_millis(600);
+
+const LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+const LARGE_COOKIE_FILE_BYTES: u64 = 64 * 1024;
+
+// Animated avatars keep at most this many frames, each scaled to fit this many pixels a side
+const MAX_AVATAR_FRAMES: usize = 64;
+const MAX_AVATAR_FRAME_SIDE: u32 = 128;
+const MAX_ANIMATION_CANVAS_SIDE: u32 = 2048;
+const AVATAR_LOADER_THREADS: usize = 4;
+
+// freedesktop sound theme name, played through the notification or canberra-gtk-play
+const SWITCH_SOUND: &str = "complete";
+
+const AUTHENTICATED_USER_URL: &str = "https://users.roblox.com/v1/users/authenticated";
+// Give Sober time to start and log in before checking the session
+const LOGIN_CHECK_DELAY: Duration = Duration::from_secs(15);
+// At most one post-launch check per minute, however often profiles are switched
+const LOGIN_CHECK_COOLDOWN: Duration = Duration::from_secs(60);
+// What fetch_authenticated_user reports for a rejected token, as opposed to a network problem
+const NOT_LOGGED_IN: &str = "Session is not logged in";
+
+// Where other Sober installs keep their data, checked alongside recently used directories
+const OTHER_INSTALL_DIRECTORIES: &[&str] = &[
+    DEFAULT_COOKIE_DIRECTORY,
+    "~/.var/app/org.vinegarhq.Sober/.local/share/sober/",
+    "~/.local/share/sober/",
+];
+
+// How long a directory's profile count in the recent directories list is trusted
+const DIRECTORY_COUNT_TTL: Duration = Duration::from_secs(10);
+
+// Side of the avatar shown in a profile's hover card
+const HOVER_CARD_IMAGE_SIZE: f32 = 120.0;
+
+// Longest nickname that still fits on a small avatar
+const MAX_NICKNAME_LENGTH: usize = 6;
+
+// Side of the framing preview in the details window
+const AVATAR_EDITOR_SIZE: f32 = 96.0;
+
+// Narrower than this, the profile grid gives way to the dropdown
+const DROPDOWN_ONLY_WIDTH: f32 = 300.0;
+
+// Avatar size and padding of the mini mode strip
+const MINI_AVATAR_SIZE: f32 = 44.0;
+const MINI_PADDING: f32 = 8.0;
+// Window size changes take a few frames to be reported back; don't record geometry until then
+const WINDOW_MODE_SETTLE: Duration = Duration::from_millis(500);
+
+const USAGE: &str = "Usage: cookie_gui [--dir <path>] [--list] [--switch <profile|alias>] [--no-backup] [--backup-dir <path>] [--read-only] [--portable] [sober-switch://<profile>]
+
+--read-only opens the window without writing any files: profiles can be browsed and compared only.
+--portable, or a portable.txt next to the executable, keeps settings and logs in a sober-cookie-manager
+folder beside the executable instead of ~/.config.
+A sober-switch://<profile> link switches in the window that is already open, or opens one;
+sober-switch://<profile>?all asks to switch every recently used directory that has that profile.
+
+Set SOBER_PROFILE=<profile|alias> to switch on startup, with or without the window;
+an explicit --switch takes precedence.";
+
+// Appends timestamped records to a log file in the config dir, keeping one rotated copy
+struct FileLogger {
+    path: PathBuf,
+    level: log::LevelFilter,
+    file: Mutex<Option<fs::File>>,
+}
+
+impl FileLogger {
+    fn open(path: &Path) -> Option<fs::File> {
+        fs::OpenOptions::new().create(true).append(true).open(path).ok()
+    }
+    
+    fn rotate_if_needed(&self, file: &mut Option<fs::File>, incoming: usize) {
+        let size = file.as_ref().and_then(|f| f.metadata().ok()).map(|m| m.len()).unwrap_or(0);
+        if size + incoming as u64 > LOG_MAX_BYTES {
+            *file = None;
+            fs::rename(&self.path, self.path.with_extension("log.1")).ok();
+            *file = Self::open(&self.path);
+        }
+    }
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        // Only warnings from eframe/winit/etc., everything at the configured level from this app
+        let limit = if metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            self.level
+        } else {
+            log::LevelFilter::Warn
+        };
+        metadata.level() <= limit
+    }
+    
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        
+        // Problems still show up on the terminal as they did before
+        if record.level() <= log::Level::Warn {
+            eprintln!("{}", record.args());
+        }
+        
+        let line = format!(
+            "{} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.args()
+        );
+        
+        if let Ok(mut file) = self.file.lock() {
+            self.rotate_if_needed(&mut file, line.len());
+            if let Some(file) = file.as_mut() {
+                file.write_all(line.as_bytes()).ok();
+            }
+        }
+    }
+    
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            if let Some(file) = file.as_mut() {
+                file.flush().ok();
+            }
+        }
+    }
+}
+
+// SOBER_LOG=debug (or trace/warn/off) overrides the default info level
+fn init_logging() {
+    let level = env::var("SOBER_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(log::LevelFilter::Info);
+    let path = core::get_config_dir().join(LOG_FILE_NAME);
+    let logger = FileLogger {
+        file: Mutex::new(FileLogger::open(&path)),
+        path,
+        level,
+    };
+    
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(log::LevelFilter::Trace);
+    }
+}
+
+#[derive(Clone)]
+struct CliArgs {
+    dir: Option<PathBuf>,
+    switch: Option<String>,
+    list: bool,
+    help: bool,
+    // Session-only overrides of the config's backup settings
+    no_backup: bool,
+    backup_dir: Option<PathBuf>,
+    // Nothing is written for the whole session
+    read_only: bool,
+    portable: bool,
+    // A sober-switch:// link, as passed by the desktop when one is opened
+    link: Option<String>,
+    // From SOBER_PROFILE, ignored when --switch is given
+    env_profile: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    let mut cli = CliArgs { dir: None, switch: None, list: false, help: false, no_backup: false, backup_dir: None, read_only: false, portable: false, link: None, env_profile: None };
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--switch" => {
+                let name = iter.next().ok_or("--switch requires a profile name or alias")?;
+                cli.switch = Some(name.clone());
+            }
+            "--dir" => {
+                let dir = iter.next().ok_or("--dir requires a path")?;
+                cli.dir = Some(core::expand_path(dir));
+            }
+            "--backup-dir" => {
+                let dir = iter.next().ok_or("--backup-dir requires a path")?;
+                cli.backup_dir = Some(core::expand_path(dir));
+            }
+            "--no-backup" => cli.no_backup = true,
+            "--read-only" => cli.read_only = true,
+            "--portable" => cli.portable = true,
+            "--list" => cli.list = true,
+            "--help" | "-h" => cli.help = true,
+            other if other.split_once(':').is_some_and(|(scheme, _)| scheme.eq_ignore_ascii_case(URL_SCHEME)) => {
+                cli.link = Some(other.to_string());
+            }
+            other => return Err(format!("Unknown argument: {}\n{}", other, USAGE)),
+        }
+    }
+
+    Ok(cli)
+}
+
+// Longest link accepted from another instance
+const MAX_FORWARDED_LINK_BYTES: u64 = 1024;
+
+// Hand a link to the window that is already open; false when there is none
+fn forward_link(link: &str) -> bool {
+    UnixStream::connect(core::instance_socket_path())
+        .and_then(|mut stream| stream.write_all(link.as_bytes()))
+        .is_ok()
+}
+
+// Accept links forwarded by later launches; they are parsed and checked on arrival like any other
+fn start_link_listener(ctx: &egui::Context) -> Option<Receiver<String>> {
+    let path = core::instance_socket_path();
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            if UnixStream::connect(&path).is_ok() {
+                log::warn!("Another window is already receiving {}:// links", URL_SCHEME);
+                return None;
+            }
+            // Left behind by a window that didn't exit cleanly
+            fs::remove_file(&path).ok();
+            UnixListener::bind(&path)
+                .map_err(|e| log::warn!("Could not listen for {}:// links: {}", URL_SCHEME, e))
+                .ok()?
+        }
+        Err(e) => {
+            log::warn!("Could not listen for {}:// links: {}", URL_SCHEME, e);
+            return None;
+        }
+    };
+    
+    let (sender, receiver) = mpsc::channel();
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut link = String::new();
+            if stream.take(MAX_FORWARDED_LINK_BYTES).read_to_string(&mut link).is_err() || link.trim().is_empty() {
+                continue;
+            }
+            if sender.send(link.trim().to_string()).is_err() {
+                return;
+            }
+            ctx.request_repaint();
+        }
+    });
+    Some(receiver)
+}
+
+// The directory, config and profiles the command line works on; errors are printed here
+fn load_cli_profiles(cli: &CliArgs) -> Option<(PathBuf, Config, Vec<Profile>)> {
+    let cookie_directory = cli.dir.clone().unwrap_or_else(|| {
+        let (directory, notice) = core::load_saved_directory();
+        if let Some(notice) = notice {
+            log::warn!("{}", notice);
+        }
+        directory
+    });
+    let (config, config_warning) = Config::load(cli.read_only);
+    if let Some(warning) = config_warning {
+        log::warn!("{}", warning);
+    }
+
+    let mut warnings = Vec::new();
+    let directory_config = DirectoryConfig::load(&cookie_directory).unwrap_or_else(|e| {
+        warnings.push(e);
+        None
+    });
+    let profile_configs = core::profile_configs(&config, directory_config.as_ref());
+
+    let (profiles, scan_warnings) = match SoberApp::scan_profiles(&cookie_directory, &config.live_cookie_file(&cookie_directory), profile_configs, &config.emoji_palette()) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            return None;
+        }
+    };
+
+    warnings.extend(scan_warnings);
+    for warning in &warnings {
+        eprintln!("{}", warning);
+    }
+    Some((cookie_directory, config, profiles))
+}
+
+// Headless mode: list or switch profiles without opening the window
+fn run_cli(cli: &CliArgs) -> i32 {
+    let Some((cookie_directory, mut config, profiles)) = load_cli_profiles(cli) else {
+        return 1;
+    };
+
+    if cli.list {
+        for profile in &profiles {
+            if profile.aliases.is_empty() {
+                println!("{}", profile.name);
+            } else {
+                println!("{} ({})", profile.name, profile.aliases.join(", "));
+            }
+        }
+    }
+
+    if let Some(query) = cli.switch.as_ref().or(cli.env_profile.as_ref()) {
+        if cli.read_only || config.read_only {
+            eprintln!("Switching is disabled in read-only mode");
+            return if cli.switch.is_some() { 1 } else { 0 };
+        }
+        let profile = match SoberApp::resolve_profile(&profiles, query) {
+            Ok(profile) => profile,
+            // A stale SOBER_PROFILE shouldn't fail a --list
+            Err(e) if cli.switch.is_none() => {
+                eprintln!("SOBER_PROFILE: {}", e);
+                return 0;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        };
+
+        let options = SwitchOptions::new(&config, &cookie_directory, cli.no_backup, cli.backup_dir.as_deref());
+        match core::apply_profile_by_name(&cookie_directory, &profile.name, &options) {
+            Ok(SwitchOutcome::AlreadyActive) => {
+                println!("{} is already active", profile.display_name);
+                if !mirror_cli_switch(&cookie_directory, &config) {
+                    return 1;
+                }
+            }
+            Ok(SwitchOutcome::Switched { backup_warning }) => {
+                if let Some(warning) = backup_warning {
+                    eprintln!("{}", warning);
+                }
+                config.record_switch(&profile.name);
+                if let Err(e) = config.save() {
+                    log::error!("Failed to save switch history: {}", e);
+                }
+                log::info!("Switched to {} profile from the command line", profile.display_name);
+                println!("Switched to {} profile", profile.display_name);
+                if !mirror_cli_switch(&cookie_directory, &config) {
+                    return 1;
+                }
+                if config.auto_launch {
+                    if let Err(e) = SoberApp::launch_sober(&config.launch_command, None) {
+                        eprintln!("Failed to launch Sober: {}", e);
+                        return 1;
+                    }
+                }
+            }
+            Err(e @ AppError::BackupFailed { .. }) => {
+                eprintln!("{}, not switching", e);
+                return 1;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        }
+    }
+
+    0
+}
+
+// False when any mirror target couldn't be written; the switch itself has already happened
+fn mirror_cli_switch(cookie_directory: &Path, config: &Config) -> bool {
+    let mut ok = true;
+    for (_, result) in core::mirror_live_cookies(
+        cookie_directory,
+        &config.live_cookie_file(cookie_directory),
+        &config.mirror_targets(),
+        config.freeze_live_cookies
+    ) {
+        if let Err(e) = result {
+            eprintln!("Could not mirror the switch: {}", e);
+            ok = false;
+        }
+    }
+    ok
+}
+
+// Used when the window can't open, e.g. over SSH or without a working GPU: pick a profile by number
+fn run_text_mode(cli: &CliArgs) -> i32 {
+    let Some((cookie_directory, config, profiles)) = load_cli_profiles(cli) else {
+        return 1;
+    };
+    if profiles.is_empty() {
+        println!("No profiles found in {}", cookie_directory.display());
+        return 0;
+    }
+
+    let active = SoberApp::detect_active_profile(&cookie_directory, &config.live_cookie_file(&cookie_directory), &profiles);
+    for (index, profile) in profiles.iter().enumerate() {
+        let marker = if active == Some(index) { " (active)" } else { "" };
+        println!("{:>3}. {}{}", index + 1, profile.display_name, marker);
+    }
+    print!("Switch to (number or name, empty to quit): ");
+    io::stdout().flush().ok();
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() || answer.trim().is_empty() {
+        return 0;
+    }
+    let answer = answer.trim();
+    let name = match answer.parse::<usize>() {
+        Ok(number) => match number.checked_sub(1).and_then(|index| profiles.get(index)) {
+            Some(profile) => profile.name.clone(),
+            None => {
+                eprintln!("There is no profile {}", number);
+                return 1;
+            }
+        },
+        Err(_) => answer.to_string(),
+    };
+    run_cli(&CliArgs { switch: Some(name), list: false, env_profile: None, ..cli.clone() })
+}
 
 fn main() -> Result<(), eframe::Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut cli = match parse_args(&args) {
+        Ok(cli) => cli,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+    
+    // Decided before logging starts, since the log lives in the config dir too
+    let portable = env::current_exe().ok().and_then(|executable| core::portable_config_dir(&executable, cli.portable));
+    if let Some(config_dir) = portable {
+        core::enable_portable_mode(config_dir);
+    }
+    init_logging();
+    if core::is_portable() {
+        log::info!("Portable mode: keeping settings in {}", core::get_config_dir().display());
+    } else if cli.portable {
+        log::warn!("--portable ignored: the executable's location is unknown");
+    }
+
+    if cli.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    cli.env_profile = env::var("SOBER_PROFILE").ok().filter(|name| !name.trim().is_empty());
+    if cli.switch.is_some() && cli.env_profile.is_some() {
+        log::info!("--switch given, ignoring SOBER_PROFILE");
+        cli.env_profile = None;
+    }
+
+    if cli.list || cli.switch.is_some() {
+        std::process::exit(run_cli(&cli));
+    }
+    
+    if cli.link.as_deref().is_some_and(forward_link) {
+        return Ok(());
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([520.0, 360.0])
@@ -13,254 +487,4853 @@ fn main() -> Result<(), eframe::Error> {
         ..Default::default()
     };
 
-    eframe::run_native(
+    // --dir and the backup flags apply to this session only; a --dir override isn't saved to directory.txt
+    let gui_cli = cli.clone();
+    let result = eframe::run_native(
         "Sober - Who's Playing?",
         options,
-        Box::new(|cc| Box::new(SoberApp::new(cc))),
-    )
+        Box::new(|cc| Box::new(SoberApp::new(cc, gui_cli))),
+    );
+
+    // No display or no usable GPU context; the profiles can still be switched from the terminal
+    if let Err(e) = &result {
+        // Errors are echoed to the terminal by the logger too
+        log::error!("Could not open the window: {}", e);
+        if io::stdin().is_terminal() {
+            eprintln!("Falling back to text mode.");
+            std::process::exit(run_text_mode(&cli));
+        }
+        eprintln!("Use --list and --switch <profile> to manage profiles without the window.");
+        std::process::exit(1);
+    }
+    result
 }
 
 #[derive(Clone)]
 struct Profile {
     name: String,
-    cookie_file: String,
+    // The real file name, which may not be valid UTF-8
+    cookie_file: OsString,
     display_name: String,
     emoji: String,
+    aliases: Vec<String>,
+    favorite: bool,
+    notes: String,
+    locked: bool,
+    color_tag: Option<[u8; 3]>,
+    last_login: Option<LoginResult>,
+    // Roblox username fetched from the cookie; when set it is also the display name
+    username: Option<String>,
+    remind_at: Option<u64>,
+    hotkey: Option<Chord>,
+    crop: AvatarCrop,
+    // As configured; empty shows the initials
+    nickname: String,
+    games: Vec<GameShortcut>,
+    added_at: Option<u64>,
+    // The cookie file no longer matches the checksum recorded for it
+    modified_externally: bool,
+    file_size: u64,
     image: Option<TextureHandle>,
+    // Set for multi-frame avatars; image then holds the first frame
+    animation: Option<Arc<AvatarAnimation>>,
 }
 
-struct SoberApp {
-    profiles: Vec<Profile>,
-    selected_profile: Option<usize>,
-    sober_logo: Option<TextureHandle>,
-    error_message: Option<String>,
-    cookie_directory: PathBuf,
-    show_directory_dialog: bool,
-    temp_directory_input: String,
+// An avatar file decoded off the main thread, ready to become textures
+enum DecodedAvatar {
+    Still(ColorImage),
+    Animated(Vec<(ColorImage, Duration)>),
+    Failed,
 }
 
-impl SoberApp {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let ctx = &cc.egui_ctx;
-        
-        // Load Sober logo if available
-        let sober_logo = Self::load_image_from_path(ctx, "Sober_logo.png");
-        
-        // Load saved directory or use default
-        let cookie_directory = Self::load_saved_directory();
+// Avatars being decoded on worker threads; poll_avatars uploads them as they arrive
+struct AvatarLoader {
+    receiver: Receiver<(String, PathBuf, DecodedAvatar)>,
+    // Set when a newer load replaces this one, so the workers stop taking files
+    cancelled: Arc<AtomicBool>,
+    // Profile names still waiting for their avatar
+    pending: HashSet<String>,
+}
+
+// Decoded frames of an animated GIF or APNG avatar
+struct AvatarAnimation {
+    frames: Vec<TextureHandle>,
+    // When each frame ends, counted from the start of the loop
+    ends: Vec<Duration>,
+}
+
+impl AvatarAnimation {
+    // The frame showing at time (seconds) and how long until the next one
+    fn frame_at(&self, time: f64) -> (&TextureHandle, Duration) {
+        let total = self.ends.last().copied().unwrap_or_default();
+        if total.is_zero() {
+            return (&self.frames[0], Duration::MAX);
+        }
         
-        let mut app = Self {
-            profiles: Vec::new(),
-            selected_profile: None,
-            sober_logo,
-            error_message: None,
-            cookie_directory: cookie_directory.clone(),
-            show_directory_dialog: false,
-            temp_directory_input: cookie_directory.to_string_lossy().to_string(),
+        let position = Duration::from_secs_f64(time % total.as_secs_f64());
+        let index = self.ends.iter().position(|&end| position < end).unwrap_or(0);
+        (&self.frames[index], self.ends[index].saturating_sub(position))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ProfileAction {
+    Switch,
+    // Index into the profile's game shortcuts
+    LaunchGame(usize),
+    SwitchEverywhere,
+    ShowDetails,
+    Rename,
+    EditAliases,
+    EditNickname,
+    EditGames,
+    EditNotes,
+    ToggleFavorite,
+    ToggleLock,
+    EditColorTag,
+    ClearColorTag,
+    AcceptChecksum,
+    SetReminder,
+    SnoozeReminder,
+    ClearReminder,
+    Duplicate,
+    Compare,
+    Export,
+    Archive,
+    Delete,
+}
+
+// Password prompt for locking the other profiles away or bringing them back
+enum VaultDialog {
+    Lock { keep: String, password: String, confirm: String },
+    Unlock { password: String },
+}
+
+// What the naming dialog saves: a pasted token gets a fresh cookie jar, while the live session is
+// copied as it is so none of its other cookies are lost
+enum NewProfileSource {
+    Token(String),
+    LiveSession,
+}
+
+// The live session as it was when kept, for getting back to it after a throwaway login; never written
+// anywhere until restored, and gone when the app closes
+struct ScratchSession {
+    directory: PathBuf,
+    contents: Vec<u8>,
+    kept_at: u64,
+}
+
+// Two profiles side by side; the second is picked in the window
+struct ProfileComparison {
+    first: String,
+    second: Option<String>,
+    // Worked out once per pick rather than every frame
+    result: Option<Result<CookieComparison, String>>,
+}
+
+#[derive(Clone, Copy)]
+enum ToastKind {
+    Success,
+    Warning,
+    Error,
+}
+
+struct Toast {
+    message: String,
+    kind: ToastKind,
+    created: Instant,
+}
+
+// One profile's result from Verify All
+struct VerifyResult {
+    profile_name: String,
+    cookie_file: OsString,
+    health: CookieHealth,
+    username: Option<String>,
+}
+
+// Result of checking one cookie file with the validator
+#[derive(Clone)]
+enum CookieHealth {
+    Valid,
+    Empty,
+    MissingToken,
+    Malformed(String),
+    Unreadable(String),
+}
+
+impl CookieHealth {
+    fn check_file(path: &Path) -> Self {
+        let contents = match core::read_cookie_text(path) {
+            Ok(contents) => contents,
+            Err(e) => return Self::Unreadable(e.to_string()),
         };
         
-        // Auto-curate profiles from cookie files
-        app.load_profiles(ctx);
+        if contents.trim().is_empty() {
+            return Self::Empty;
+        }
+        if core::parse_roblosecurity(&contents).is_none_or(|token| token.is_empty()) {
+            return Self::MissingToken;
+        }
         
-        app
+        match core::validate_cookie(&contents) {
+            Ok(_) => Self::Valid,
+            Err(reason) => Self::Malformed(reason),
+        }
     }
     
-    fn load_saved_directory() -> PathBuf {
-        let config_path = Self::get_config_file_path();
-        
-        if let Ok(saved_dir) = fs::read_to_string(&config_path) {
-            let saved_dir = saved_dir.trim();
-            if !saved_dir.is_empty() {
-                let expanded_path = Self::expand_path(saved_dir);
-                if expanded_path.exists() {
-                    return expanded_path;
-                }
-            }
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Valid => "valid",
+            Self::Empty => "empty",
+            Self::MissingToken => "missing token",
+            Self::Malformed(_) => "malformed",
+            Self::Unreadable(_) => "unreadable",
         }
-        
-        // Default directory
-        Self::expand_path("~/.var/app/org.vinegarhq.Sober/data/sober/")
     }
     
-    fn get_config_file_path() -> PathBuf {
-        let mut config_path = dirs::config_dir().unwrap_or_else(|| {
-            env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
-        });
-        config_path.push("sober-cookie-manager");
-        fs::create_dir_all(&config_path).ok();
-        config_path.push("directory.txt");
-        config_path
+    fn description(&self) -> String {
+        match self {
+            Self::Malformed(reason) | Self::Unreadable(reason) => format!("{}: {}", self.label(), reason),
+            _ => self.label().to_string(),
+        }
+    }
+}
+
+// Profile found in another Sober data directory, offered for import
+struct ForeignProfile {
+    directory: PathBuf,
+    file_name: OsString,
+    profile_name: String,
+    // A profile of the same name already exists here, so it would be skipped
+    collides: bool,
+    selected: bool,
+}
+
+// Cookie file being imported from elsewhere on disk
+#[derive(Default)]
+struct ImportDialog {
+    source: String,
+    name: String,
+    move_source: bool,
+    confirm_move: bool,
+    // The source is a Cookie-Editor JSON export, converted rather than copied
+    cookie_editor: bool,
+}
+
+// Tray menu clicks, forwarded to the UI thread
+enum TrayEvent {
+    Show,
+    Quit,
+}
+
+struct SoberTray {
+    sender: mpsc::Sender<TrayEvent>,
+    ctx: egui::Context,
+}
+
+impl SoberTray {
+    fn send(&self, event: TrayEvent) {
+        self.sender.send(event).ok();
+        self.ctx.request_repaint();
+    }
+}
+
+impl ksni::Tray for SoberTray {
+    fn id(&self) -> String {
+        "sober-profile-changer".to_string()
+    }
+    
+    fn title(&self) -> String {
+        "Sober - Who's Playing?".to_string()
+    }
+    
+    fn icon_name(&self) -> String {
+        "system-users".to_string()
+    }
+    
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.send(TrayEvent::Show);
     }
     
-    fn expand_path(path: &str) -> PathBuf {
-        if path.starts_with("~/") {
-            if let Some(home_dir) = dirs::home_dir() {
-                return home_dir.join(&path[2..]);
+    fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+        vec![
+            ksni::menu::StandardItem {
+                label: "Show".to_string(),
+                activate: Box::new(|tray: &mut Self| tray.send(TrayEvent::Show)),
+                ..Default::default()
             }
-        }
-        PathBuf::from(path)
+            .into(),
+            ksni::MenuItem::Separator,
+            ksni::menu::StandardItem {
+                label: "Quit".to_string(),
+                icon_name: "application-exit".to_string(),
+                activate: Box::new(|tray: &mut Self| tray.send(TrayEvent::Quit)),
+                ..Default::default()
+            }
+            .into(),
+        ]
+    }
+}
+
+// A profile's own shortcut; Ctrl and Cmd count as the same modifier
+#[derive(Clone, Copy, PartialEq)]
+struct Chord {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    key: egui::Key,
+}
+
+// Shortcuts the app already uses, which profiles can't take
+const RESERVED_CHORDS: [(Chord, &str); 8] = [
+    (Chord::ctrl(egui::Key::K), "the command palette"),
+    (Chord::ctrl(egui::Key::P), "switching to the previous profile"),
+    (Chord { ctrl: true, alt: false, shift: true, key: egui::Key::R }, "rescanning images"),
+    (Chord::ctrl(egui::Key::A), "text editing"),
+    (Chord::ctrl(egui::Key::C), "text editing"),
+    (Chord::ctrl(egui::Key::V), "text editing"),
+    (Chord::ctrl(egui::Key::X), "text editing"),
+    (Chord::ctrl(egui::Key::Z), "text editing"),
+];
+
+impl Chord {
+    const fn ctrl(key: egui::Key) -> Self {
+        Self { ctrl: true, alt: false, shift: false, key }
     }
     
-    fn save_directory(&self) {
-        let config_path = Self::get_config_file_path();
-        if let Err(e) = fs::write(&config_path, self.cookie_directory.to_string_lossy().as_ref()) {
-            eprintln!("Failed to save directory preference: {}", e);
+    fn from_input(modifiers: egui::Modifiers, key: egui::Key) -> Self {
+        Self {
+            ctrl: modifiers.ctrl || modifiers.command,
+            alt: modifiers.alt,
+            shift: modifiers.shift,
+            key,
         }
     }
     
-    fn load_profiles(&mut self, ctx: &egui::Context) {
-        self.profiles.clear();
-        self.error_message = None;
+    // Accepts the stored form as well as lowercase global hotkey style ("ctrl+alt+s")
+    fn parse(text: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key_name = parts.pop()?;
+        let key = egui::Key::from_name(key_name).or_else(|| egui::Key::from_name(&key_name.to_uppercase()))?;
         
-        // Scan cookie directory for cookies_* files
-        match fs::read_dir(&self.cookie_directory) {
-            Ok(entries) => {
-                let mut cookie_files = Vec::new();
-                
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                            if file_name.starts_with("cookies_") && (file_name.ends_with(".txt") || !file_name.contains('.')) {
-                                // Extract profile name from filename
-                                let profile_name = if file_name.ends_with(".txt") {
-                                    file_name
-                                        .strip_prefix("cookies_")
-                                        .and_then(|s| s.strip_suffix(".txt"))
-                                        .unwrap_or("Unknown")
-                                        .to_string()
-                                } else {
-                                    file_name
-                                        .strip_prefix("cookies_")
-                                        .unwrap_or("Unknown")
-                                        .to_string()
-                                };
-                                
-                                cookie_files.push((profile_name, file_name.to_string()));
-                            }
-                        }
+        let mut chord = Self { ctrl: false, alt: false, shift: false, key };
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" | "command" => chord.ctrl = true,
+                "alt" | "option" => chord.alt = true,
+                "shift" => chord.shift = true,
+                _ => return None,
+            }
+        }
+        Some(chord)
+    }
+    
+    // Plain keys would fire while typing in a text field, so a chord needs Ctrl or Alt unless it's a function key
+    fn is_usable(&self) -> bool {
+        let name = self.key.name();
+        let function_key = name.len() > 1 && name.starts_with('F') && name[1..].chars().all(|c| c.is_ascii_digit());
+        self.ctrl || self.alt || function_key
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [(self.ctrl, "Ctrl+"), (self.alt, "Alt+"), (self.shift, "Shift+")] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(self.key.name())
+    }
+}
+
+// Ctrl+K launcher: the typed query and the row Enter would run
+#[derive(Default)]
+struct CommandPalette {
+    query: String,
+    highlighted: usize,
+}
+
+#[derive(Clone, Copy)]
+enum PaletteCommand {
+    Switch(usize),
+    ChangeDirectory,
+    Refresh,
+    RescanImages,
+    PasteCookie,
+    VerifyAll,
+    Settings,
+    MiniMode,
+    SwitchPrevious,
+}
+
+// Listed after the matching profiles
+const PALETTE_ACTIONS: [(&str, PaletteCommand); 8] = [
+    ("⇄ Switch to previous profile", PaletteCommand::SwitchPrevious),
+    ("📁 Change directory", PaletteCommand::ChangeDirectory),
+    ("↻ Refresh profiles", PaletteCommand::Refresh),
+    ("🖼 Rescan images", PaletteCommand::RescanImages),
+    ("📋 Paste cookie", PaletteCommand::PasteCookie),
+    ("🩺 Verify all", PaletteCommand::VerifyAll),
+    ("⚙ Settings", PaletteCommand::Settings),
+    ("🗕 Mini mode", PaletteCommand::MiniMode),
+];
+
+// Square grid of QR modules, row by row
+struct QrModules {
+    width: usize,
+    dark: Vec<bool>,
+}
+
+// Escape, and Enter while no field or button has focus, for the open inline dialog
+#[derive(Clone, Copy, Default)]
+struct DialogKeys {
+    confirm: bool,
+    cancel: bool,
+}
+
+// Inline dialogs opened from the profile context menu, keyed by profile name so a refresh can't retarget them
+enum ProfileDialog {
+    Rename { name: String, input: String },
+    Aliases { name: String, input: String },
+    Nickname { name: String, input: String },
+    Games { name: String, input: String },
+    Notes { name: String, input: String },
+    Reminder { name: String, input: String },
+    ColorTag { name: String, color: [u8; 3] },
+    Export { name: String, input: String },
+    ConfirmDelete { name: String },
+    // locked_name is set when a locked profile is active and would be replaced
+    ConfirmSwitch { name: String, locked_name: Option<String> },
+    // Sober was running when the switch was asked for and require_sober_closed is on
+    ForceSwitch { name: String },
+    // Every directory that has the profile, current one included
+    ConfirmBroadcast { name: String, directories: Vec<PathBuf> },
+}
+
+impl ThemeConfig {
+    fn panel_color(&self) -> Color32 {
+        let [r, g, b] = self.panel;
+        Color32::from_rgb(r, g, b)
+    }
+    
+    fn accent_color(&self) -> Color32 {
+        let [r, g, b] = self.accent;
+        Color32::from_rgb(r, g, b)
+    }
+    
+    // Brighter accent for outlines drawn on top of an accent fill
+    fn accent_border(&self) -> Color32 {
+        let [r, g, b] = self.accent.map(|c| c.saturating_add(30));
+        Color32::from_rgb(r, g, b)
+    }
+    
+    fn visuals(&self) -> egui::Visuals {
+        let mut visuals = egui::Visuals::dark();
+        visuals.widgets.noninteractive.bg_stroke = Stroke::NONE;
+        visuals.widgets.inactive.bg_stroke = Stroke::NONE;
+        visuals.widgets.hovered.bg_stroke = Stroke::NONE;
+        visuals.widgets.active.bg_stroke = Stroke::NONE;
+        visuals.widgets.open.bg_stroke = Stroke::NONE;
+        visuals.panel_fill = self.panel_color();
+        visuals.selection.bg_fill = self.accent_color();
+        visuals
+    }
+}
+
+struct SoberApp {
+    profiles: Vec<Profile>,
+    selected_profile: Option<usize>,
+    // Profile clicked once in double-click mode; highlighted until it is clicked again
+    armed_profile: Option<usize>,
+    // Profile picked in the dropdown but not switched to yet
+    dropdown_profile: Option<usize>,
+    sober_logo: Option<TextureHandle>,
+    toasts: Vec<Toast>,
+    scan_error: Option<AppError>,
+    cookie_directory: PathBuf,
+    // Directory the current avatars were loaded from
+    avatars_directory: PathBuf,
+    avatar_loader: Option<AvatarLoader>,
+    // Worked out on each load, for the chip next to the path
+    flatpak_directory: bool,
+    show_directory_dialog: bool,
+    recent_directories: Vec<RecentDirectory>,
+    directory_counts: HashMap<String, (Instant, Option<usize>)>,
+    temp_directory_input: String,
+    // The live file name being typed in settings, committed once the field loses focus
+    live_file_input: Option<String>,
+    config: Config,
+    directory_config: Option<DirectoryConfig>,
+    search_query: String,
+    pasted_cookie: Option<NewProfileSource>,
+    pasted_profile_name: String,
+    import_dialog: Option<ImportDialog>,
+    profile_dialog: Option<ProfileDialog>,
+    cookie_health: HashMap<OsString, CookieHealth>,
+    verify_receiver: Option<Receiver<VerifyResult>>,
+    show_settings: bool,
+    background_image: Option<TextureHandle>,
+    detail_profile: Option<String>,
+    // QR code of the token of the profile in the details window, keyed by profile name
+    detail_qr: Option<(String, Result<QrModules, String>)>,
+    comparison: Option<ProfileComparison>,
+    vault_dialog: Option<VaultDialog>,
+    // Other profiles are locked in the cookie directory's vault
+    profiles_locked: bool,
+    // Profile whose hotkey is being recorded from the next key press
+    recording_hotkey: Option<String>,
+    // Preview shown in settings until applied or dismissed
+    legacy_renames: Option<Vec<LegacyRename>>,
+    batch_find: String,
+    other_installs: Option<Vec<ForeignProfile>>,
+    batch_replace: String,
+    // Typed confirmation for resetting the manager, while the prompt is open
+    confirm_reset: Option<String>,
+    show_onboarding: bool,
+    hotkey_manager: Option<GlobalHotKeyManager>,
+    registered_hotkey: Option<HotKey>,
+    hotkey_pressed: Arc<AtomicBool>,
+    quick_switch_open: bool,
+    command_palette: Option<CommandPalette>,
+    quick_switch_was_minimized: bool,
+    tray: Option<ksni::blocking::Handle<SoberTray>>,
+    tray_receiver: Option<Receiver<TrayEvent>>,
+    hidden_to_tray: bool,
+    // Last seen mtime of config.json, so external edits can be picked up live
+    config_modified: Option<SystemTime>,
+    // Every periodic file check runs off this one timer, so the window sleeps in between
+    last_poll: Instant,
+    live_modified: Option<SystemTime>,
+    live_exists: bool,
+    idle_frames: IdleFrameCounter,
+    login_receiver: Option<Receiver<(String, Result<String, String>)>>,
+    identity_receiver: Option<Receiver<(String, Result<String, String>)>>,
+    // Shown as a banner until dismissed or a switch replaces the session
+    session_warning: Option<String>,
+    session_check_receiver: Option<Receiver<Result<String, String>>>,
+    link_receiver: Option<Receiver<String>>,
+    // Place the next switch launches into, set by a game shortcut
+    launch_place: Option<GameShortcut>,
+    scratch_session: Option<ScratchSession>,
+    last_login_check: Option<Instant>,
+    window_mode_changed: Instant,
+    cli_no_backup: bool,
+    cli_backup_dir: Option<PathBuf>,
+    cli_read_only: bool,
+}
+
+impl SoberApp {
+    fn new(cc: &eframe::CreationContext<'_>, cli: CliArgs) -> Self {
+        let ctx = &cc.egui_ctx;
+        
+        
+        // Use the --dir override, else the saved directory or the default
+        let mut directory_notice = None;
+        let cookie_directory = cli.dir.unwrap_or_else(|| {
+            let (directory, notice) = core::load_saved_directory();
+            directory_notice = notice;
+            directory
+        });
+        
+        let (config, config_warning) = Config::load(cli.read_only);
+        let sober_logo = Self::load_logo(ctx, &config.theme.logo_path);
+        let show_onboarding = !config.onboarding_complete;
+        
+        let mut app = Self {
+            profiles: Vec::new(),
+            selected_profile: None,
+            armed_profile: None,
+            dropdown_profile: None,
+            sober_logo,
+            toasts: Vec::new(),
+            scan_error: None,
+            cookie_directory: cookie_directory.clone(),
+            avatars_directory: PathBuf::new(),
+            avatar_loader: None,
+            flatpak_directory: false,
+            show_directory_dialog: false,
+            recent_directories: Vec::new(),
+            directory_counts: HashMap::new(),
+            temp_directory_input: cookie_directory.to_string_lossy().to_string(),
+            live_file_input: None,
+            config,
+            directory_config: None,
+            search_query: String::new(),
+            pasted_cookie: None,
+            import_dialog: None,
+            pasted_profile_name: String::new(),
+            profile_dialog: None,
+            cookie_health: HashMap::new(),
+            verify_receiver: None,
+            show_settings: false,
+            background_image: None,
+            detail_profile: None,
+            detail_qr: None,
+            comparison: None,
+            vault_dialog: None,
+            profiles_locked: false,
+            recording_hotkey: None,
+            legacy_renames: None,
+            batch_find: String::new(),
+            other_installs: None,
+            batch_replace: String::new(),
+            confirm_reset: None,
+            show_onboarding,
+            hotkey_manager: None,
+            registered_hotkey: None,
+            hotkey_pressed: Arc::new(AtomicBool::new(false)),
+            quick_switch_open: false,
+            command_palette: None,
+            quick_switch_was_minimized: false,
+            tray: None,
+            tray_receiver: None,
+            hidden_to_tray: false,
+            config_modified: Config::modified(),
+            last_poll: Instant::now(),
+            live_modified: None,
+            live_exists: false,
+            idle_frames: IdleFrameCounter::default(),
+            login_receiver: None,
+            identity_receiver: None,
+            session_warning: None,
+            session_check_receiver: None,
+            launch_place: None,
+            scratch_session: None,
+            link_receiver: start_link_listener(ctx),
+            last_login_check: None,
+            window_mode_changed: Instant::now(),
+            cli_no_backup: cli.no_backup,
+            cli_backup_dir: cli.backup_dir,
+            cli_read_only: cli.read_only,
+        };
+        
+        // Auto-curate profiles from cookie files
+        app.load_profiles(ctx);
+        app.restore_active_profile();
+        if app.config.check_session_on_startup {
+            app.check_live_session(ctx);
+        }
+        app.report_changes_since_last_open();
+        
+        // Launcher scripts hand over a profile through SOBER_PROFILE
+        if let Some(query) = &cli.env_profile {
+            match Self::resolve_profile(&app.profiles, query) {
+                Ok(profile) => {
+                    let name = profile.name.clone();
+                    if let Some(index) = app.profiles.iter().position(|p| p.name == name) {
+                        app.request_switch(ctx, index);
+                    }
+                }
+                Err(e) => app.push_toast(ToastKind::Warning, format!("SOBER_PROFILE: {}", e)),
+            }
+        }
+        if let Some(link) = &cli.link {
+            app.open_switch_link(ctx, link);
+        }
+        
+        if app.config.theme.background == BackgroundStyle::Image {
+            app.load_background_image(ctx);
+        }
+        
+        app.apply_hotkey_config(ctx);
+        app.apply_tray_config(ctx);
+        app.apply_window_mode(ctx);
+        
+        if let Some(warning) = config_warning {
+            app.push_toast(ToastKind::Warning, warning);
+        }
+        if let Some(notice) = directory_notice {
+            app.push_toast(ToastKind::Warning, notice);
+        }
+        
+        let due: Vec<String> = app
+            .profiles
+            .iter()
+            .filter(|p| Self::reminder_due(p))
+            .map(|p| p.display_name.clone())
+            .collect();
+        if !due.is_empty() {
+            app.push_toast(ToastKind::Warning, format!("⏰ Due for attention: {}", due.join(", ")));
+        }
+        
+        app
+    }
+    
+    fn set_directory_config_enabled(&mut self, enabled: bool) {
+        if self.blocked_by_read_only("Moving profile settings") {
+            return;
+        }
+        let result = if enabled {
+            // Seed the directory file with the global settings so nothing visibly changes
+            let directory_config = DirectoryConfig::new(self.config.profiles.clone());
+            directory_config.save(&self.cookie_directory).map(|()| {
+                self.directory_config = Some(directory_config);
+            })
+        } else {
+            // Fold the directory's settings back into the global config before removing the file
+            if let Some(directory_config) = self.directory_config.take() {
+                self.config.profiles.extend(directory_config.profiles);
+            }
+            let path = self.cookie_directory.join(DIRECTORY_CONFIG_FILE);
+            self.config
+                .save()
+                .and_then(|()| fs::remove_file(&path).map_err(AppError::io("remove", &path)))
+        };
+        
+        if let Err(e) = result {
+            self.push_toast(ToastKind::Error, format!("Failed to update profile settings location: {}", e));
+        }
+    }
+    
+    // Move the current directory to the front of the history
+    fn save_directory(&mut self) {
+        if self.read_only() {
+            return;
+        }
+        self.recent_directories = core::record_directory(&self.cookie_directory);
+    }
+    
+    // Build profiles (without images) from the directory and config, plus any config warnings
+    fn scan_profiles(directory: &Path, live_file: &str, profile_configs: &BTreeMap<String, ProfileConfig>, palette: &[&str]) -> Result<(Vec<Profile>, Vec<String>), AppError> {
+        let cookie_files = core::scan_cookie_files(directory, live_file).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => AppError::DirectoryNotFound(directory.to_path_buf()),
+            _ => AppError::Io { action: "scan", path: directory.to_path_buf(), error: e },
+        })?;
+        
+        let mut warnings = Vec::new();
+        let mut profiles: Vec<Profile> = cookie_files
+            .into_iter()
+            .enumerate()
+            .map(|(i, file)| {
+                let profile_config = profile_configs.get(&file.profile_name).cloned().unwrap_or_default();
+                let hotkey = profile_config.hotkey.as_deref().and_then(|text| {
+                    let chord = Chord::parse(text);
+                    if chord.is_none() {
+                        warnings.push(format!("Ignored hotkey '{}' on {}: not a recognised key", text, file.profile_name));
+                    }
+                    chord
+                });
+                Profile {
+                    display_name: profile_config
+                        .username
+                        .clone()
+                        .unwrap_or_else(|| core::format_profile_name(&file.profile_name)),
+                    emoji: if profile_config.emoji.is_empty() {
+                        core::palette_emoji(palette, i)
+                    } else {
+                        profile_config.emoji.clone()
+                    },
+                    aliases: profile_config.aliases,
+                    favorite: profile_config.favorite,
+                    notes: profile_config.notes,
+                    locked: profile_config.locked,
+                    color_tag: profile_config.color_tag,
+                    last_login: profile_config.last_login,
+                    username: profile_config.username,
+                    remind_at: profile_config.remind_at,
+                    hotkey,
+                    crop: profile_config.crop.unwrap_or_default(),
+                    nickname: profile_config.nickname,
+                    games: profile_config.games,
+                    added_at: profile_config.added_at,
+                    modified_externally: false,
+                    name: file.profile_name,
+                    cookie_file: file.file_name,
+                    file_size: file.size,
+                    image: None,
+                    animation: None,
+                }
+            })
+            .collect();
+        
+        // Favorites first; the sort is stable so the rest stay alphabetical
+        profiles.sort_by_key(|p| !p.favorite);
+        
+        warnings.extend(Self::reject_conflicting_aliases(&mut profiles));
+        warnings.extend(Self::reject_conflicting_hotkeys(&mut profiles));
+        Ok((profiles, warnings))
+    }
+    
+    // What already uses a chord, if anything, so a profile hotkey never shadows it
+    fn chord_conflict(chord: Chord, profiles: &[Profile], except: &str) -> Option<String> {
+        if let Some((_, owner)) = RESERVED_CHORDS.iter().find(|(reserved, _)| *reserved == chord) {
+            return Some(owner.to_string());
+        }
+        profiles
+            .iter()
+            .find(|p| p.name != except && p.hotkey == Some(chord))
+            .map(|p| p.display_name.clone())
+    }
+    
+    // Hand-edited configs can assign a chord twice or pick a reserved one; the first profile keeps it
+    fn reject_conflicting_hotkeys(profiles: &mut [Profile]) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for i in 0..profiles.len() {
+            let Some(chord) = profiles[i].hotkey else {
+                continue;
+            };
+            let conflict = Self::chord_conflict(chord, &profiles[..i], &profiles[i].name);
+            if conflict.is_some() || !chord.is_usable() {
+                let reason = conflict.map_or("it needs Ctrl or Alt".to_string(), |owner| format!("already used by {}", owner));
+                warnings.push(format!("Ignored hotkey {} on {}: {}", chord, profiles[i].display_name, reason));
+                profiles[i].hotkey = None;
+            }
+        }
+        warnings
+    }
+    
+    // Drop aliases that clash with another profile's name, filename or alias so switching stays unambiguous
+    fn reject_conflicting_aliases(profiles: &mut [Profile]) -> Vec<String> {
+        let mut warnings = Vec::new();
+        
+        // Names and filenames always win over aliases
+        let mut taken: HashMap<String, usize> = HashMap::new();
+        for (i, profile) in profiles.iter().enumerate() {
+            taken.insert(profile.name.to_lowercase(), i);
+            taken.insert(profile.cookie_file.to_string_lossy().to_lowercase(), i);
+        }
+        
+        for i in 0..profiles.len() {
+            let mut accepted = Vec::new();
+            
+            for alias in std::mem::take(&mut profiles[i].aliases) {
+                let key = alias.trim().to_lowercase();
+                if key.is_empty() {
+                    continue;
+                }
+                
+                match taken.get(&key) {
+                    Some(&owner) if owner != i => {
+                        warnings.push(format!(
+                            "Ignored alias '{}' on {}: already used by {}",
+                            alias, profiles[i].display_name, profiles[owner].display_name
+                        ));
+                    }
+                    Some(_) => {}
+                    None => {
+                        taken.insert(key, i);
+                        accepted.push(alias.trim().to_string());
+                    }
+                }
+            }
+            
+            profiles[i].aliases = accepted;
+        }
+        
+        warnings
+    }
+    
+    // Case-insensitive substring match, falling back to an in-order subsequence match
+    fn fuzzy_match(haystack: &str, needle: &str) -> bool {
+        let haystack = haystack.to_lowercase();
+        let needle = needle.to_lowercase();
+        
+        if haystack.contains(&needle) {
+            return true;
+        }
+        
+        let mut chars = haystack.chars();
+        needle.chars().all(|c| chars.any(|h| h == c))
+    }
+    
+    fn profile_matches(profile: &Profile, query: &str) -> bool {
+        let query = query.trim();
+        if query.is_empty() {
+            return true;
+        }
+        
+        Self::fuzzy_match(&profile.name, query)
+            || Self::fuzzy_match(&profile.display_name, query)
+            || profile.aliases.iter().any(|alias| Self::fuzzy_match(alias, query))
+    }
+    
+    // Exact name/filename/alias matches win; otherwise the fuzzy match must be unique
+    fn resolve_profile<'a>(profiles: &'a [Profile], query: &str) -> Result<&'a Profile, String> {
+        let key = query.trim().to_lowercase();
+        
+        let exact = profiles.iter().find(|p| {
+            p.name.to_lowercase() == key
+                || p.cookie_file.to_string_lossy().to_lowercase() == key
+                || p.aliases.iter().any(|a| a.to_lowercase() == key)
+        });
+        if let Some(profile) = exact {
+            return Ok(profile);
+        }
+        
+        let matches: Vec<&Profile> = profiles.iter().filter(|p| Self::profile_matches(p, query)).collect();
+        match matches.as_slice() {
+            [profile] => Ok(profile),
+            [] => Err(format!("No profile matches '{}'", query)),
+            _ => Err(format!(
+                "'{}' is ambiguous: {}",
+                query,
+                matches.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+    
+    fn load_profiles(&mut self, ctx: &egui::Context) {
+        // Avatars from the same directory stay up while their files are decoded again
+        let previous = std::mem::take(&mut self.profiles);
+        let same_directory = self.avatars_directory == self.cookie_directory;
+        self.avatars_directory = self.cookie_directory.clone();
+        self.armed_profile = None;
+        self.dropdown_profile = None;
+        self.scan_error = None;
+        self.flatpak_directory = core::is_flatpak_path(&self.cookie_directory);
+        self.profiles_locked = core::is_locked(&self.cookie_directory);
+        self.refresh_live_state();
+        
+        let mut warnings = Vec::new();
+        self.directory_config = DirectoryConfig::load(&self.cookie_directory).unwrap_or_else(|e| {
+            warnings.push(e);
+            None
+        });
+        
+        // Settings of purged profiles go with them, unless a profile of the same name exists again
+        let purged = if self.read_only() {
+            Vec::new()
+        } else {
+            core::purge_trash(&self.cookie_directory, self.config.trash_retention_days)
+        };
+        let live = core::scan_cookie_files(&self.cookie_directory, &self.live_file()).unwrap_or_default();
+        let orphaned: Vec<String> = purged
+            .into_iter()
+            .filter(|name| !live.iter().any(|file| &file.profile_name == name))
+            .collect();
+        if !orphaned.is_empty() {
+            for name in &orphaned {
+                self.profile_configs_mut().remove(name);
+            }
+            self.save_profile_configs();
+        }
+        let profile_configs = core::profile_configs(&self.config, self.directory_config.as_ref());
+        
+        // Scan cookie directory for cookies_* files
+        match Self::scan_profiles(&self.cookie_directory, &self.live_file(), profile_configs, &self.config.emoji_palette()) {
+            Ok((mut profiles, scan_warnings)) => {
+                warnings.extend(scan_warnings);
+                
+                for profile in &mut profiles {
+                    if let Some(old) = previous.iter().find(|old| same_directory && old.name == profile.name) {
+                        profile.image = old.image.clone();
+                        profile.animation = old.animation.clone();
+                    }
+                }
+                let avatar_jobs = self.avatar_jobs(ctx, &mut profiles);
+                self.start_avatar_loads(ctx, avatar_jobs);
+                
+                self.profiles = profiles;
+                self.check_profile_checksums();
+                self.record_added_times();
+                self.sort_profiles();
+                self.publish_profiles();
+                
+                for warning in warnings {
+                    self.push_toast(ToastKind::Warning, warning);
+                }
+            }
+            Err(e) => {
+                self.start_avatar_loads(ctx, Vec::new());
+                self.scan_error = Some(e);
+            }
+        }
+    }
+    
+    // Refresh profiles.json for external scripts and widgets
+    fn publish_profiles(&self) {
+        if self.read_only() {
+            return;
+        }
+        let active = Self::detect_active_profile(&self.cookie_directory, &self.live_file(), &self.profiles);
+        let profiles = self
+            .profiles
+            .iter()
+            .enumerate()
+            .map(|(index, profile)| ListedProfile {
+                name: profile.name.clone(),
+                file: profile.cookie_file.to_string_lossy().into_owned(),
+                display_name: profile.display_name.clone(),
+                active: active == Some(index),
+                last_used: self.config.last_used(&profile.name),
+            })
+            .collect();
+        if let Err(e) = ProfilesListing::new(&self.cookie_directory, profiles).write() {
+            log::warn!("Failed to write profiles.json: {}", e);
+        }
+    }
+    
+    // Profile-specific images from the cookie directory are decoded in the background; a profile
+    // whose image is gone falls back to initials or its emoji
+    fn avatar_jobs(&mut self, ctx: &egui::Context, profiles: &mut [Profile]) -> Vec<(String, PathBuf)> {
+        let mut avatar_jobs = Vec::new();
+        for profile in profiles {
+            let image_path = core::profile_image_path(&self.cookie_directory, &profile.name);
+            if image_path.is_file() {
+                avatar_jobs.push((profile.name.clone(), image_path));
+                continue;
+            }
+            (profile.image, profile.animation) = (None, None);
+            if self.config.initials_avatars {
+                self.add_initials_avatar(ctx, profile, image_path, &mut avatar_jobs);
+            }
+        }
+        avatar_jobs
+    }
+    
+    // Decode every avatar again without rescanning the cookie files; the old pictures stay up meanwhile
+    fn rescan_images(&mut self, ctx: &egui::Context) {
+        let mut profiles = std::mem::take(&mut self.profiles);
+        let avatar_jobs = self.avatar_jobs(ctx, &mut profiles);
+        self.profiles = profiles;
+        
+        let count = avatar_jobs.len();
+        self.start_avatar_loads(ctx, avatar_jobs);
+        self.push_toast(ToastKind::Success, format!("Reloading {} image{}", count, if count == 1 { "" } else { "s" }));
+    }
+    
+    // Written out when the user opted in (then loaded like any other image), else only kept as a texture
+    fn add_initials_avatar(&mut self, ctx: &egui::Context, profile: &mut Profile, image_path: PathBuf, avatar_jobs: &mut Vec<(String, PathBuf)>) {
+        let avatar = core::initials_avatar(&profile.name);
+        if self.config.save_initials_avatars && !self.read_only() {
+            match avatar.save(&image_path) {
+                Ok(()) => {
+                    log::info!("Created {}", image_path.display());
+                    // Remembered so a reset can remove it again, but not an image put there later
+                    if let Ok(checksum) = core::file_checksum(&image_path) {
+                        self.config.generated_avatars.insert(image_path.clone(), checksum);
+                        if let Err(e) = self.save_config() {
+                            log::error!("Failed to save the generated avatar list: {}", e);
+                        }
+                    }
+                    avatar_jobs.push((profile.name.clone(), image_path));
+                    return;
+                }
+                Err(e) => log::warn!("Could not save {}: {}", image_path.display(), e),
+            }
+        }
+        let size = [avatar.width() as usize, avatar.height() as usize];
+        let image = ColorImage::from_rgba_unmultiplied(size, avatar.as_raw());
+        profile.image = Some(ctx.load_texture(format!("{}#initials", profile.name), image, TextureOptions::default()));
+    }
+    
+    // Replaces (and cancels) any load still running, e.g. from the previous directory
+    fn start_avatar_loads(&mut self, ctx: &egui::Context, jobs: Vec<(String, PathBuf)>) {
+        if let Some(loader) = self.avatar_loader.take() {
+            loader.cancelled.store(true, Ordering::SeqCst);
+        }
+        if jobs.is_empty() {
+            return;
+        }
+        
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let pending = jobs.iter().map(|(name, _)| name.clone()).collect();
+        let workers = AVATAR_LOADER_THREADS.min(jobs.len());
+        let queue = Arc::new(Mutex::new(jobs));
+        
+        for _ in 0..workers {
+            let (sender, cancelled, queue, ctx) = (sender.clone(), cancelled.clone(), queue.clone(), ctx.clone());
+            thread::spawn(move || loop {
+                if cancelled.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Some((name, path)) = queue.lock().unwrap_or_else(|e| e.into_inner()).pop() else {
+                    break;
+                };
+                let decoded = Self::decode_avatar(&path);
+                if sender.send((name, path, decoded)).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            });
+        }
+        
+        self.avatar_loader = Some(AvatarLoader { receiver, cancelled, pending });
+    }
+    
+    fn poll_avatars(&mut self, ctx: &egui::Context) {
+        let Some(loader) = &mut self.avatar_loader else {
+            return;
+        };
+        
+        loop {
+            match loader.receiver.try_recv() {
+                Ok((name, path, decoded)) => {
+                    loader.pending.remove(&name);
+                    if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == name) {
+                        (profile.image, profile.animation) = Self::upload_avatar(ctx, &path, decoded);
+                    }
+                }
+                Err(TryRecvError::Empty) if !loader.pending.is_empty() => return,
+                Err(_) => break,
+            }
+        }
+        self.avatar_loader = None;
+    }
+    
+    fn avatar_loading(&self, profile_name: &str) -> bool {
+        self.avatar_loader.as_ref().is_some_and(|loader| loader.pending.contains(profile_name))
+    }
+    
+    // Flag profiles whose file changed behind our back; files seen for the first time become the baseline
+    fn check_profile_checksums(&mut self) {
+        let mut first_seen = Vec::new();
+        let profile_configs = core::profile_configs(&self.config, self.directory_config.as_ref());
+        for profile in &mut self.profiles {
+            let checksum = match core::file_checksum(&self.cookie_directory.join(&profile.cookie_file)) {
+                Ok(checksum) => checksum,
+                Err(e) => {
+                    log::warn!("Could not checksum {}: {}", profile.cookie_file.to_string_lossy(), e);
+                    continue;
+                }
+            };
+            match profile_configs.get(&profile.name).and_then(|c| c.checksum.as_ref()) {
+                Some(stored) => profile.modified_externally = *stored != checksum,
+                None => first_seen.push((profile.name.clone(), checksum)),
+            }
+        }
+        
+        if !first_seen.is_empty() {
+            for (name, checksum) in first_seen {
+                self.profile_configs_mut().entry(name).or_default().checksum = Some(checksum);
+            }
+            self.save_profile_configs();
+        }
+    }
+    
+    // Profiles seen for the first time are dated by their file's creation time where the filesystem has one
+    fn record_added_times(&mut self) {
+        let now = core::unix_now();
+        let mut added = Vec::new();
+        for profile in self.profiles.iter_mut().filter(|profile| profile.added_at.is_none()) {
+            let created = fs::metadata(self.cookie_directory.join(&profile.cookie_file))
+                .and_then(|metadata| metadata.created())
+                .ok()
+                .and_then(|created| created.duration_since(UNIX_EPOCH).ok())
+                .map_or(now, |since| since.as_secs().min(now));
+            profile.added_at = Some(created);
+            added.push((profile.name.clone(), created));
+        }
+        
+        if !added.is_empty() {
+            for (name, added_at) in added {
+                self.profile_configs_mut().entry(name).or_default().added_at = Some(added_at);
+            }
+            self.save_profile_configs();
+        }
+    }
+    
+    // scan_profiles leaves them alphabetical with favorites first
+    fn sort_profiles(&mut self) {
+        if self.config.sort_mode == SortMode::NewestAdded {
+            self.profiles.sort_by_key(|profile| (!profile.favorite, std::cmp::Reverse(profile.added_at)));
+        }
+    }
+    
+    // Take the file as it is now as the expected contents of the profile
+    fn record_checksum(&mut self, profile_name: &str, path: &Path) {
+        match core::file_checksum(path) {
+            Ok(checksum) => {
+                self.profile_configs_mut().entry(profile_name.to_string()).or_default().checksum = Some(checksum);
+                self.save_profile_configs();
+            }
+            Err(e) => log::warn!("Could not checksum {}: {}", path.display(), e),
+        }
+    }
+    
+    // Links only switch on an exact name or alias, never on a partial match
+    fn open_switch_link(&mut self, ctx: &egui::Context, link: &str) {
+        let name = match core::parse_switch_url(link) {
+            Ok(name) => name,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Ignored a {}:// link: {}", URL_SCHEME, e));
+                return;
+            }
+        };
+        let query = name.to_lowercase();
+        let index = self
+            .profiles
+            .iter()
+            .position(|p| p.name.to_lowercase() == query || p.aliases.iter().any(|a| a.to_lowercase() == query));
+        match index {
+            Some(index) if core::wants_broadcast(link) => {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                let name = self.profiles[index].name.clone();
+                self.request_broadcast(&name);
+            }
+            Some(index) => {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                self.request_switch(ctx, index);
+            }
+            None => self.push_toast(ToastKind::Error, format!("The {}:// link names no profile called '{}'", URL_SCHEME, name)),
+        }
+    }
+    
+    // The current directory and the recently used ones that have this profile
+    fn broadcast_directories(&self, name: &str) -> Vec<PathBuf> {
+        let mut directories = vec![self.cookie_directory.clone()];
+        directories.extend(self.recent_directories.iter().map(|recent| core::expand_path(&recent.path)));
+        core::directories_with_profile(&directories, name, &self.config)
+    }
+    
+    // Always confirmed first, since it overwrites several live files at once
+    fn request_broadcast(&mut self, name: &str) {
+        if self.blocked_by_read_only("Switching") {
+            return;
+        }
+        let directories = self.broadcast_directories(name);
+        if directories.len() < 2 {
+            self.push_toast(
+                ToastKind::Warning,
+                format!("No other recently used directory has a profile called {}", core::format_profile_name(name)),
+            );
+            return;
+        }
+        self.profile_dialog = Some(ProfileDialog::ConfirmBroadcast { name: name.to_string(), directories });
+    }
+    
+    fn apply_broadcast(&mut self, ctx: &egui::Context, name: &str, directories: &[PathBuf]) {
+        let options = SwitchOptions::new(&self.config, &self.cookie_directory, self.cli_no_backup, self.cli_backup_dir.as_deref());
+        let results = core::broadcast_switch(directories, name, &self.config, options);
+        let mut switched = 0;
+        for (directory, result) in &results {
+            match result {
+                Ok(SwitchOutcome::AlreadyActive) | Ok(SwitchOutcome::Switched { .. }) => {
+                    log::info!("Switched {} to {}", directory.display(), name);
+                    switched += 1;
+                }
+                Err(e) => self.push_toast(ToastKind::Error, format!("{}: {}", directory.display(), e)),
+            }
+        }
+        
+        let here = results.iter().any(|(directory, result)| directory == &self.cookie_directory && result.is_ok());
+        if here {
+            self.config.record_switch(name);
+            if let Err(e) = self.save_config() {
+                log::error!("Failed to save switch history: {}", e);
+            }
+            self.session_warning = None;
+            self.reload_profiles_keeping_selection(ctx, Some(name.to_string()));
+        }
+        let kind = if switched == results.len() { ToastKind::Success } else { ToastKind::Warning };
+        self.push_toast(kind, format!("Switched to {} in {} of {} directories", core::format_profile_name(name), switched, results.len()));
+    }
+    
+    fn poll_links(&mut self, ctx: &egui::Context) {
+        let Some(receiver) = &self.link_receiver else {
+            return;
+        };
+        let links: Vec<String> = receiver.try_iter().collect();
+        for link in links {
+            log::info!("Received {}", link);
+            self.open_switch_link(ctx, &link);
+        }
+    }
+    
+    // Make this executable the desktop's handler for sober-switch:// links
+    fn register_url_scheme(&mut self) {
+        if self.blocked_by_read_only("Registering links") {
+            return;
+        }
+        let result = env::current_exe().map_err(|e| e.to_string()).and_then(|executable| {
+            let applications = dirs::data_dir().ok_or("no data directory")?.join("applications");
+            let desktop_file = format!("{}.desktop", URL_SCHEME);
+            let path = applications.join(&desktop_file);
+            fs::create_dir_all(&applications)
+                .and_then(|()| fs::write(&path, core::url_handler_desktop_entry(&executable)))
+                .map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+            
+            let mime_type = format!("x-scheme-handler/{}", URL_SCHEME);
+            match std::process::Command::new("xdg-mime").args(["default", &desktop_file, &mime_type]).status() {
+                Ok(status) if status.success() => Ok(()),
+                Ok(status) => Err(format!("xdg-mime failed ({})", status)),
+                Err(e) => Err(format!("could not run xdg-mime: {}", e)),
+            }
+        });
+        
+        match result {
+            Ok(()) => self.push_toast(ToastKind::Success, format!("{}:// links now open this app", URL_SCHEME)),
+            Err(e) => self.push_toast(ToastKind::Error, format!("Failed to register {}:// links: {}", URL_SCHEME, e)),
+        }
+    }
+    
+    fn find_profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+    
+    fn reload_profiles_keeping_selection(&mut self, ctx: &egui::Context, selected_name: Option<String>) {
+        self.load_profiles(ctx);
+        self.selected_profile = selected_name.and_then(|name| self.profiles.iter().position(|p| p.name == name));
+    }
+    
+    // The most recent profile in the switch history other than the active one, so repeated
+    // use bounces between the last two
+    fn previous_profile(&self) -> Option<usize> {
+        let current = self
+            .selected_profile_name()
+            .or_else(|| self.config.switch_history.first().map(|record| record.profile.clone()));
+        self.config
+            .switch_history
+            .iter()
+            .filter(|record| Some(&record.profile) != current.as_ref())
+            .find_map(|record| self.profiles.iter().position(|p| p.name == record.profile))
+    }
+    
+    fn switch_to_previous(&mut self, ctx: &egui::Context) {
+        match self.previous_profile() {
+            Some(index) => {
+                self.request_switch(ctx, index);
+            }
+            None => self.push_toast(ToastKind::Warning, "No previous profile to switch back to yet"),
+        }
+    }
+    
+    fn selected_profile_name(&self) -> Option<String> {
+        self.selected_profile
+            .and_then(|i| self.profiles.get(i))
+            .map(|p| p.name.clone())
+    }
+    
+    fn profile_configs_mut(&mut self) -> &mut BTreeMap<String, ProfileConfig> {
+        match &mut self.directory_config {
+            Some(directory_config) => &mut directory_config.profiles,
+            None => &mut self.config.profiles,
+        }
+    }
+    
+    fn save_profile_configs(&mut self) {
+        if self.read_only() {
+            return;
+        }
+        let result = match &self.directory_config {
+            Some(directory_config) => directory_config.save(&self.cookie_directory),
+            None => self.save_config(),
+        };
+        
+        if let Err(e) = result {
+            self.push_toast(ToastKind::Error, format!("Failed to save profile settings: {}", e));
+        }
+    }
+    
+    // Save and remember the new mtime so our own write isn't mistaken for an external edit
+    fn save_config(&mut self) -> Result<(), AppError> {
+        if self.read_only() {
+            return Ok(());
+        }
+        self.config.save()?;
+        self.config_modified = Config::modified();
+        Ok(())
+    }
+    
+    fn read_only(&self) -> bool {
+        self.cli_read_only || self.config.read_only
+    }
+    
+    // True (after telling the user) when the action would write and read-only mode is on
+    fn blocked_by_read_only(&mut self, what: &str) -> bool {
+        if self.read_only() {
+            self.push_toast(ToastKind::Warning, format!("{} is disabled in read-only mode", what));
+        }
+        self.read_only()
+    }
+    
+    // The toggle itself is always saved, so read-only mode survives a restart and can be left again
+    fn set_read_only(&mut self, read_only: bool) {
+        self.config.read_only = read_only;
+        match self.config.save() {
+            Ok(()) => self.config_modified = Config::modified(),
+            Err(e) => self.push_toast(ToastKind::Error, format!("Failed to save settings: {}", e)),
+        }
+    }
+    
+    fn poll_filesystem(&mut self, ctx: &egui::Context) {
+        let interval = self.config.poll_interval();
+        ctx.request_repaint_after(interval);
+        if self.last_poll.elapsed() < interval {
+            return;
+        }
+        self.last_poll = Instant::now();
+        
+        self.reload_config_if_changed(ctx);
+        if self.refresh_live_state() {
+            self.follow_external_switch();
+        }
+    }
+    
+    // Nothing the user did and nothing on screen that moves: a frame like this only the poll timer
+    // should cause
+    fn frame_is_idle(&self, ctx: &egui::Context) -> bool {
+        let no_input = ctx.input(|i| i.events.is_empty() && !i.pointer.is_moving() && !i.pointer.any_down());
+        let animating = !self.toasts.is_empty() || self.profiles.iter().any(|profile| profile.animation.is_some());
+        let working = self.avatar_loader.is_some()
+            || self.verify_receiver.is_some()
+            || self.login_receiver.is_some()
+            || self.identity_receiver.is_some()
+            || self.session_check_receiver.is_some();
+        no_input && !animating && !working
+    }
+    
+    fn count_idle_frames(&mut self, ctx: &egui::Context) {
+        let idle = self.frame_is_idle(ctx);
+        let Some(count) = self.idle_frames.record(ctx.input(|i| i.time), idle) else {
+            return;
+        };
+        let budget = core::idle_frame_budget(self.config.poll_interval());
+        if count > budget {
+            log::warn!(
+                "{} frames painted while idle in the last {} s (expected at most {}); something keeps requesting repaints",
+                count,
+                core::IDLE_FRAME_WINDOW_SECS,
+                budget
+            );
+        }
+    }
+    
+    // The live file name for the current directory, and its path
+    fn live_file(&self) -> String {
+        self.config.live_cookie_file(&self.cookie_directory)
+    }
+    
+    fn live_cookies_path(&self) -> PathBuf {
+        self.cookie_directory.join(self.live_file())
+    }
+    
+    // Remember whether the live cookies file exists and when it last changed; true when that moved
+    fn refresh_live_state(&mut self) -> bool {
+        let modified = fs::metadata(self.live_cookies_path()).and_then(|m| m.modified()).ok();
+        self.live_exists = modified.is_some();
+        let changed = modified != self.live_modified;
+        self.live_modified = modified;
+        changed
+    }
+    
+    // Another tool or Sober itself replaced the live file; show whichever profile it now matches
+    fn follow_external_switch(&mut self) {
+        if Self::detect_active_profile(&self.cookie_directory, &self.live_file(), &self.profiles) == self.selected_profile {
+            return;
+        }
+        log::info!("The live cookies file changed outside the app");
+        self.restore_active_profile();
+        self.publish_profiles();
+    }
+    
+    // Re-read config.json when something else changed it and apply everything derived from it
+    fn reload_config_if_changed(&mut self, ctx: &egui::Context) {
+        if Config::modified() == self.config_modified {
+            return;
+        }
+        
+        // Loading can rewrite the file (migration), so take the mtime afterwards
+        let (config, warning) = Config::load(self.cli_read_only);
+        self.config = config;
+        self.config_modified = Config::modified();
+        if let Some(warning) = warning {
+            self.push_toast(ToastKind::Warning, warning);
+        }
+        log::info!("Reloaded {} after an external change", Config::path().display());
+        
+        self.background_image = None;
+        if self.config.theme.background == BackgroundStyle::Image {
+            self.load_background_image(ctx);
+        }
+        self.apply_hotkey_config(ctx);
+        self.apply_tray_config(ctx);
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(self.window_level()));
+        let selected = self.selected_profile_name();
+        self.reload_profiles_keeping_selection(ctx, selected);
+    }
+    
+    fn handle_profile_action(&mut self, ctx: &egui::Context, index: usize, action: ProfileAction) {
+        let Some(profile) = self.profiles.get(index).cloned() else {
+            return;
+        };
+        let name = profile.name.clone();
+        
+        let writes = !matches!(
+            action,
+            ProfileAction::Switch
+                | ProfileAction::LaunchGame(_)
+                | ProfileAction::SwitchEverywhere
+                | ProfileAction::ShowDetails
+                | ProfileAction::Compare
+        );
+        if writes && self.blocked_by_read_only("Editing profiles") {
+            return;
+        }
+        
+        match action {
+            ProfileAction::Switch => {
+                self.request_switch(ctx, index);
+            }
+            ProfileAction::LaunchGame(game) => {
+                self.launch_place = profile.games.get(game).cloned();
+                self.request_switch(ctx, index);
+                // Only a switch still waiting on confirmation keeps the place for later
+                if !matches!(self.profile_dialog, Some(ProfileDialog::ConfirmSwitch { .. } | ProfileDialog::ForceSwitch { .. })) {
+                    self.launch_place = None;
+                }
+            }
+            ProfileAction::SwitchEverywhere => self.request_broadcast(&name),
+            ProfileAction::ShowDetails => self.detail_profile = Some(name),
+            ProfileAction::Rename => {
+                self.profile_dialog = Some(ProfileDialog::Rename { input: name.clone(), name });
+            }
+            ProfileAction::EditAliases => {
+                self.profile_dialog = Some(ProfileDialog::Aliases { input: profile.aliases.join(", "), name });
+            }
+            ProfileAction::EditNickname => {
+                self.profile_dialog = Some(ProfileDialog::Nickname { input: profile.nickname.clone(), name });
+            }
+            ProfileAction::EditGames => {
+                self.profile_dialog = Some(ProfileDialog::Games { input: core::format_game_shortcuts(&profile.games), name });
+            }
+            ProfileAction::EditNotes => {
+                self.profile_dialog = Some(ProfileDialog::Notes { input: profile.notes.clone(), name });
+            }
+            ProfileAction::SetReminder => {
+                self.profile_dialog = Some(ProfileDialog::Reminder { input: "7".to_string(), name });
+            }
+            ProfileAction::SnoozeReminder => {
+                self.set_reminder(&name, Some(core::unix_now() + 24 * 60 * 60));
+                let selected = self.selected_profile_name();
+                self.reload_profiles_keeping_selection(ctx, selected);
+                self.push_toast(ToastKind::Success, format!("Snoozed the reminder for {} until tomorrow", profile.display_name));
+            }
+            ProfileAction::ClearReminder => {
+                self.set_reminder(&name, None);
+                let selected = self.selected_profile_name();
+                self.reload_profiles_keeping_selection(ctx, selected);
+            }
+            ProfileAction::Export => {
+                let file_name = core::cookie_file_name(&name, "cookies.txt");
+                self.profile_dialog = Some(ProfileDialog::Export { input: format!("~/{}", file_name), name });
+            }
+            ProfileAction::Archive => match core::archive_profile(&self.cookie_directory, &profile.cookie_file) {
+                Ok(()) => {
+                    let selected = self.selected_profile_name().filter(|s| s != &name);
+                    self.reload_profiles_keeping_selection(ctx, selected);
+                    self.push_toast(ToastKind::Success, format!("Archived {}; bring it back from Settings → Archived", profile.display_name));
+                }
+                Err(e) => self.push_toast(ToastKind::Error, e.to_string()),
+            },
+            ProfileAction::Delete => {
+                self.profile_dialog = Some(ProfileDialog::ConfirmDelete { name });
+            }
+            ProfileAction::ToggleFavorite => {
+                let entry = self.profile_configs_mut().entry(name).or_default();
+                entry.favorite = !entry.favorite;
+                self.save_profile_configs();
+                let selected = self.selected_profile_name();
+                self.reload_profiles_keeping_selection(ctx, selected);
+            }
+            ProfileAction::ToggleLock => {
+                let entry = self.profile_configs_mut().entry(name).or_default();
+                entry.locked = !entry.locked;
+                self.save_profile_configs();
+                let selected = self.selected_profile_name();
+                self.reload_profiles_keeping_selection(ctx, selected);
+            }
+            ProfileAction::EditColorTag => {
+                let color = profile.color_tag.unwrap_or([220, 80, 80]);
+                self.profile_dialog = Some(ProfileDialog::ColorTag { name, color });
+            }
+            ProfileAction::ClearColorTag => {
+                self.profile_configs_mut().entry(name).or_default().color_tag = None;
+                self.save_profile_configs();
+                let selected = self.selected_profile_name();
+                self.reload_profiles_keeping_selection(ctx, selected);
+            }
+            ProfileAction::AcceptChecksum => {
+                self.record_checksum(&name, &self.cookie_directory.join(&profile.cookie_file));
+                let selected = self.selected_profile_name();
+                self.reload_profiles_keeping_selection(ctx, selected);
+                self.push_toast(ToastKind::Success, format!("Accepted the current cookie file for {}", profile.display_name));
+            }
+            ProfileAction::Compare => {
+                self.comparison = Some(ProfileComparison { first: name, second: None, result: None });
+            }
+            ProfileAction::Duplicate => {
+                match self.duplicate_profile(&profile) {
+                    Ok(new_name) => {
+                        let selected = self.selected_profile_name();
+                        self.reload_profiles_keeping_selection(ctx, selected);
+                        self.push_toast(ToastKind::Success, format!("Duplicated {} as {}", profile.display_name, core::format_profile_name(&new_name)));
+                    }
+                    Err(e) => self.push_toast(ToastKind::Error, e.to_string()),
+                }
+            }
+        }
+    }
+    
+    // Every GUI switch goes through here so a locked active profile can't be replaced by accident
+    // Returns whether the switch happened now rather than waiting on a confirmation
+    fn request_switch(&mut self, ctx: &egui::Context, index: usize) -> bool {
+        if self.blocked_by_read_only("Switching") {
+            return false;
+        }
+        let Some(target) = self.profiles.get(index) else {
+            return false;
+        };
+        
+        match self.locked_active_name(index) {
+            Some(locked_name) => {
+                self.profile_dialog = Some(ProfileDialog::ConfirmSwitch {
+                    name: target.name.clone(),
+                    locked_name: Some(locked_name),
+                });
+                false
+            }
+            None => {
+                self.selected_profile = Some(index);
+                self.copy_cookie_file(ctx, index, false)
+            }
+        }
+    }
+    
+    // The active profile's name if it is locked and switching to index would replace it
+    fn locked_active_name(&self, index: usize) -> Option<String> {
+        self.selected_profile
+            .filter(|&active| active != index)
+            .and_then(|active| self.profiles.get(active))
+            .filter(|active| active.locked)
+            .map(|active| active.name.clone())
+    }
+    
+    // A click on a profile in the grid, handled according to the switch mode
+    fn click_profile(&mut self, ctx: &egui::Context, index: usize) {
+        let armed = self.armed_profile.take();
+        match self.config.switch_mode {
+            SwitchMode::Immediate => {
+                self.request_switch(ctx, index);
+            }
+            SwitchMode::DoubleClick if armed == Some(index) => {
+                self.request_switch(ctx, index);
+            }
+            SwitchMode::DoubleClick => self.armed_profile = Some(index),
+            SwitchMode::Confirm => {
+                if let Some(target) = self.profiles.get(index) {
+                    self.profile_dialog = Some(ProfileDialog::ConfirmSwitch {
+                        name: target.name.clone(),
+                        locked_name: self.locked_active_name(index),
+                    });
+                }
+            }
+        }
+    }
+    
+    // Keyboard-friendly alternative to the grid, switching through the same path as a click would
+    fn draw_profile_dropdown(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, visible: &[usize]) {
+        let choice = self
+            .dropdown_profile
+            .or(self.selected_profile)
+            .filter(|i| visible.contains(i))
+            .unwrap_or(visible[0]);
+        let label = |index: usize| {
+            let profile = &self.profiles[index];
+            if self.selected_profile == Some(index) {
+                format!("{} (active)", profile.display_name)
+            } else {
+                profile.display_name.clone()
+            }
+        };
+        
+        let mut picked = choice;
+        let mut switch = false;
+        ui.vertical_centered(|ui| {
+            ui.horizontal(|ui| {
+                let width = 160.0_f32.min(ui.available_width() - 70.0).max(80.0);
+                let row_width = width + 70.0;
+                ui.add_space(((ui.available_width() - row_width) / 2.0).max(0.0));
+                egui::ComboBox::from_id_source("profile_dropdown")
+                    .width(width)
+                    .selected_text(label(choice))
+                    .show_ui(ui, |ui| {
+                        for &index in visible {
+                            ui.selectable_value(&mut picked, index, label(index));
+                        }
+                    });
+                switch = ui.button("Switch").clicked();
+            });
+        });
+        
+        self.dropdown_profile = Some(picked);
+        if switch {
+            self.request_switch(ctx, picked);
+        }
+    }
+    
+    // Shift+click with developer features on: switch, then put the token on the clipboard
+    fn switch_and_copy_token(&mut self, ctx: &egui::Context, index: usize) {
+        if !self.request_switch(ctx, index) {
+            return;
+        }
+        let Some(profile) = self.profiles.get(index) else {
+            return;
+        };
+        
+        let token = core::read_cookie_text(&self.cookie_directory.join(&profile.cookie_file))
+            .ok()
+            .and_then(|contents| core::parse_roblosecurity(&contents));
+        let display_name = profile.display_name.clone();
+        
+        match token {
+            Some(token) => {
+                ctx.output_mut(|o| o.copied_text = token);
+                self.push_toast(ToastKind::Success, format!("Switched to {} and copied its {} to the clipboard", display_name, ROBLOSECURITY));
+            }
+            None => {
+                self.push_toast(ToastKind::Warning, format!("Switched to {}, but its cookie file has no {} to copy", display_name, ROBLOSECURITY));
+            }
+        }
+    }
+    
+    // Confirm whichever profile dialog is open
+    fn apply_profile_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = self.profile_dialog.take() else {
+            return;
+        };
+        if self.blocked_by_read_only("Editing profiles") {
+            return;
+        }
+        let selected = self.selected_profile_name();
+        
+        let result = match &dialog {
+            ProfileDialog::Rename { name, input } => self.rename_profile(name, input).map(|new_name| {
+                let selected = if selected.as_deref() == Some(name.as_str()) { Some(new_name.clone()) } else { selected.clone() };
+                self.reload_profiles_keeping_selection(ctx, selected);
+                format!("Renamed {} to {}", core::format_profile_name(name), core::format_profile_name(&new_name))
+            }),
+            ProfileDialog::Aliases { name, input } => self.set_profile_aliases(name, input).map(|()| {
+                self.reload_profiles_keeping_selection(ctx, selected.clone());
+                format!("Updated aliases for {}", core::format_profile_name(name))
+            }),
+            ProfileDialog::Nickname { name, input } => {
+                let nickname: String = input.trim().chars().take(MAX_NICKNAME_LENGTH).collect();
+                self.profile_configs_mut().entry(name.clone()).or_default().nickname = nickname;
+                self.save_profile_configs();
+                self.reload_profiles_keeping_selection(ctx, selected.clone());
+                Ok(format!("Saved nickname for {}", core::format_profile_name(name)))
+            }
+            ProfileDialog::Games { name, input } => match core::parse_game_shortcuts(input) {
+                Ok(games) => {
+                    self.profile_configs_mut().entry(name.clone()).or_default().games = games;
+                    self.save_profile_configs();
+                    self.reload_profiles_keeping_selection(ctx, selected.clone());
+                    Ok(format!("Saved game shortcuts for {}", core::format_profile_name(name)))
+                }
+                Err(reason) => Err(AppError::InvalidInput(reason)),
+            },
+            ProfileDialog::Notes { name, input } => {
+                self.profile_configs_mut().entry(name.clone()).or_default().notes = input.trim().to_string();
+                self.save_profile_configs();
+                self.reload_profiles_keeping_selection(ctx, selected.clone());
+                Ok(format!("Saved notes for {}", core::format_profile_name(name)))
+            }
+            ProfileDialog::Reminder { name, input } => match input.trim().parse::<u64>() {
+                Ok(days) if days > 0 => {
+                    self.set_reminder(name, Some(core::unix_now() + days * 24 * 60 * 60));
+                    self.reload_profiles_keeping_selection(ctx, selected.clone());
+                    Ok(format!("Will remind you about {} in {} day(s)", core::format_profile_name(name), days))
+                }
+                _ => Err(AppError::InvalidInput("Enter a number of days, e.g. 7")),
+            },
+            ProfileDialog::ColorTag { name, color } => {
+                self.profile_configs_mut().entry(name.clone()).or_default().color_tag = Some(*color);
+                self.save_profile_configs();
+                self.reload_profiles_keeping_selection(ctx, selected.clone());
+                Ok(format!("Set color tag for {}", core::format_profile_name(name)))
+            }
+            ProfileDialog::Export { name, input } => self.export_profile(name, input),
+            ProfileDialog::ConfirmSwitch { name, .. } => {
+                if let Some(index) = self.profiles.iter().position(|p| &p.name == name) {
+                    self.selected_profile = Some(index);
+                    self.copy_cookie_file(ctx, index, false);
+                }
+                return;
+            }
+            ProfileDialog::ForceSwitch { name } => {
+                if let Some(index) = self.profiles.iter().position(|p| &p.name == name) {
+                    self.selected_profile = Some(index);
+                    self.copy_cookie_file(ctx, index, true);
+                }
+                return;
+            }
+            ProfileDialog::ConfirmBroadcast { name, directories } => {
+                self.apply_broadcast(ctx, name, directories);
+                return;
+            }
+            ProfileDialog::ConfirmDelete { name } => self.delete_profile(name).map(|()| {
+                let selected = selected.clone().filter(|s| s != name);
+                self.reload_profiles_keeping_selection(ctx, selected);
+                format!("Moved {} profile to the trash", core::format_profile_name(name))
+            }),
+        };
+        
+        match result {
+            Ok(message) => self.push_toast(ToastKind::Success, message),
+            Err(e) => {
+                // Keep the dialog open so the input can be corrected
+                self.push_toast(ToastKind::Error, e.to_string());
+                if !matches!(dialog, ProfileDialog::ConfirmDelete { .. }) {
+                    self.profile_dialog = Some(dialog);
+                }
+            }
+        }
+    }
+    
+    fn rename_profile(&mut self, old_name: &str, new_name: &str) -> Result<String, AppError> {
+        let profile = self.find_profile(old_name).ok_or_else(|| AppError::ProfileNotFound(old_name.to_string()))?.clone();
+        let new_name = core::validate_new_profile_name(&self.cookie_directory, new_name)?;
+        
+        let new_file = core::cookie_file_name(&new_name, &profile.cookie_file);
+        let old_path = self.cookie_directory.join(&profile.cookie_file);
+        fs::rename(&old_path, self.cookie_directory.join(&new_file)).map_err(AppError::io("rename", &old_path))?;
+        
+        self.carry_over_profile(old_name, &new_name);
+        Ok(new_name)
+    }
+    
+    // Move everything keyed by a profile's name (image, settings, active marker) to its new name
+    fn carry_over_profile(&mut self, old_name: &str, new_name: &str) {
+        let old_image = core::profile_image_path(&self.cookie_directory, old_name);
+        if old_image.exists() {
+            if let Err(e) = fs::rename(&old_image, core::profile_image_path(&self.cookie_directory, new_name)) {
+                log::warn!("Failed to rename {}: {}", old_image.display(), e);
+            }
+        }
+        
+        if let Some(entry) = self.profile_configs_mut().remove(old_name) {
+            self.profile_configs_mut().insert(new_name.to_string(), entry);
+            self.save_profile_configs();
+        }
+        
+        if core::read_active_marker(&self.cookie_directory).as_deref() == Some(old_name) {
+            core::write_active_marker(&self.cookie_directory, new_name);
+        }
+    }
+    
+    fn set_profile_aliases(&mut self, name: &str, input: &str) -> Result<(), AppError> {
+        let aliases: Vec<String> = input
+            .split(',')
+            .map(|alias| alias.trim().to_string())
+            .filter(|alias| !alias.is_empty())
+            .collect();
+        
+        // Same rule as on load: another profile's name, filename or alias can't be reused
+        for alias in &aliases {
+            let key = alias.to_lowercase();
+            let owner = self.profiles.iter().find(|p| {
+                p.name != name
+                    && (p.name.to_lowercase() == key
+                        || p.cookie_file.to_string_lossy().to_lowercase() == key
+                        || p.aliases.iter().any(|a| a.to_lowercase() == key))
+            });
+            if let Some(owner) = owner {
+                return Err(AppError::AliasTaken { alias: alias.clone(), owner: owner.display_name.clone() });
+            }
+        }
+        
+        self.profile_configs_mut().entry(name.to_string()).or_default().aliases = aliases;
+        self.save_profile_configs();
+        Ok(())
+    }
+    
+    fn duplicate_profile(&mut self, profile: &Profile) -> Result<String, AppError> {
+        let existing = core::scan_cookie_files(&self.cookie_directory, &self.live_file()).map_err(AppError::io("scan", &self.cookie_directory))?;
+        let new_name = (1..)
+            .map(|n| if n == 1 { format!("{}_copy", profile.name) } else { format!("{}_copy{}", profile.name, n) })
+            .find(|candidate| !existing.iter().any(|file| file.profile_name.eq_ignore_ascii_case(candidate)))
+            .unwrap_or_default();
+        
+        let new_file = core::cookie_file_name(&new_name, &profile.cookie_file);
+        let source = self.cookie_directory.join(&profile.cookie_file);
+        let target = self.cookie_directory.join(&new_file);
+        fs::copy(&source, &target).map_err(|error| AppError::CopyFailed { source, target, error })?;
+        self.record_checksum(&new_name, &self.cookie_directory.join(&new_file));
+        
+        let image = core::profile_image_path(&self.cookie_directory, &profile.name);
+        if image.exists() {
+            fs::copy(&image, core::profile_image_path(&self.cookie_directory, &new_name)).ok();
+        }
+        
+        Ok(new_name)
+    }
+    
+    fn export_profile(&self, name: &str, destination: &str) -> Result<String, AppError> {
+        let profile = self.find_profile(name).ok_or_else(|| AppError::ProfileNotFound(name.to_string()))?;
+        let destination = core::expand_path(destination.trim());
+        
+        if destination.exists() {
+            return Err(AppError::AlreadyExists(destination));
+        }
+        
+        // Exports are always plain text so they can be used anywhere
+        core::read_cookie_file(&self.cookie_directory.join(&profile.cookie_file))
+            .and_then(|contents| fs::write(&destination, contents))
+            .map_err(AppError::io("export to", &destination))?;
+        
+        Ok(format!("Exported {} to {}", profile.display_name, destination.display()))
+    }
+    
+    // Deleting moves the cookie file and image into the trash; settings stay until the trash is purged
+    fn delete_profile(&mut self, name: &str) -> Result<(), AppError> {
+        let profile = self.find_profile(name).ok_or_else(|| AppError::ProfileNotFound(name.to_string()))?.clone();
+        let trash = self.cookie_directory.join(TRASH_DIR);
+        fs::create_dir_all(&trash).map_err(AppError::io("create", &trash))?;
+        
+        let deleted_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let path = self.cookie_directory.join(&profile.cookie_file);
+        // A name that isn't UTF-8 is trashed under its lossy form so the trash can still list it
+        fs::rename(&path, trash.join(format!("{}__{}", deleted_at, profile.cookie_file.to_string_lossy())))
+            .map_err(AppError::io("move to the trash", &path))?;
+        
+        let image = core::profile_image_path(&self.cookie_directory, name);
+        if image.exists() {
+            let image_name = image.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            fs::rename(&image, trash.join(format!("{}__{}", deleted_at, image_name))).ok();
+        }
+        
+        Ok(())
+    }
+    
+    // Save every profile's current emoji so adding or removing profiles no longer reshuffles them
+    fn pin_emojis(&mut self, ctx: &egui::Context) {
+        let emojis: Vec<(String, String)> = self.profiles.iter().map(|p| (p.name.clone(), p.emoji.clone())).collect();
+        if self.set_profile_emojis(ctx, emojis) {
+            self.push_toast(ToastKind::Success, "Pinned the current emojis");
+        }
+    }
+    
+    fn shuffle_emojis(&mut self, ctx: &egui::Context) {
+        let emojis = core::shuffled_emojis(&self.config.emoji_palette(), self.profiles.len());
+        let emojis: Vec<(String, String)> = self.profiles.iter().map(|p| p.name.clone()).zip(emojis).collect();
+        if self.set_profile_emojis(ctx, emojis) {
+            self.push_toast(ToastKind::Success, "Shuffled and pinned new emojis");
+        }
+    }
+    
+    // An empty emoji unpins the profile again
+    fn set_profile_emojis(&mut self, ctx: &egui::Context, emojis: Vec<(String, String)>) -> bool {
+        if self.blocked_by_read_only("Changing emojis") {
+            return false;
+        }
+        for (name, emoji) in emojis {
+            self.profile_configs_mut().entry(name).or_default().emoji = emoji;
+        }
+        self.save_profile_configs();
+        let selected = self.selected_profile_name();
+        self.reload_profiles_keeping_selection(ctx, selected);
+        true
+    }
+    
+    fn avatar_uv(crop: &AvatarCrop, texture: &TextureHandle) -> Rect {
+        let size = texture.size_vec2();
+        let [min, max] = crop.uv_rect(size.x, size.y);
+        Rect::from_min_max(Pos2::new(min[0], min[1]), Pos2::new(max[0], max[1]))
+    }
+    
+    // Applied straight away while a slider moves; only written to the config once it settles
+    fn set_avatar_crop(&mut self, name: &str, crop: AvatarCrop, save: bool) {
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == name) {
+            profile.crop = crop;
+        }
+        if save {
+            self.profile_configs_mut().entry(name.to_string()).or_default().crop = Some(crop).filter(|c| *c != AvatarCrop::default());
+            self.save_profile_configs();
+        }
+    }
+    
+    fn load_logo(ctx: &egui::Context, configured: &str) -> Option<TextureHandle> {
+        let path = core::logo_candidates(configured).into_iter().find(|path| path.is_file())?;
+        log::debug!("Using the logo at {}", path.display());
+        Self::load_image_from_path(ctx, path.to_str().unwrap_or(""))
+    }
+    
+    // Missing images are the normal case; only a file that exists but won't decode is worth a warning
+    fn load_image_from_path(ctx: &egui::Context, path: &str) -> Option<TextureHandle> {
+        if !Path::new(path).is_file() {
+            log::debug!("No image at {}", path);
+            return None;
+        }
+        
+        match Self::decode_image(Path::new(path)) {
+            Ok(color_image) => Some(ctx.load_texture(path, color_image, TextureOptions::default())),
+            Err(e) => {
+                log::warn!("{}", e);
+                None
+            }
+        }
+    }
+    
+    // Avatars are sniffed by content, so an animated GIF saved as <name>.png still plays.
+    // Runs on the loader's worker threads, so it must not touch the egui context
+    fn decode_avatar(path: &Path) -> DecodedAvatar {
+        match Self::decode_animation(path) {
+            Ok(frames) if frames.len() > 1 => return DecodedAvatar::Animated(frames),
+            Ok(_) => {}
+            Err(e) => log::warn!("Could not decode the frames of {}, showing it still: {}", path.display(), e),
+        }
+        
+        match Self::decode_image(path) {
+            Ok(image) => DecodedAvatar::Still(image),
+            Err(e) => {
+                log::warn!("{}", e);
+                DecodedAvatar::Failed
+            }
+        }
+    }
+    
+    fn upload_avatar(ctx: &egui::Context, path: &Path, decoded: DecodedAvatar) -> (Option<TextureHandle>, Option<Arc<AvatarAnimation>>) {
+        let name = path.to_string_lossy();
+        match decoded {
+            DecodedAvatar::Animated(frames) => {
+                let mut ends = Vec::with_capacity(frames.len());
+                let mut textures = Vec::with_capacity(frames.len());
+                let mut end = Duration::ZERO;
+                for (i, (frame, delay)) in frames.into_iter().enumerate() {
+                    end += delay;
+                    ends.push(end);
+                    textures.push(ctx.load_texture(format!("{}#{}", name, i), frame, TextureOptions::default()));
+                }
+                let first = textures[0].clone();
+                (Some(first), Some(Arc::new(AvatarAnimation { frames: textures, ends })))
+            }
+            DecodedAvatar::Still(image) => (Some(ctx.load_texture(name, image, TextureOptions::default())), None),
+            DecodedAvatar::Failed => (None, None),
+        }
+    }
+    
+    // Frames of a GIF or APNG, downscaled and capped; empty for anything that isn't animated
+    fn decode_animation(path: &Path) -> image::ImageResult<Vec<(ColorImage, Duration)>> {
+        use image::codecs::{gif::GifDecoder, png::PngDecoder};
+        use image::{AnimationDecoder, ImageDecoder};
+        
+        let bytes = fs::read(path)?;
+        let frames = match image::guess_format(&bytes)? {
+            image::ImageFormat::Gif => {
+                let decoder = GifDecoder::new(io::Cursor::new(&bytes))?;
+                if Self::animation_too_large(decoder.dimensions()) {
+                    return Ok(Vec::new());
+                }
+                decoder.into_frames()
+            }
+            image::ImageFormat::Png => {
+                let decoder = PngDecoder::new(io::Cursor::new(&bytes))?;
+                if !decoder.is_apng() || Self::animation_too_large(decoder.dimensions()) {
+                    return Ok(Vec::new());
+                }
+                decoder.apng().into_frames()
+            }
+            _ => return Ok(Vec::new()),
+        };
+        
+        let mut decoded = Vec::new();
+        for frame in frames.take(MAX_AVATAR_FRAMES) {
+            let frame = frame?;
+            // Browsers treat near-zero delays as "as fast as possible" and slow them down; so do we
+            let delay = Duration::from(frame.delay());
+            let delay = if delay < Duration::from_millis(20) { Duration::from_millis(100) } else { delay };
+            
+            let mut buffer = frame.into_buffer();
+            let (width, height) = buffer.dimensions();
+            if width.max(height) > MAX_AVATAR_FRAME_SIDE {
+                let scale = MAX_AVATAR_FRAME_SIDE as f32 / width.max(height) as f32;
+                let new_width = ((width as f32 * scale) as u32).max(1);
+                let new_height = ((height as f32 * scale) as u32).max(1);
+                buffer = image::imageops::thumbnail(&buffer, new_width, new_height);
+            }
+            let size = [buffer.width() as usize, buffer.height() as usize];
+            decoded.push((ColorImage::from_rgba_unmultiplied(size, buffer.as_raw()), delay));
+        }
+        Ok(decoded)
+    }
+    
+    // Every frame is decoded at full size before downscaling, so huge canvases aren't animated at all
+    fn animation_too_large((width, height): (u32, u32)) -> bool {
+        width.max(height) > MAX_ANIMATION_CANVAS_SIDE
+    }
+    
+    fn decode_image(path: &Path) -> Result<ColorImage, AppError> {
+        let img = image::io::Reader::open(path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(image::ImageError::IoError)
+            .and_then(|reader| reader.decode())
+            .map_err(|error| AppError::ImageDecode { path: path.to_path_buf(), error })?;
+        let rgba = img.to_rgba8();
+        let size = [rgba.width() as usize, rgba.height() as usize];
+        let pixels = rgba.as_flat_samples();
+        Ok(ColorImage::from_rgba_unmultiplied(size, pixels.as_slice()))
+    }
+    
+    fn paste_cookie_from_clipboard(&mut self) {
+        if self.blocked_by_read_only("Adding profiles") {
+            return;
+        }
+        let contents = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Could not read the clipboard: {}", e));
+                return;
+            }
+        };
+        
+        match core::validate_cookie(&contents) {
+            Ok(token) => {
+                self.pasted_cookie = Some(NewProfileSource::Token(token));
+                self.pasted_profile_name.clear();
+            }
+            Err(reason) => {
+                self.push_toast(ToastKind::Error, format!("Clipboard does not look like a valid cookie: {}", reason));
+            }
+        }
+    }
+    
+    // Holding the session only reads the live file, so it's allowed in read-only mode too
+    fn keep_scratch_session(&mut self) {
+        let live = self.live_cookies_path();
+        let contents = match fs::read(&live) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Could not read {}: {}", live.display(), e));
+                return;
+            }
+        };
+        if let Err(reason) = core::validate_cookie(&String::from_utf8_lossy(&contents)) {
+            self.push_toast(ToastKind::Error, format!("The current session is not a valid cookie: {}", reason));
+            return;
+        }
+        
+        self.scratch_session = Some(ScratchSession { directory: self.cookie_directory.clone(), contents, kept_at: core::unix_now() });
+        self.push_toast(ToastKind::Success, "Kept the current session until the app closes; log into anything and restore it afterwards");
+    }
+    
+    fn restore_scratch_session(&mut self) {
+        if self.blocked_by_read_only("Restoring the kept session") {
+            return;
+        }
+        let Some(scratch) = self.scratch_session.take() else {
+            return;
+        };
+        
+        let options = SwitchOptions::new(&self.config, &scratch.directory, self.cli_no_backup, self.cli_backup_dir.as_deref());
+        match core::restore_live_session(&scratch.directory, &scratch.contents, &options) {
+            Ok(backup_warning) => {
+                if let Some(warning) = backup_warning {
+                    self.push_toast(ToastKind::Warning, warning);
+                }
+                let directory = scratch.directory;
+                if directory == self.cookie_directory {
+                    self.restore_active_profile();
+                    self.session_warning = None;
+                    self.publish_profiles();
+                }
+                self.push_toast(ToastKind::Success, format!("Restored the kept session in {}", directory.display()));
+            }
+            Err(e) => {
+                self.push_toast(ToastKind::Error, e.to_string());
+                self.scratch_session = Some(scratch);
+            }
+        }
+    }
+    
+    // Offer the live cookies file as a new profile, through the same naming dialog as a paste
+    fn capture_live_session(&mut self) {
+        if self.blocked_by_read_only("Adding profiles") {
+            return;
+        }
+        let live = self.live_cookies_path();
+        let contents = match core::read_cookie_text(&live) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Could not read {}: {}", live.display(), e));
+                return;
+            }
+        };
+        
+        match core::validate_cookie(&contents) {
+            Ok(_) => {
+                self.pasted_cookie = Some(NewProfileSource::LiveSession);
+                self.pasted_profile_name.clear();
+            }
+            Err(reason) => {
+                self.push_toast(ToastKind::Error, format!("The current session is not a valid cookie: {}", reason));
+            }
+        }
+    }
+    
+    fn save_pasted_cookie(&mut self, ctx: &egui::Context) {
+        if self.blocked_by_read_only("Adding profiles") {
+            return;
+        }
+        let Some(source) = &self.pasted_cookie else {
+            return;
+        };
+        
+        let name = match core::validate_new_profile_name(&self.cookie_directory, &self.pasted_profile_name) {
+            Ok(name) => name,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, e.to_string());
+                return;
+            }
+        };
+        
+        let live = self.live_cookies_path();
+        let from_live = matches!(source, NewProfileSource::LiveSession);
+        let (path, result) = if self.config.compress_new_profiles {
+            let path = self.cookie_directory.join(format!("cookies_{}.txt.gz", name));
+            let contents = match source {
+                NewProfileSource::Token(token) => Ok(core::format_cookie_file(token)),
+                NewProfileSource::LiveSession => core::read_cookie_text(&live),
+            };
+            let result = contents.and_then(|contents| core::write_compressed(&path, contents.as_bytes()));
+            (path, result)
+        } else {
+            let path = self.cookie_directory.join(format!("cookies_{}.txt", name));
+            let result = match source {
+                NewProfileSource::Token(token) => fs::write(&path, core::format_cookie_file(token)),
+                NewProfileSource::LiveSession => fs::copy(&live, &path).map(|_| ()),
+            };
+            (path, result)
+        };
+        match result {
+            Ok(()) => {
+                self.pasted_cookie = None;
+                self.record_checksum(&name, &path);
+                // The copy is what Sober is running on, so it becomes the active profile
+                if from_live {
+                    core::write_active_marker(&self.cookie_directory, &name);
+                }
+                self.load_profiles(ctx);
+                if from_live {
+                    self.restore_active_profile();
+                    self.publish_profiles();
+                }
+                self.push_toast(ToastKind::Success, format!("Created {} profile", core::format_profile_name(&name)));
+            }
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Failed to write {}: {}", path.display(), e));
+            }
+        }
+    }
+    
+    // The export is left where it is; only the converted cookie jar is written
+    fn import_cookie_editor_json(&mut self, ctx: &egui::Context, source: &Path) {
+        let jar = match fs::read_to_string(source) {
+            Ok(contents) => core::parse_cookie_editor_json(&contents),
+            Err(e) => Err(format!("could not read it: {}", e)),
+        };
+        let jar = match jar {
+            Ok(jar) => jar,
+            Err(reason) => {
+                self.push_toast(ToastKind::Error, format!("Can't import {}: {}", source.display(), reason));
+                return;
+            }
+        };
+        let name = match core::validate_new_profile_name(&self.cookie_directory, self.import_dialog.as_ref().map_or("", |d| d.name.as_str())) {
+            Ok(name) => name,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, e.to_string());
+                return;
+            }
+        };
+        
+        let target = self.cookie_directory.join(format!("cookies_{}.txt", name));
+        match fs::write(&target, jar) {
+            Ok(()) => {
+                self.import_dialog = None;
+                self.record_checksum(&name, &target);
+                self.load_profiles(ctx);
+                self.push_toast(ToastKind::Success, format!("Imported the Cookie-Editor export as the {} profile", core::format_profile_name(&name)));
+            }
+            Err(e) => self.push_toast(ToastKind::Error, format!("Failed to write {}: {}", target.display(), e)),
+        }
+    }
+    
+    // Bring a cookie file from elsewhere into the directory, moving it if asked to
+    fn import_cookie_file(&mut self, ctx: &egui::Context) {
+        if self.blocked_by_read_only("Importing profiles") {
+            return;
+        }
+        let Some(dialog) = self.import_dialog.as_mut() else {
+            return;
+        };
+        
+        let source = core::expand_path(dialog.source.trim());
+        if dialog.cookie_editor {
+            self.import_cookie_editor_json(ctx, &source);
+            return;
+        }
+        let contents = match core::read_cookie_text(&source) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Could not read {}: {}", source.display(), e));
+                return;
+            }
+        };
+        if let Err(reason) = core::validate_cookie(&contents) {
+            self.push_toast(ToastKind::Error, format!("{} does not look like a valid cookie: {}", source.display(), reason));
+            return;
+        }
+        
+        let name = match core::validate_new_profile_name(&self.cookie_directory, &dialog.name) {
+            Ok(name) => name,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, e.to_string());
+                return;
+            }
+        };
+        
+        // Removing the original is the one part of an import that can't be undone, so ask first
+        if dialog.move_source && !dialog.confirm_move {
+            dialog.confirm_move = true;
+            return;
+        }
+        let move_source = dialog.move_source;
+        
+        let source_name = source.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let target = self.cookie_directory.join(core::cookie_file_name(&name, &source_name));
+        let result = if move_source {
+            core::move_file(&source, &target)
+        } else {
+            fs::copy(&source, &target).map(|_| ())
+        };
+        
+        match result {
+            Ok(()) => {
+                self.import_dialog = None;
+                self.record_checksum(&name, &target);
+                self.load_profiles(ctx);
+                let verb = if move_source { "Moved" } else { "Imported" };
+                self.push_toast(ToastKind::Success, format!("{} {} as the {} profile", verb, source.display(), core::format_profile_name(&name)));
+            }
+            Err(e) if target.exists() => {
+                // The copy landed but the original couldn't be removed afterwards
+                self.import_dialog = None;
+                self.record_checksum(&name, &target);
+                self.load_profiles(ctx);
+                self.push_toast(ToastKind::Warning, format!("Imported {} but could not remove the original: {}", source.display(), e));
+            }
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Failed to import {}: {}", source.display(), e));
+            }
+        }
+    }
+    
+    // force skips the require_sober_closed check
+    fn copy_cookie_file(&mut self, ctx: &egui::Context, profile_index: usize, force: bool) -> bool {
+        if self.blocked_by_read_only("Switching") {
+            return false;
+        }
+        if self.config.require_sober_closed && !force && core::sober_running() {
+            if let Some(profile) = self.profiles.get(profile_index) {
+                self.profile_dialog = Some(ProfileDialog::ForceSwitch { name: profile.name.clone() });
+            }
+            self.push_toast(ToastKind::Warning, "Close Sober to switch, or force the switch");
+            return false;
+        }
+        let mut switched = false;
+        let place = self.launch_place.take();
+        if let Some(profile) = self.profiles.get(profile_index).cloned() {
+            let source_path = self.cookie_directory.join(&profile.cookie_file);
+            let target_path = self.live_cookies_path();
+            
+            let options = SwitchOptions::new(&self.config, &self.cookie_directory, self.cli_no_backup, self.cli_backup_dir.as_deref());
+            match core::apply_profile_by_name(&self.cookie_directory, &profile.name, &options) {
+                Ok(SwitchOutcome::AlreadyActive) => {
+                    self.push_toast(ToastKind::Success, format!("{} is already active", profile.display_name));
+                    self.mirror_switch();
+                    switched = true;
+                    if place.is_some() {
+                        self.launch_after_switch(ctx, &profile.name, place.as_ref());
+                    }
+                }
+                Ok(SwitchOutcome::Switched { backup_warning }) => {
+                    if let Some(warning) = backup_warning {
+                        self.push_toast(ToastKind::Warning, warning);
+                    }
+                    log::info!("Successfully copied {} to {}", source_path.display(), target_path.display());
+                    self.push_toast(ToastKind::Success, format!("Switched to {} profile", profile.display_name));
+                    self.mirror_switch();
+                    Self::announce_switch(&self.config, &profile.display_name);
+                    self.config.record_switch(&profile.name);
+                    if let Err(e) = self.save_config() {
+                        log::error!("Failed to save switch history: {}", e);
+                    }
+                    self.publish_profiles();
+                    switched = true;
+                    
+                    if self.config.confirm_identity_after_switch {
+                        self.start_identity_check(ctx, profile.name.clone());
+                    }
+                    
+                    if self.config.shared_machine && profile.name == self.config.shared_profile && !self.profiles_locked {
+                        self.vault_dialog = Some(VaultDialog::Lock { keep: profile.name.clone(), password: String::new(), confirm: String::new() });
+                    }
+                    
+                    if self.config.auto_launch || place.is_some() {
+                        self.launch_after_switch(ctx, &profile.name, place.as_ref());
+                    }
+                }
+                Err(e @ AppError::BackupFailed { .. }) => {
+                    self.push_toast(ToastKind::Error, format!("{}, not switching", e));
+                }
+                Err(e) => {
+                    self.push_toast(ToastKind::Error, e.to_string());
+                }
+            }
+        }
+        if switched {
+            self.session_warning = None;
+        }
+        switched
+    }
+    
+    // Copy the new live file to the extra targets; a failed one doesn't undo the switch
+    fn mirror_switch(&mut self) {
+        let targets = self.config.mirror_targets();
+        if targets.is_empty() {
+            return;
+        }
+        let results = core::mirror_live_cookies(&self.cookie_directory, &self.live_file(), &targets, self.config.freeze_live_cookies);
+        let failures: Vec<String> = results.iter().filter_map(|(_, result)| result.as_ref().err().map(|e| e.to_string())).collect();
+        if failures.is_empty() {
+            log::info!("Mirrored the live cookies to {} other target(s)", results.len());
+            return;
+        }
+        let summary = format!("Mirrored to {} of {} targets; {}", results.len() - failures.len(), results.len(), failures.join("; "));
+        log::error!("{}", summary);
+        self.push_toast(ToastKind::Error, summary);
+    }
+    
+    // Index of the profile that is live: the marker if the live file still matches it, else any
+    // profile with identical contents, else the marker alone (Sober has rotated the session since)
+    fn detect_active_profile(directory: &Path, live_file: &str, profiles: &[Profile]) -> Option<usize> {
+        let marker = core::read_active_marker(directory).and_then(|name| profiles.iter().position(|p| p.name == name));
+        if !directory.join(live_file).exists() {
+            return marker;
+        }
+        let matches_live = |profile: &Profile| core::is_live(directory, live_file, &profile.cookie_file);
+        
+        if let Some(index) = marker.filter(|&i| matches_live(&profiles[i])) {
+            return Some(index);
+        }
+        profiles.iter().position(matches_live).or(marker)
+    }
+    
+    // Select whichever profile is live; one only found by its contents is written to the marker
+    // so it is still recognised after Sober rotates the session
+    fn restore_active_profile(&mut self) {
+        self.selected_profile = Self::detect_active_profile(&self.cookie_directory, &self.live_file(), &self.profiles);
+        if let Some(profile) = self.selected_profile.and_then(|index| self.profiles.get(index)) {
+            if !self.read_only() && core::read_active_marker(&self.cookie_directory).as_deref() != Some(profile.name.as_str()) {
+                core::write_active_marker(&self.cookie_directory, &profile.name);
+            }
+        }
+    }
+    
+    fn launch_after_switch(&mut self, ctx: &egui::Context, name: &str, game: Option<&GameShortcut>) {
+        match Self::launch_sober(&self.config.launch_command, game) {
+            Ok(()) => {
+                if let Some(game) = game {
+                    self.push_toast(ToastKind::Success, format!("Launching {}", game.label));
+                }
+                if self.config.check_login_after_launch {
+                    self.start_login_check(ctx, name.to_string());
+                }
+            }
+            Err(e) => self.push_toast(ToastKind::Error, format!("Failed to launch Sober: {}", e)),
+        }
+    }
+    
+    // Run the configured launch command, joining the game's place if given; the child is reaped
+    // on a helper thread
+    fn launch_sober(command: &str, game: Option<&GameShortcut>) -> io::Result<()> {
+        let mut parts = command.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "launch command is empty"))?;
+        let url = game.map(GameShortcut::launch_url);
+        let mut child = std::process::Command::new(program).args(parts).args(&url).spawn()?;
+        match &url {
+            Some(url) => log::info!("Launched Sober with '{} {}'", command, url),
+            None => log::info!("Launched Sober with '{}'", command),
+        }
+        
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+    
+    // Desktop notification and/or chime for a finished switch, sent from a helper thread so a slow
+    // notification daemon can't stall the UI
+    fn announce_switch(config: &Config, display_name: &str) {
+        let notify = config.notify_on_switch;
+        let sound = config.sound_on_switch;
+        if !notify && !sound {
+            return;
+        }
+        
+        let body = format!("Switched to {}", display_name);
+        thread::spawn(move || {
+            if notify {
+                let mut notification = notify_rust::Notification::new();
+                notification.summary("Sober Profile Changer").body(&body).icon("system-users");
+                if sound {
+                    notification.sound_name(SWITCH_SOUND);
+                }
+                match notification.show() {
+                    Ok(_) => return,
+                    Err(e) => log::warn!("Failed to show the switch notification: {}", e),
+                }
+            }
+            if sound {
+                let played = std::process::Command::new("canberra-gtk-play").args(["-i", SWITCH_SOUND]).status();
+                if let Err(e) = played {
+                    log::warn!("Failed to play the switch sound: {}", e);
+                }
+            }
+        });
+    }
+    
+    // Ask Roblox who the token belongs to; Ok holds the username
+    fn fetch_authenticated_user(token: &str) -> Result<String, String> {
+        #[derive(Deserialize)]
+        struct AuthenticatedUser {
+            name: String,
+        }
+        
+        let response = ureq::get(AUTHENTICATED_USER_URL)
+            .set("Cookie", &format!("{}={}", ROBLOSECURITY, token))
+            .timeout(Duration::from_secs(10))
+            .call();
+        
+        match response {
+            Ok(response) => {
+                let body = response.into_string().map_err(|e| format!("Failed to read response: {}", e))?;
+                serde_json::from_str::<AuthenticatedUser>(&body)
+                    .map(|user| user.name)
+                    .map_err(|e| format!("Unexpected response: {}", e))
+            }
+            Err(ureq::Error::Status(401, _)) => Err(NOT_LOGGED_IN.to_string()),
+            Err(ureq::Error::Status(code, _)) => Err(format!("Roblox returned HTTP {}", code)),
+            Err(e) => Err(format!("Request failed: {}", e)),
+        }
+    }
+    
+    fn start_login_check(&mut self, ctx: &egui::Context, profile_name: String) {
+        if self.login_receiver.is_some() || self.last_login_check.is_some_and(|at| at.elapsed() < LOGIN_CHECK_COOLDOWN) {
+            log::info!("Skipping login check for {}: one ran less than a minute ago", profile_name);
+            return;
+        }
+        self.last_login_check = Some(Instant::now());
+        
+        let live_cookies = self.live_cookies_path();
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        self.login_receiver = Some(receiver);
+        
+        thread::spawn(move || {
+            thread::sleep(LOGIN_CHECK_DELAY);
+            
+            // Sober may have rotated the session by now, so check the live file rather than the profile
+            let result = fs::read_to_string(&live_cookies)
+                .map_err(|e| format!("Could not read {}: {}", live_cookies.display(), e))
+                .and_then(|contents| core::parse_roblosecurity(&contents).ok_or_else(|| "No .ROBLOSECURITY in the live cookies file".to_string()))
+                .and_then(|token| SoberApp::fetch_authenticated_user(&token));
+            
+            sender.send((profile_name, result)).ok();
+            ctx.request_repaint();
+        });
+    }
+    
+    fn poll_login_check(&mut self, ctx: &egui::Context) {
+        let Some(receiver) = &self.login_receiver else {
+            return;
+        };
+        
+        let (profile_name, result) = match receiver.try_recv() {
+            Ok(message) => message,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.login_receiver = None;
+                return;
+            }
+        };
+        self.login_receiver = None;
+        
+        let display_name = core::format_profile_name(&profile_name);
+        match &result {
+            Ok(username) => self.push_toast(ToastKind::Success, format!("{} logged in as {}", display_name, username)),
+            Err(e) => self.push_toast(ToastKind::Warning, format!("{} did not log in: {}", display_name, e)),
+        }
+        
+        let checked_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let ok = result.is_ok();
+        let detail = result.unwrap_or_else(|e| e);
+        self.profile_configs_mut().entry(profile_name).or_default().last_login = Some(LoginResult { ok, detail, checked_at });
+        self.save_profile_configs();
+        let selected = self.selected_profile_name();
+        self.reload_profiles_keeping_selection(ctx, selected);
+    }
+    
+    // Who the live file logs in as right after a switch, before Sober has had a chance to touch it
+    fn start_identity_check(&mut self, ctx: &egui::Context, profile_name: String) {
+        let live_cookies = self.live_cookies_path();
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        // A newer switch replaces the check of the previous one
+        self.identity_receiver = Some(receiver);
+        
+        thread::spawn(move || {
+            let result = core::read_cookie_text(&live_cookies)
+                .map_err(|e| format!("Could not read {}: {}", live_cookies.display(), e))
+                .and_then(|contents| core::parse_roblosecurity(&contents).ok_or_else(|| "No .ROBLOSECURITY in the live cookies file".to_string()))
+                .and_then(|token| SoberApp::fetch_authenticated_user(&token));
+            sender.send((profile_name, result)).ok();
+            ctx.request_repaint();
+        });
+    }
+    
+    fn poll_identity_check(&mut self) {
+        let Some(receiver) = &self.identity_receiver else {
+            return;
+        };
+        let (profile_name, result) = match receiver.try_recv() {
+            Ok(message) => message,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.identity_receiver = None;
+                return;
+            }
+        };
+        self.identity_receiver = None;
+        
+        let display_name = self.find_profile(&profile_name).map_or_else(|| core::format_profile_name(&profile_name), |p| p.display_name.clone());
+        let expected = self.find_profile(&profile_name).and_then(|p| p.username.clone());
+        match result {
+            Ok(username) => match expected {
+                Some(expected) if !expected.eq_ignore_ascii_case(&username) => {
+                    let warning = format!("Now playing as {}, not {} as expected for {} — check the profile before launching", username, expected, display_name);
+                    log::warn!("{}", warning);
+                    self.push_toast(ToastKind::Error, warning);
+                }
+                expected => {
+                    self.push_toast(ToastKind::Success, format!("Now playing as {}", username));
+                    // Remembered so a later switch has something to compare against
+                    if expected.is_none() && self.config.fetch_usernames && !self.read_only() {
+                        self.set_fetched_username(&profile_name, username);
+                    }
+                }
+            },
+            Err(e) => {
+                log::warn!("Could not confirm who {} logs in as: {}", profile_name, e);
+                self.push_toast(ToastKind::Error, format!("Could not confirm who {} logs in as: {}", display_name, e));
+            }
+        }
+    }
+    
+    // Cookie files changed by other tools between sessions; a snapshot of another directory says nothing
+    fn report_changes_since_last_open(&mut self) {
+        let Some(previous) = self.config.last_snapshot.as_ref().filter(|s| s.directory == self.cookie_directory) else {
+            return;
+        };
+        if let Some(summary) = previous.diff(&DirectorySnapshot::capture(&self.cookie_directory, &self.live_file())).summary() {
+            self.push_toast(ToastKind::Warning, format!("{} since last open", summary));
+        }
+    }
+    
+    // Offline token check of the live cookies file, then optionally a background check with Roblox
+    fn check_live_session(&mut self, ctx: &egui::Context) {
+        let live = self.live_cookies_path();
+        if !live.exists() {
+            self.warn_about_session("there is no live cookies file".to_string());
+            return;
+        }
+        match CookieHealth::check_file(&live) {
+            CookieHealth::Valid => {}
+            health => {
+                self.warn_about_session(health.description());
+                return;
+            }
+        }
+        if !self.config.check_session_online {
+            return;
+        }
+        
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        self.session_check_receiver = Some(receiver);
+        
+        thread::spawn(move || {
+            let result = core::read_cookie_text(&live)
+                .map_err(|e| format!("Could not read {}: {}", live.display(), e))
+                .and_then(|contents| core::parse_roblosecurity(&contents).ok_or_else(|| "No .ROBLOSECURITY in the live cookies file".to_string()))
+                .and_then(|token| SoberApp::fetch_authenticated_user(&token));
+            sender.send(result).ok();
+            ctx.request_repaint();
+        });
+    }
+    
+    fn poll_session_check(&mut self) {
+        let Some(receiver) = &self.session_check_receiver else {
+            return;
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.session_check_receiver = None;
+                return;
+            }
+        };
+        self.session_check_receiver = None;
+        
+        match result {
+            Ok(username) => log::info!("Live session is logged in as {}", username),
+            Err(e) if e == NOT_LOGGED_IN => self.warn_about_session("Roblox no longer accepts the token".to_string()),
+            // Being offline says nothing about the session
+            Err(e) => log::warn!("Could not verify the live session online: {}", e),
+        }
+    }
+    
+    fn warn_about_session(&mut self, reason: String) {
+        let warning = format!("Current session appears invalid ({}) — switch or re-login", reason);
+        log::warn!("{}", warning);
+        // The banner only exists in the full window
+        if self.config.mini_mode {
+            self.push_toast(ToastKind::Warning, warning.clone());
+        }
+        self.session_warning = Some(warning);
+    }
+    
+    fn reminder_due(profile: &Profile) -> bool {
+        profile.remind_at.is_some_and(|at| at <= core::unix_now())
+    }
+    
+    fn set_reminder(&mut self, profile_name: &str, remind_at: Option<u64>) {
+        self.profile_configs_mut().entry(profile_name.to_string()).or_default().remind_at = remind_at;
+        self.save_profile_configs();
+    }
+    
+    fn format_timestamp(secs: u64) -> String {
+        chrono::DateTime::from_timestamp(secs as i64, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    }
+    
+    fn describe_login(result: &LoginResult) -> String {
+        if result.ok {
+            format!("Logged in as {} ({})", result.detail, Self::format_timestamp(result.checked_at))
+        } else {
+            format!("Login failed: {} ({})", result.detail, Self::format_timestamp(result.checked_at))
+        }
+    }
+    
+    // Validate every cookie file on a background thread; results stream in through poll_verification
+    fn start_verification(&mut self, ctx: &egui::Context) {
+        let directory = self.cookie_directory.clone();
+        let fetch_usernames = self.config.fetch_usernames;
+        // (name, file, whether the username still needs fetching)
+        let profiles: Vec<(String, OsString, bool)> = self
+            .profiles
+            .iter()
+            .map(|p| (p.name.clone(), p.cookie_file.clone(), fetch_usernames && p.username.is_none()))
+            .collect();
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        
+        self.cookie_health.clear();
+        self.verify_receiver = Some(receiver);
+        
+        thread::spawn(move || {
+            for (profile_name, cookie_file, needs_username) in profiles {
+                let path = directory.join(&cookie_file);
+                let health = CookieHealth::check_file(&path);
+                
+                let username = if needs_username && matches!(health, CookieHealth::Valid) {
+                    let token = core::read_cookie_text(&path).ok().and_then(|c| core::parse_roblosecurity(&c));
+                    match token.map(|token| SoberApp::fetch_authenticated_user(&token)) {
+                        Some(Ok(username)) => Some(username),
+                        Some(Err(e)) => {
+                            log::warn!("Could not fetch the username for {}: {}", profile_name, e);
+                            None
+                        }
+                        None => None,
+                    }
+                } else {
+                    None
+                };
+                
+                let result = VerifyResult { profile_name, cookie_file, health, username };
+                if sender.send(result).is_err() {
+                    // The app moved on (directory change or a new scan)
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+    }
+    
+    fn set_fetched_username(&mut self, profile_name: &str, username: String) {
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == profile_name) {
+            profile.display_name = username.clone();
+            profile.username = Some(username.clone());
+        }
+        self.profile_configs_mut().entry(profile_name.to_string()).or_default().username = Some(username);
+        self.save_profile_configs();
+    }
+    
+    fn poll_verification(&mut self) {
+        let Some(receiver) = &self.verify_receiver else {
+            return;
+        };
+        
+        let mut results = Vec::new();
+        let finished = loop {
+            match receiver.try_recv() {
+                Ok(result) => results.push(result),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        
+        for result in results {
+            if let Some(username) = result.username {
+                self.set_fetched_username(&result.profile_name, username);
+            }
+            self.cookie_health.insert(result.cookie_file, result.health);
+        }
+        if !finished {
+            return;
+        }
+        
+        self.verify_receiver = None;
+        
+        // Summarize in a fixed order, e.g. "8 valid, 2 missing token, 1 unreadable"
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for profile in &self.profiles {
+            if let Some(health) = self.cookie_health.get(&profile.cookie_file) {
+                match counts.iter_mut().find(|(label, _)| *label == health.label()) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((health.label(), 1)),
+                }
+            }
+        }
+        let order = ["valid", "empty", "missing token", "malformed", "unreadable"];
+        counts.sort_by_key(|(label, _)| order.iter().position(|o| o == label));
+        
+        let summary = counts
+            .iter()
+            .map(|(label, count)| format!("{} {}", count, label))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let all_valid = counts.iter().all(|(label, _)| *label == "valid");
+        
+        if summary.is_empty() {
+            self.push_toast(ToastKind::Warning, "No profiles to verify");
+        } else if all_valid {
+            self.push_toast(ToastKind::Success, format!("Verified: {}", summary));
+        } else {
+            self.push_toast(ToastKind::Warning, format!("Verified: {}", summary));
+        }
+    }
+    
+    fn push_toast(&mut self, kind: ToastKind, message: impl Into<String>) {
+        let message = message.into();
+        match kind {
+            ToastKind::Success => log::info!("{}", message),
+            ToastKind::Warning => log::warn!("{}", message),
+            ToastKind::Error => log::error!("{}", message),
+        }
+        
+        self.toasts.push(Toast {
+            message,
+            kind,
+            created: Instant::now(),
+        });
+        
+        // Drop the oldest once the stack is full
+        if self.toasts.len() > MAX_TOASTS {
+            let excess = self.toasts.len() - MAX_TOASTS;
+            self.toasts.drain(..excess);
+        }
+    }
+    
+    fn draw_toasts(&mut self, ctx: &egui::Context) {
+        self.toasts.retain(|toast| toast.created.elapsed() < TOAST_LIFETIME);
+        if self.toasts.is_empty() {
+            return;
+        }
+        
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(Align2::RIGHT_TOP, Vec2::new(-10.0, 50.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                for toast in self.toasts.iter().rev() {
+                    // Fade out over the last part of the toast's lifetime
+                    let remaining = TOAST_LIFETIME.saturating_sub(toast.created.elapsed());
+                    let opacity = (remaining.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0);
+                    
+                    let (icon, color) = match toast.kind {
+                        ToastKind::Success => ("✅", Color32::from_rgb(40, 110, 60)),
+                        ToastKind::Warning => ("⚠", Color32::from_rgb(140, 110, 30)),
+                        ToastKind::Error => ("❌", Color32::from_rgb(150, 45, 40)),
+                    };
+                    
+                    egui::Frame::none()
+                        .fill(color.gamma_multiply(opacity))
+                        .rounding(Rounding::same(6.0))
+                        .inner_margin(8.0)
+                        .show(ui, |ui| {
+                            ui.set_max_width(260.0);
+                            ui.label(
+                                egui::RichText::new(format!("{} {}", icon, toast.message))
+                                    .font(FontId::proportional(12.0))
+                                    .color(Color32::WHITE.gamma_multiply(opacity))
+                            );
+                        });
+                    ui.add_space(6.0);
+                }
+            });
+        
+        // Keep repainting while a toast is fading, otherwise wake up when the next one starts to
+        let next_fade = self
+            .toasts
+            .iter()
+            .map(|toast| (TOAST_LIFETIME - TOAST_FADE).saturating_sub(toast.created.elapsed()))
+            .min()
+            .unwrap_or_default();
+        if next_fade.is_zero() {
+            ctx.request_repaint();
+        } else {
+            ctx.request_repaint_after(next_fade);
+        }
+    }
+    
+    fn open_directory_dialog(&mut self) {
+        if self.config.directory_pinned {
+            self.push_toast(ToastKind::Warning, "The cookie directory is pinned; unpin it to change it");
+            return;
+        }
+        self.show_directory_dialog = true;
+        self.temp_directory_input = self.cookie_directory.to_string_lossy().to_string();
+        self.recent_directories = core::load_directory_history();
+        self.directory_counts.clear();
+    }
+    
+    fn set_directory_pinned(&mut self, pinned: bool) {
+        if self.blocked_by_read_only("Pinning the directory") {
+            return;
+        }
+        self.config.directory_pinned = pinned;
+        if let Err(e) = self.save_config() {
+            self.push_toast(ToastKind::Error, format!("Failed to save settings: {}", e));
+        }
+    }
+    
+    // Number of cookies_* files in a recent directory, None if it can't be read; rescanned
+    // once the cached count is older than DIRECTORY_COUNT_TTL
+    fn directory_profile_count(&mut self, path: &str) -> Option<usize> {
+        if let Some((counted_at, count)) = self.directory_counts.get(path) {
+            if counted_at.elapsed() < DIRECTORY_COUNT_TTL {
+                return *count;
+            }
+        }
+        
+        let directory = core::expand_path(path);
+        let count = core::scan_cookie_files(&directory, &self.config.live_cookie_file(&directory)).ok().map(|files| files.len());
+        self.directory_counts.insert(path.to_string(), (Instant::now(), count));
+        count
+    }
+    
+    fn create_cookie_directory(&mut self, ctx: &egui::Context) {
+        if self.blocked_by_read_only("Creating the directory") {
+            return;
+        }
+        match fs::create_dir_all(&self.cookie_directory) {
+            Ok(()) => {
+                log::info!("Created {}", self.cookie_directory.display());
+                self.load_profiles(ctx);
+                self.push_toast(ToastKind::Success, format!("Created {}", self.cookie_directory.display()));
+            }
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Failed to create {}: {}", self.cookie_directory.display(), e));
+            }
+        }
+    }
+    
+    // Keep the live file a new directory's client already left there, so the target stays put
+    // even while that file is briefly missing; with nothing found, "cookies" is used
+    fn remember_live_file(&mut self) {
+        if self.read_only() || self.config.cookie_targets.contains_key(&self.cookie_directory) {
+            return;
+        }
+        let Some(detected) = core::detect_live_cookie_file(&self.cookie_directory) else {
+            return;
+        };
+        log::info!("Using {} as the live cookies file in {}", detected, self.cookie_directory.display());
+        self.config.cookie_targets.insert(self.cookie_directory.clone(), detected.to_string());
+        if let Err(e) = self.save_config() {
+            log::error!("Failed to save the live cookies file name: {}", e);
+        }
+    }
+    
+    fn apply_directory_change(&mut self, ctx: &egui::Context) {
+        let new_path = core::expand_path(&self.temp_directory_input);
+        
+        if new_path.exists() && new_path.is_dir() {
+            self.cookie_directory = new_path;
+            self.live_file_input = None;
+            self.cookie_health.clear();
+            self.verify_receiver = None;
+            self.legacy_renames = None;
+            self.session_warning = None;
+            self.session_check_receiver = None;
+            self.save_directory();
+            self.remember_live_file();
+            self.load_profiles(ctx);
+            self.restore_active_profile();
+            self.show_directory_dialog = false;
+            self.push_toast(ToastKind::Success, "Directory changed successfully");
+            if core::overlaps_config_dir(&self.cookie_directory, &core::get_config_dir()) {
+                self.push_toast(
+                    ToastKind::Warning,
+                    "This directory overlaps the manager's config folder; its own files are never treated as profiles, but a separate folder is safer"
+                );
+            }
+        } else {
+            self.push_toast(ToastKind::Error, "Directory does not exist or is not a directory");
+        }
+    }
+    
+    fn load_background_image(&mut self, ctx: &egui::Context) {
+        let path = core::get_config_dir().join("background.png");
+        self.background_image = Self::load_image_from_path(ctx, path.to_str().unwrap_or(""));
+    }
+    
+    // Paint the configured background behind the central panel's contents
+    fn draw_background(&self, ui: &egui::Ui) {
+        let theme = &self.config.theme;
+        let rect = ui.max_rect();
+        let painter = ui.painter();
+        
+        match theme.background {
+            BackgroundStyle::Solid => return,
+            BackgroundStyle::Gradient => {
+                let [tr, tg, tb] = theme.gradient_top;
+                let [br, bg, bb] = theme.gradient_bottom;
+                let top = Color32::from_rgb(tr, tg, tb);
+                let bottom = Color32::from_rgb(br, bg, bb);
+                
+                let mut mesh = egui::Mesh::default();
+                mesh.colored_vertex(rect.left_top(), top);
+                mesh.colored_vertex(rect.right_top(), top);
+                mesh.colored_vertex(rect.left_bottom(), bottom);
+                mesh.colored_vertex(rect.right_bottom(), bottom);
+                mesh.add_triangle(0, 1, 2);
+                mesh.add_triangle(1, 2, 3);
+                painter.add(egui::Shape::mesh(mesh));
+            }
+            BackgroundStyle::Image => {
+                let Some(texture) = &self.background_image else {
+                    return;
+                };
+                
+                // Crop to cover the panel without stretching
+                let image_aspect = texture.aspect_ratio();
+                let rect_aspect = rect.width() / rect.height();
+                let uv = if image_aspect > rect_aspect {
+                    let visible = rect_aspect / image_aspect;
+                    Rect::from_min_max(Pos2::new((1.0 - visible) / 2.0, 0.0), Pos2::new((1.0 + visible) / 2.0, 1.0))
+                } else {
+                    let visible = image_aspect / rect_aspect;
+                    Rect::from_min_max(Pos2::new(0.0, (1.0 - visible) / 2.0), Pos2::new(1.0, (1.0 + visible) / 2.0))
+                };
+                painter.image(texture.id(), rect, uv, Color32::WHITE);
+            }
+        }
+        
+        if theme.background_dim > 0.0 {
+            painter.rect_filled(rect, Rounding::ZERO, Color32::from_black_alpha((theme.background_dim * 255.0) as u8));
+        }
+    }
+    
+    fn draw_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        let mut changed = false;
+        let mut reload_avatars = false;
+        let mut reset = false;
+        
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("Background").strong());
+                
+                let previous = self.config.theme.background;
+                ui.horizontal(|ui| {
+                    changed |= ui.radio_value(&mut self.config.theme.background, BackgroundStyle::Solid, "Solid").changed();
+                    changed |= ui.radio_value(&mut self.config.theme.background, BackgroundStyle::Gradient, "Gradient").changed();
+                    changed |= ui.radio_value(&mut self.config.theme.background, BackgroundStyle::Image, "Image").changed();
+                });
+                
+                match self.config.theme.background {
+                    BackgroundStyle::Solid => {}
+                    BackgroundStyle::Gradient => {
+                        ui.horizontal(|ui| {
+                            ui.label("Top");
+                            changed |= ui.color_edit_button_srgb(&mut self.config.theme.gradient_top).changed();
+                            ui.label("Bottom");
+                            changed |= ui.color_edit_button_srgb(&mut self.config.theme.gradient_bottom).changed();
+                        });
+                    }
+                    BackgroundStyle::Image => {
+                        ui.label(
+                            egui::RichText::new(format!("Place background.png in {}", core::get_config_dir().display()))
+                                .font(FontId::proportional(10.0))
+                                .color(Color32::GRAY)
+                        );
+                        if ui.button("↻ Reload image").clicked() {
+                            self.load_background_image(ctx);
+                        }
+                    }
+                }
+                
+                if self.config.theme.background != BackgroundStyle::Solid {
+                    changed |= ui
+                        .add(egui::Slider::new(&mut self.config.theme.background_dim, 0.0..=0.8).text("Dim"))
+                        .changed();
+                }
+                
+                ui.horizontal(|ui| {
+                    ui.label("Panel");
+                    changed |= ui.color_edit_button_srgb(&mut self.config.theme.panel).changed();
+                    ui.label("Accent");
+                    changed |= ui.color_edit_button_srgb(&mut self.config.theme.accent).changed();
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("Logo");
+                    let logo_edit = ui
+                        .add(
+                            egui::TextEdit::singleline(&mut self.config.theme.logo_path)
+                                .desired_width(200.0)
+                                .hint_text(core::LOGO_FILE_NAME)
+                        )
+                        .on_hover_text("Left empty, Sober_logo.png is looked for next to the app, in the config folder and in the working directory");
+                    if logo_edit.lost_focus() {
+                        changed = true;
+                        self.sober_logo = Self::load_logo(ctx, &self.config.theme.logo_path);
+                    }
+                });
+                
+                if previous != BackgroundStyle::Image && self.config.theme.background == BackgroundStyle::Image {
+                    self.load_background_image(ctx);
+                    if self.background_image.is_none() {
+                        self.push_toast(ToastKind::Warning, "No background.png found; using the solid background");
+                    }
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Layout").strong());
+                
+                ui.horizontal(|ui| {
+                    changed |= ui.radio_value(&mut self.config.grid_alignment, GridAlignment::Center, "Centered").changed();
+                    changed |= ui.radio_value(&mut self.config.grid_alignment, GridAlignment::Left, "Left-aligned").changed();
+                });
+                
+                let previous_sort = self.config.sort_mode;
+                ui.horizontal(|ui| {
+                    ui.label("Sort");
+                    ui.radio_value(&mut self.config.sort_mode, SortMode::Name, "By name");
+                    ui.radio_value(&mut self.config.sort_mode, SortMode::NewestAdded, "Newest added first");
+                });
+                if self.config.sort_mode != previous_sort {
+                    changed = true;
+                    let selected = self.selected_profile_name();
+                    self.reload_profiles_keeping_selection(ctx, selected);
+                }
+                
+                ui.horizontal(|ui| {
+                    ui.label("Emojis");
+                    if ui
+                        .add(egui::TextEdit::singleline(&mut self.config.emoji_palette).desired_width(200.0))
+                        .on_hover_text("Separated by spaces; profiles without a pinned emoji take them in order")
+                        .lost_focus()
+                    {
+                        changed = true;
+                        let selected = self.selected_profile_name();
+                        self.reload_profiles_keeping_selection(ctx, selected);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("📌 Pin current emojis").on_hover_text("Keeps each profile's emoji when profiles are added or removed").clicked() {
+                        self.pin_emojis(ctx);
+                    }
+                    if ui.button("🎲 Shuffle").on_hover_text("Pins a random emoji from the palette on every profile").clicked() {
+                        self.shuffle_emojis(ctx);
+                    }
+                    let pinned = core::profile_configs(&self.config, self.directory_config.as_ref()).values().any(|c| !c.emoji.is_empty());
+                    if pinned && ui.small_button("Unpin").clicked() {
+                        let emojis = self.profiles.iter().map(|p| (p.name.clone(), String::new())).collect();
+                        self.set_profile_emojis(ctx, emojis);
+                    }
+                });
+                
+                changed |= ui
+                    .checkbox(&mut self.config.profile_dropdown, "Show a profile dropdown under the grid")
+                    .on_hover_text("Narrow windows always use the dropdown instead of the grid")
+                    .changed();
+                
+                ui.horizontal(|ui| {
+                    ui.label("Names");
+                    changed |= ui.radio_value(&mut self.config.theme.name_placement, NamePlacement::Below, "Below").changed();
+                    changed |= ui.radio_value(&mut self.config.theme.name_placement, NamePlacement::OverlayTop, "Nickname on top").changed();
+                    changed |= ui
+                        .radio_value(&mut self.config.theme.name_placement, NamePlacement::OverlayBottom, "Nickname at the bottom")
+                        .changed();
+                });
+                if self.config.theme.name_placement == NamePlacement::Below {
+                    ui.horizontal(|ui| {
+                        ui.label("Long names");
+                        changed |= ui.radio_value(&mut self.config.theme.name_overflow, NameOverflow::Ellipsis, "Cut off with …").changed();
+                        changed |= ui.radio_value(&mut self.config.theme.name_overflow, NameOverflow::Wrap, "Wrap onto two lines").changed();
+                    });
+                } else {
+                    changed |= ui
+                        .checkbox(&mut self.config.theme.name_pill, "Draw a dark pill behind nicknames")
+                        .on_hover_text("Set a profile's nickname from its right-click menu; it defaults to the initials")
+                        .changed();
+                }
+                
+                changed |= ui
+                    .checkbox(&mut self.config.theme.hotkey_hints, "Show hotkeys on avatars")
+                    .on_hover_text("A faint corner label with each profile's hotkey; the hover card always lists it")
+                    .changed();
+                
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.config.theme.avatar_rounding, 0.0..=0.5).text("Avatar rounding"))
+                    .on_hover_text("0 gives sharp corners, 0.5 round avatars")
+                    .changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.config.theme.avatar_border_width, 0.0..=MAX_AVATAR_BORDER_WIDTH).text("Avatar border"))
+                    .changed();
+                
+                let mut avatars_changed = ui
+                    .checkbox(&mut self.config.initials_avatars, "Show coloured initials for profiles without an image")
+                    .changed();
+                ui.add_enabled_ui(self.config.initials_avatars, |ui| {
+                    avatars_changed |= ui
+                        .checkbox(&mut self.config.save_initials_avatars, "Save them as <name>.png in the cookie directory")
+                        .on_hover_text("Otherwise they are drawn each time and no files are written")
+                        .changed();
+                });
+                changed |= avatars_changed;
+                reload_avatars |= avatars_changed;
+                
+                changed |= ui
+                    .checkbox(&mut self.config.manual_window_drag, "Move the window manually when dragging")
+                    .on_hover_text("Try this if the window won't move or jumps when dragged by its title bar")
+                    .changed();
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Info").strong());
+                
+                let total_size: u64 = self.profiles.iter().map(|p| p.file_size).sum();
+                ui.label(format!(
+                    "{} profiles, {} of cookie files",
+                    self.profiles.len(),
+                    Self::format_size(total_size)
+                ));
+                if core::is_portable() {
+                    ui.label(format!("Portable mode: settings are kept in {}", core::get_config_dir().display()));
+                }
+                ui.label(
+                    egui::RichText::new("Ctrl+K opens a searchable list of profiles and actions; Ctrl+P switches back to the previous profile")
+                        .font(FontId::proportional(10.0))
+                        .color(Color32::GRAY)
+                );
+                
+                if ui.button("👋 Show the welcome screen").clicked() {
+                    self.show_onboarding = true;
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Legacy file names").strong());
+                
+                match &self.legacy_renames {
+                    None => {
+                        if ui.button("🔍 Find cookie_<name> files").on_hover_text("Older guides used cookie_ without the s; those files aren't detected").clicked() {
+                            match core::find_legacy_cookie_files(&self.cookie_directory) {
+                                Ok(renames) if renames.is_empty() => {
+                                    self.push_toast(ToastKind::Success, "No legacy cookie_ files found");
+                                }
+                                Ok(renames) => self.legacy_renames = Some(renames),
+                                Err(e) => self.push_toast(ToastKind::Error, format!("Failed to scan {}: {}", self.cookie_directory.display(), e)),
+                            }
+                        }
+                    }
+                    Some(renames) => {
+                        for rename in renames {
+                            if rename.conflict {
+                                ui.colored_label(Color32::YELLOW, format!("{} → {} (exists, skipped)", rename.from, rename.to));
+                            } else {
+                                ui.label(format!("{} → {}", rename.from, rename.to));
+                            }
+                        }
+                        
+                        let applicable = renames.iter().any(|r| !r.conflict);
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(applicable, egui::Button::new("✅ Rename")).clicked() {
+                                self.apply_legacy_renames(ctx);
+                            }
+                            if ui.button("❌ Cancel").clicked() {
+                                self.legacy_renames = None;
+                            }
+                        });
+                    }
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Other Sober installs").strong());
+                
+                let mut import = false;
+                match &mut self.other_installs {
+                    None => {
+                        if ui.button("🔍 Look for profiles in other installs").clicked() {
+                            let found = Self::find_other_installs(&self.cookie_directory, &self.config);
+                            if found.is_empty() {
+                                self.push_toast(ToastKind::Success, "No profiles found in other Sober installs");
+                            } else {
+                                self.other_installs = Some(found);
+                            }
+                        }
+                    }
+                    Some(found) => {
+                        egui::ScrollArea::vertical().id_source("other_installs").max_height(120.0).show(ui, |ui| {
+                            for profile in found.iter_mut() {
+                                let label = format!("{} ({})", core::format_profile_name(&profile.profile_name), profile.directory.display());
+                                let response = ui.checkbox(&mut profile.selected, label);
+                                if profile.collides {
+                                    response.on_hover_text("A profile with this name already exists here; it will be skipped");
+                                    ui.colored_label(Color32::YELLOW, "   name already taken");
+                                }
+                            }
+                        });
+                        
+                        let mut cancel = false;
+                        ui.horizontal(|ui| {
+                            let any = found.iter().any(|p| p.selected);
+                            import = ui.add_enabled(any, egui::Button::new("📥 Import selected")).clicked();
+                            cancel = ui.button("❌ Cancel").clicked();
+                        });
+                        if cancel {
+                            self.other_installs = None;
+                        }
+                    }
+                }
+                if import {
+                    self.import_other_installs(ctx);
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Batch rename").strong());
+                
+                egui::Grid::new("batch_rename_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Find");
+                    ui.text_edit_singleline(&mut self.batch_find);
+                    ui.end_row();
+                    ui.label("Replace with");
+                    ui.text_edit_singleline(&mut self.batch_replace);
+                    ui.end_row();
+                });
+                
+                if !self.batch_find.is_empty() {
+                    match core::plan_batch_rename(&self.cookie_directory, &self.live_file(), &self.batch_find, &self.batch_replace) {
+                        Ok(renames) if renames.is_empty() => {
+                            ui.label(egui::RichText::new("No file names would change").color(Color32::GRAY));
+                        }
+                        Ok(renames) => {
+                            egui::ScrollArea::vertical().id_source("batch_rename_preview").max_height(120.0).show(ui, |ui| {
+                                for rename in &renames {
+                                    match &rename.problem {
+                                        Some(problem) => {
+                                            ui.colored_label(Color32::YELLOW, format!("{} → {} ({})", rename.from, rename.to, problem));
+                                        }
+                                        None => {
+                                            ui.label(format!("{} → {}", rename.from, rename.to));
+                                        }
+                                    }
+                                }
+                            });
+                            
+                            // All or nothing, so a half-applied pattern never leaves a mix of names behind
+                            let valid = renames.iter().all(|r| r.problem.is_none());
+                            if ui
+                                .add_enabled(valid, egui::Button::new(format!("✅ Rename {} file(s)", renames.len())))
+                                .on_disabled_hover_text("Fix the highlighted names first")
+                                .clicked()
+                            {
+                                self.apply_batch_rename(ctx, &renames);
+                                self.batch_find.clear();
+                                self.batch_replace.clear();
+                            }
+                        }
+                        Err(e) => {
+                            ui.colored_label(Color32::LIGHT_RED, format!("Failed to scan {}: {}", self.cookie_directory.display(), e));
+                        }
+                    }
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Switching").strong());
+                
+                ui.label("Clicking a profile");
+                changed |= ui.radio_value(&mut self.config.switch_mode, SwitchMode::Immediate, "Switches to it immediately").changed();
+                changed |= ui
+                    .radio_value(&mut self.config.switch_mode, SwitchMode::DoubleClick, "Selects it; a second click switches")
+                    .changed();
+                changed |= ui.radio_value(&mut self.config.switch_mode, SwitchMode::Confirm, "Asks for confirmation first").changed();
+                
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.config.poll_interval_secs, 1..=MAX_POLL_INTERVAL_SECS).suffix(" s").text("Check for outside changes every"))
+                    .on_hover_text("How often config.json and the live cookies file are looked at; longer intervals use less power")
+                    .changed();
+                
+                changed |= ui
+                    .checkbox(&mut self.config.freeze_live_cookies, "Keep live cookies read-only between switches")
+                    .on_hover_text("Stops Sober from rotating the saved session; the file is made writable again for the next switch")
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut self.config.compress_new_profiles, "Compress new profiles (.txt.gz)")
+                    .on_hover_text("Applies to pasted cookies; existing profiles are left as they are")
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut self.config.normalize_line_endings, "Use LF line endings in the live cookies file")
+                    .on_hover_text("For profiles saved on Windows; the profile files themselves are not changed")
+                    .changed();
+                
+                changed |= ui
+                    .checkbox(&mut self.config.backup_before_switch, "Back up the live cookies file before switching")
+                    .changed();
+                ui.add_enabled_ui(self.config.backup_before_switch, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Backup folder");
+                        changed |= ui
+                            .add(
+                                egui::TextEdit::singleline(&mut self.config.backup_dir)
+                                    .desired_width(180.0)
+                                    .hint_text("cookie directory")
+                            )
+                            .lost_focus();
+                    });
+                });
+                if self.cli_no_backup || self.cli_backup_dir.is_some() {
+                    ui.label(
+                        egui::RichText::new("Overridden from the command line for this session")
+                            .font(FontId::proportional(10.0))
+                            .color(Color32::GRAY)
+                    );
+                }
+                
+                ui.horizontal(|ui| {
+                    ui.label("Live cookies file in this directory");
+                    let saved = self.config.cookie_targets.get(&self.cookie_directory).cloned();
+                    let mut target = self.live_file_input.clone().or_else(|| saved.clone()).unwrap_or_default();
+                    let detected = core::detect_live_cookie_file(&self.cookie_directory).unwrap_or(LIVE_COOKIE_FILE);
+                    let response = ui
+                        .add(egui::TextEdit::singleline(&mut target).desired_width(120.0).hint_text(detected))
+                        .on_hover_text("The file this directory's client reads its session from");
+                    if response.changed() {
+                        self.live_file_input = Some(target);
+                    }
+                    if response.lost_focus() {
+                        if let Some(input) = self.live_file_input.take() {
+                            match core::validate_live_file_name(&input) {
+                                Ok(name) if saved.as_deref() != Some(name.as_str()) => {
+                                    self.config.cookie_targets.insert(self.cookie_directory.clone(), name);
+                                    changed = true;
+                                }
+                                Ok(_) => {}
+                                Err(e) => self.push_toast(ToastKind::Error, e.to_string()),
+                            }
+                        }
+                    }
+                    if saved.is_some() && ui.small_button("Detect").on_hover_text("Forget the chosen name and look for a known live file again").clicked() {
+                        self.config.cookie_targets.remove(&self.cookie_directory);
+                        self.live_file_input = None;
+                        changed = true;
+                    }
+                });
+                
+                ui.label("Also write the live cookies to (one file per line)")
+                    .on_hover_text("For another client sharing the accounts, e.g. a native install next to the flatpak");
+                let mut mirror_targets = self.config.mirror_targets.join("\n");
+                let response = ui.add(egui::TextEdit::multiline(&mut mirror_targets).desired_rows(2).desired_width(320.0).hint_text("~/other/client/cookies"));
+                if response.changed() {
+                    self.config.mirror_targets = mirror_targets.split('\n').map(str::to_string).collect();
+                }
+                if response.lost_focus() {
+                    self.config.mirror_targets.retain(|target| !target.trim().is_empty());
+                    changed = true;
+                }
+                
+                changed |= ui.checkbox(&mut self.config.auto_launch, "Launch Sober after switching").changed();
+                ui.add_enabled_ui(self.config.auto_launch, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Command");
+                        changed |= ui
+                            .add(egui::TextEdit::singleline(&mut self.config.launch_command).desired_width(200.0))
+                            .lost_focus();
+                    });
+                    changed |= ui
+                        .checkbox(&mut self.config.check_login_after_launch, "Check the login afterwards")
+                        .on_hover_text("Asks users.roblox.com whether the session is logged in, shortly after launch and at most once a minute")
+                        .changed();
+                });
+                
+                changed |= ui
+                    .checkbox(&mut self.config.confirm_identity_after_switch, "Confirm the Roblox account after switching")
+                    .on_hover_text("Asks users.roblox.com who the live cookie logs in as and warns when it isn't the profile's known username")
+                    .changed();
+                changed |= ui.checkbox(&mut self.config.notify_on_switch, "Show a desktop notification after switching").changed();
+                changed |= ui
+                    .checkbox(&mut self.config.sound_on_switch, "Play a sound after switching")
+                    .on_hover_text("Uses the notification sound, or canberra-gtk-play when notifications are off")
+                    .changed();
+                
+                changed |= ui
+                    .checkbox(&mut self.config.require_sober_closed, "Require Sober to be closed before switching")
+                    .on_hover_text("Sober only reads the cookie file when it starts; a running Sober offers to force the switch instead")
+                    .changed();
+                
+                changed |= ui
+                    .checkbox(&mut self.config.check_session_on_startup, "Check the live session on startup")
+                    .on_hover_text("Warns when the live cookies file has no usable .ROBLOSECURITY token")
+                    .changed();
+                ui.add_enabled_ui(self.config.check_session_on_startup, |ui| {
+                    changed |= ui
+                        .checkbox(&mut self.config.check_session_online, "Also ask Roblox whether it is still logged in")
+                        .on_hover_text("One request to users.roblox.com; no warning is shown when offline")
+                        .changed();
+                });
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Quick switch hotkey").strong());
+                
+                let mut hotkey_changed = ui
+                    .checkbox(&mut self.config.quick_switch_hotkey_enabled, "Show the quick switch overlay with a global hotkey")
+                    .changed();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.config.quick_switch_hotkey)
+                            .desired_width(140.0)
+                            .hint_text("ctrl+alt+s")
+                    );
+                    hotkey_changed |= ui.button("Apply").clicked();
+                });
+                if hotkey_changed {
+                    self.apply_hotkey_config(ctx);
+                    changed = true;
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Tray").strong());
+                
+                if ui
+                    .checkbox(&mut self.config.close_to_tray, "Close to the system tray")
+                    .on_hover_text("✕ hides the window to a tray icon so the hotkey keeps working; use Quit in the tray menu to exit")
+                    .changed()
+                {
+                    self.apply_tray_config(ctx);
+                    changed = true;
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Links").strong());
+                
+                ui.label(
+                    egui::RichText::new(format!("{}://<profile or alias> switches profiles, also in a window that is already open", URL_SCHEME))
+                        .font(FontId::proportional(10.0))
+                        .color(Color32::GRAY)
+                );
+                // Registering writes a desktop entry into the host's data dir
+                if ui
+                    .add_enabled(!core::is_portable(), egui::Button::new(format!("🔗 Open {}:// links with this app", URL_SCHEME)))
+                    .on_disabled_hover_text("Portable mode leaves nothing on this machine, so links can't be registered")
+                    .clicked()
+                {
+                    self.register_url_scheme();
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Network").strong());
+                
+                changed |= ui
+                    .checkbox(&mut self.config.fetch_usernames, "Show Roblox usernames")
+                    .on_hover_text("Verify All asks users.roblox.com for each valid profile's username once and remembers it")
+                    .changed();
+                if ui.small_button("Forget fetched usernames").clicked() {
+                    self.profile_configs_mut().values_mut().for_each(|profile| profile.username = None);
+                    self.save_profile_configs();
+                    let selected = self.selected_profile_name();
+                    self.reload_profiles_keeping_selection(ctx, selected);
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Developer").strong());
+                
+                changed |= ui
+                    .checkbox(&mut self.config.developer_mode, "Enable developer features")
+                    .on_hover_text(format!(
+                        "Shift+click an avatar to switch and copy its {} token. Anyone with the token can use the account.",
+                        ROBLOSECURITY
+                    ))
+                    .changed();
+                if self.config.developer_mode {
+                    let idle = self.idle_frames.last_window.map_or("counting…".to_string(), |count| count.to_string());
+                    ui.label(format!("Frames painted while idle in the last {} s: {}", core::IDLE_FRAME_WINDOW_SECS, idle))
+                        .on_hover_text(format!(
+                            "Should stay at or below {}; more means something is repainting without a reason",
+                            core::idle_frame_budget(self.config.poll_interval())
+                        ));
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Switch history").strong());
+                
+                if self.config.switch_history.is_empty() {
+                    ui.label(egui::RichText::new("No switches yet").color(Color32::GRAY));
+                }
+                
+                let mut reswitch = None;
+                egui::ScrollArea::vertical().id_source("switch_history").max_height(120.0).show(ui, |ui| {
+                    for record in &self.config.switch_history {
+                        ui.horizontal(|ui| {
+                            let index = self.profiles.iter().position(|p| p.name == record.profile);
+                            let name = index
+                                .map(|i| self.profiles[i].display_name.clone())
+                                .unwrap_or_else(|| core::format_profile_name(&record.profile));
+                            ui.label(format!("{} ({})", name, Self::format_timestamp(record.switched_at)));
+                            if ui
+                                .add_enabled(index.is_some(), egui::Button::new("↪ Switch").small())
+                                .on_disabled_hover_text("This profile is not in the current directory")
+                                .clicked()
+                            {
+                                reswitch = index;
+                            }
+                        });
+                    }
+                });
+                
+                if !self.config.switch_history.is_empty() && ui.button("🗑 Clear history").clicked() {
+                    self.config.switch_history.clear();
+                    changed = true;
+                }
+                
+                if let Some(index) = reswitch {
+                    self.request_switch(ctx, index);
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Recently deleted").strong());
+                
+                ui.horizontal(|ui| {
+                    ui.label("Keep deleted profiles for");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.config.trash_retention_days).clamp_range(1..=365).suffix(" days"))
+                        .changed();
+                });
+                
+                let trash = core::list_trash(&self.cookie_directory);
+                if trash.is_empty() {
+                    ui.label(egui::RichText::new("Trash is empty").color(Color32::GRAY));
+                }
+                
+                let mut restore = None;
+                for (i, entry) in trash.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let deleted = Self::format_timestamp(entry.deleted_at);
+                        ui.label(format!("{} ({})", core::format_profile_name(&entry.profile_name), deleted));
+                        if ui.small_button("↩ Restore").clicked() {
+                            restore = Some(i);
+                        }
+                    });
+                }
+                
+                if let Some(entry) = restore.and_then(|i| trash.get(i)).filter(|_| !self.blocked_by_read_only("Restoring profiles")) {
+                    match core::restore_from_trash(&self.cookie_directory, entry) {
+                        Ok(()) => {
+                            let selected = self.selected_profile_name();
+                            self.reload_profiles_keeping_selection(ctx, selected);
+                            self.push_toast(ToastKind::Success, format!("Restored {} profile", core::format_profile_name(&entry.profile_name)));
+                        }
+                        Err(e) => self.push_toast(ToastKind::Error, e.to_string()),
+                    }
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Archived").strong());
+                
+                let archived = core::list_archived(&self.cookie_directory);
+                if archived.is_empty() {
+                    ui.label(egui::RichText::new("No archived profiles").color(Color32::GRAY));
+                }
+                
+                let mut unarchive = None;
+                for (i, file) in archived.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(core::format_profile_name(&file.profile_name));
+                        if ui.small_button("↩ Unarchive").clicked() {
+                            unarchive = Some(i);
+                        }
+                    });
+                }
+                
+                if let Some(file) = unarchive.and_then(|i| archived.get(i)).filter(|_| !self.blocked_by_read_only("Unarchiving profiles")) {
+                    match core::unarchive_profile(&self.cookie_directory, file) {
+                        Ok(()) => {
+                            let selected = self.selected_profile_name();
+                            self.reload_profiles_keeping_selection(ctx, selected);
+                            self.push_toast(ToastKind::Success, format!("Unarchived {} profile", core::format_profile_name(&file.profile_name)));
+                        }
+                        Err(e) => self.push_toast(ToastKind::Error, e.to_string()),
+                    }
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Shared machine").strong());
+                
+                changed |= ui
+                    .checkbox(&mut self.config.shared_machine, "Offer to lock the other profiles after switching to a guest profile")
+                    .changed();
+                if self.config.shared_machine {
+                    ui.label(
+                        egui::RichText::new(
+                            "Locking moves every other cookies_* file, the live-file backups and recently deleted or archived profiles \
+                             into an encrypted .sober-vault file in the cookie directory and overwrites the originals. \
+                             Without the password they can't be recovered. Backups in a separate backup folder, avatars, \
+                             notes and cached usernames are left as they are."
+                        )
+                        .color(Color32::from_rgb(220, 160, 60))
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Guest profile");
+                        let selected = self.find_profile(&self.config.shared_profile).map_or("None".to_string(), |p| p.display_name.clone());
+                        egui::ComboBox::from_id_source("shared_profile").selected_text(selected).show_ui(ui, |ui| {
+                            for profile in &self.profiles {
+                                changed |= ui
+                                    .selectable_value(&mut self.config.shared_profile, profile.name.clone(), &profile.display_name)
+                                    .changed();
+                            }
+                        });
+                    });
+                }
+                if self.profiles_locked {
+                    if ui.button("🔓 Unlock the other profiles…").clicked() {
+                        self.vault_dialog = Some(VaultDialog::Unlock { password: String::new() });
+                    }
+                } else if self.config.shared_machine {
+                    let active = self.selected_profile.and_then(|index| self.profiles.get(index)).map(|p| p.name.clone());
+                    if ui
+                        .add_enabled(active.is_some(), egui::Button::new("🔒 Lock all but the active profile now…"))
+                        .clicked()
+                    {
+                        if let Some(keep) = active {
+                            self.vault_dialog = Some(VaultDialog::Lock { keep, password: String::new(), confirm: String::new() });
+                        }
+                    }
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Read-only mode").strong());
+                
+                let mut read_only = self.read_only();
+                if ui
+                    .add_enabled(!self.cli_read_only, egui::Checkbox::new(&mut read_only, "Browse without writing any files"))
+                    .on_hover_text("Switching, profile edits, imports and settings changes are refused until this is turned off")
+                    .changed()
+                {
+                    self.set_read_only(read_only);
+                }
+                if self.cli_read_only {
+                    ui.label(
+                        egui::RichText::new("Turned on with --read-only for this session")
+                            .font(FontId::proportional(10.0))
+                            .color(Color32::GRAY)
+                    );
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("Reset").strong());
+                
+                match &mut self.confirm_reset {
+                    None => {
+                        if ui.button("🧹 Reset everything…").on_hover_text("Return the manager to a first-run state").clicked() {
+                            self.confirm_reset = Some(String::new());
+                        }
+                    }
+                    Some(typed) => {
+                        ui.label(
+                            egui::RichText::new(
+                                "This forgets the saved and recent directories, every profile's aliases, notes, \
+                                 favorites and reminders, the switch history and all preferences, and clears loaded images. \
+                                 It also deletes profiles.json, the rotated log and the initials avatars the manager saved itself.\n\n\
+                                 Your cookie files (cookies and cookies_*) and the avatar images you added are NOT deleted or changed."
+                            )
+                            .color(Color32::from_rgb(220, 160, 60))
+                        );
+                        ui.horizontal(|ui| {
+                            ui.label("Type RESET to confirm:");
+                            ui.text_edit_singleline(typed);
+                        });
+                        let confirmed = typed.trim() == "RESET";
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(confirmed, egui::Button::new("Reset everything")).clicked() {
+                                reset = true;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.confirm_reset = None;
+                            }
+                        });
+                    }
+                }
+            });
+        
+        self.show_settings = open;
+        
+        if changed {
+            if let Err(e) = self.save_config() {
+                self.push_toast(ToastKind::Error, format!("Failed to save settings: {}", e));
+            }
+        }
+        
+        if reload_avatars {
+            let selected = self.selected_profile_name();
+            self.reload_profiles_keeping_selection(ctx, selected);
+        }
+        
+        if reset {
+            self.reset_everything(ctx);
+        }
+    }
+    
+    // Delete the manager's own metadata and start over as on first run; cookie files are untouched
+    fn reset_everything(&mut self, ctx: &egui::Context) {
+        self.confirm_reset = None;
+        if self.blocked_by_read_only("Resetting") {
+            return;
+        }
+        let removed = match core::reset_manager_files(&core::get_config_dir(), &self.cookie_directory, &self.config.generated_avatars) {
+            Ok(removed) => removed,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Reset stopped: {}", e));
+                return;
+            }
+        };
+        
+        self.config = Config::default();
+        self.config_modified = Config::modified();
+        self.cookie_directory = core::default_cookie_directory();
+        self.temp_directory_input = self.cookie_directory.to_string_lossy().to_string();
+        self.recent_directories.clear();
+        self.directory_counts.clear();
+        self.directory_config = None;
+        self.cookie_health.clear();
+        self.verify_receiver = None;
+        self.legacy_renames = None;
+        self.other_installs = None;
+        self.session_warning = None;
+        self.session_check_receiver = None;
+        self.background_image = None;
+        self.detail_profile = None;
+        self.detail_qr = None;
+        
+        self.apply_hotkey_config(ctx);
+        self.apply_tray_config(ctx);
+        self.apply_window_mode(ctx);
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(self.window_level()));
+        self.load_profiles(ctx);
+        self.restore_active_profile();
+        
+        self.show_settings = false;
+        self.show_onboarding = true;
+        self.push_toast(ToastKind::Success, format!("Reset complete; removed {} metadata files, cookie files untouched", removed.len()));
+    }
+    
+    // (Re)register the quick switch chord from config; failures are reported but never fatal
+    fn apply_hotkey_config(&mut self, ctx: &egui::Context) {
+        if let (Some(manager), Some(hotkey)) = (&self.hotkey_manager, self.registered_hotkey.take()) {
+            manager.unregister(hotkey).ok();
+        }
+        
+        if !self.config.quick_switch_hotkey_enabled {
+            return;
+        }
+        
+        let hotkey: HotKey = match self.config.quick_switch_hotkey.parse() {
+            Ok(hotkey) => hotkey,
+            Err(e) => {
+                self.push_toast(ToastKind::Error, format!("Invalid hotkey '{}': {}", self.config.quick_switch_hotkey, e));
+                return;
+            }
+        };
+        
+        if self.hotkey_manager.is_none() {
+            match GlobalHotKeyManager::new() {
+                Ok(manager) => self.hotkey_manager = Some(manager),
+                Err(e) => {
+                    self.push_toast(ToastKind::Error, format!("Global hotkeys are unavailable: {}", e));
+                    return;
+                }
+            }
+        }
+        
+        if let Some(manager) = &self.hotkey_manager {
+            if let Err(e) = manager.register(hotkey) {
+                self.push_toast(ToastKind::Error, format!("Could not register {}: {}", self.config.quick_switch_hotkey, e));
+                return;
+            }
+        }
+        
+        // The handler runs on the hotkey thread, so it only raises a flag and wakes the UI
+        let pressed = self.hotkey_pressed.clone();
+        let ctx = ctx.clone();
+        let id = hotkey.id();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            if event.id() == id && event.state() == HotKeyState::Pressed {
+                pressed.store(true, Ordering::SeqCst);
+                ctx.request_repaint();
+            }
+        }));
+        
+        self.registered_hotkey = Some(hotkey);
+    }
+    
+    fn open_quick_switch(&mut self, ctx: &egui::Context) {
+        if self.quick_switch_open {
+            return;
+        }
+        
+        self.quick_switch_open = true;
+        self.quick_switch_was_minimized = ctx.input(|i| i.viewport().minimized.unwrap_or(false));
+        
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(egui::WindowLevel::AlwaysOnTop));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+    }
+    
+    fn close_quick_switch(&mut self, ctx: &egui::Context) {
+        self.quick_switch_open = false;
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(self.window_level()));
+        
+        // Go back to the background unless a confirmation still needs answering
+        if self.profile_dialog.is_none() {
+            if self.hidden_to_tray {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+            } else if self.quick_switch_was_minimized {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+            }
+        }
+    }
+    
+    // Start or stop the tray icon to match the config; without a tray ✕ keeps quitting
+    fn apply_tray_config(&mut self, ctx: &egui::Context) {
+        if !self.config.close_to_tray {
+            if let Some(tray) = self.tray.take() {
+                tray.shutdown();
+            }
+            self.tray_receiver = None;
+            return;
+        }
+        if self.tray.is_some() {
+            return;
+        }
+        
+        let (sender, receiver) = mpsc::channel();
+        let tray = SoberTray { sender, ctx: ctx.clone() };
+        // Flatpak sandboxes don't allow owning the well-known name
+        match ksni::blocking::TrayMethods::disable_dbus_name(tray, Path::new("/.flatpak-info").exists()).spawn() {
+            Ok(handle) => {
+                self.tray = Some(handle);
+                self.tray_receiver = Some(receiver);
+            }
+            Err(e) => self.push_toast(ToastKind::Error, format!("System tray is unavailable, ✕ will quit: {}", e)),
+        }
+    }
+    
+    fn poll_tray(&mut self, ctx: &egui::Context) {
+        let Some(receiver) = &self.tray_receiver else {
+            return;
+        };
+        
+        while let Ok(event) = receiver.try_recv() {
+            match event {
+                TrayEvent::Show => {
+                    self.hidden_to_tray = false;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                }
+                TrayEvent::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            }
+        }
+    }
+    
+    // Minimal overlay listing the first nine profiles for one-key selection
+    fn draw_quick_switch(&mut self, ctx: &egui::Context) {
+        if self.hotkey_pressed.swap(false, Ordering::SeqCst) {
+            self.open_quick_switch(ctx);
+        }
+        
+        if !self.quick_switch_open {
+            return;
+        }
+        
+        const DIGITS: [egui::Key; 9] = [
+            egui::Key::Num1, egui::Key::Num2, egui::Key::Num3,
+            egui::Key::Num4, egui::Key::Num5, egui::Key::Num6,
+            egui::Key::Num7, egui::Key::Num8, egui::Key::Num9,
+        ];
+        
+        let (chosen, escape) = ctx.input(|i| {
+            (
+                DIGITS.iter().position(|key| i.key_pressed(*key)),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        
+        let screen = ctx.screen_rect();
+        let mut clicked = None;
+        
+        egui::Area::new(egui::Id::new("quick_switch"))
+            .fixed_pos(screen.min)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.painter().rect_filled(screen, Rounding::ZERO, Color32::from_black_alpha(200));
+                ui.set_min_size(screen.size());
+                
+                ui.vertical_centered(|ui| {
+                    ui.add_space(40.0);
+                    ui.label(
+                        egui::RichText::new("Quick switch")
+                            .font(FontId::proportional(20.0))
+                            .color(Color32::WHITE)
+                    );
+                    ui.add_space(15.0);
+                    
+                    for (i, profile) in self.profiles.iter().take(DIGITS.len()).enumerate() {
+                        let label = format!("{}  {} {}", i + 1, profile.emoji, profile.display_name);
+                        if ui.add(egui::Button::new(egui::RichText::new(label).font(FontId::proportional(14.0))).min_size(Vec2::new(220.0, 26.0))).clicked() {
+                            clicked = Some(i);
+                        }
+                        ui.add_space(4.0);
+                    }
+                    
+                    ui.add_space(10.0);
+                    ui.label(
+                        egui::RichText::new("Press 1–9 to switch, Esc to cancel")
+                            .font(FontId::proportional(11.0))
+                            .color(Color32::GRAY)
+                    );
+                });
+            });
+        
+        match chosen.or(clicked) {
+            Some(index) if index < self.profiles.len() => {
+                self.request_switch(ctx, index);
+                self.close_quick_switch(ctx);
+            }
+            _ if escape => self.close_quick_switch(ctx),
+            _ => {}
+        }
+    }
+    
+    // Profiles matching the query (names, display names and aliases), then matching actions
+    fn palette_entries(&self, query: &str) -> Vec<(String, PaletteCommand)> {
+        let profiles = self
+            .profiles
+            .iter()
+            .enumerate()
+            .filter(|(_, profile)| Self::profile_matches(profile, query))
+            .map(|(i, profile)| (format!("{} {}", profile.emoji, profile.display_name), PaletteCommand::Switch(i)));
+        let actions = PALETTE_ACTIONS
+            .iter()
+            .filter(|(label, _)| Self::fuzzy_match(label, query.trim()))
+            .map(|(label, command)| (label.to_string(), *command));
+        profiles.chain(actions).collect()
+    }
+    
+    fn draw_command_palette(&mut self, ctx: &egui::Context) {
+        if self.command_palette.is_none() {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::K)) {
+                self.command_palette = Some(CommandPalette::default());
+            }
+            return;
+        }
+        
+        // Arrows are taken before the text field sees them, so it doesn't move its cursor too
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        
+        let entries = self.palette_entries(self.command_palette.as_ref().map_or("", |p| &p.query));
+        let Some(palette) = &mut self.command_palette else {
+            return;
+        };
+        if down {
+            palette.highlighted += 1;
+        }
+        if up {
+            palette.highlighted = palette.highlighted.saturating_sub(1);
+        }
+        palette.highlighted = palette.highlighted.min(entries.len().saturating_sub(1));
+        
+        let screen = ctx.screen_rect();
+        let mut clicked = None;
+        
+        egui::Area::new(egui::Id::new("command_palette"))
+            .fixed_pos(screen.min)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.painter().rect_filled(screen, Rounding::ZERO, Color32::from_black_alpha(200));
+                ui.set_min_size(screen.size());
+                
+                ui.vertical_centered(|ui| {
+                    ui.add_space(40.0);
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut palette.query)
+                            .desired_width(260.0)
+                            .hint_text("Profile or action…")
+                    );
+                    response.request_focus();
+                    if response.changed() {
+                        palette.highlighted = 0;
+                    }
+                    ui.add_space(10.0);
+                    
+                    egui::ScrollArea::vertical().max_height(screen.height() - 140.0).show(ui, |ui| {
+                        for (i, (label, _)) in entries.iter().enumerate() {
+                            let row = ui.add_sized(
+                                Vec2::new(260.0, 24.0),
+                                egui::SelectableLabel::new(i == palette.highlighted, label.as_str())
+                            );
+                            if i == palette.highlighted && (up || down) {
+                                row.scroll_to_me(None);
+                            }
+                            if row.clicked() {
+                                clicked = Some(i);
+                            }
+                        }
+                    });
+                    
+                    if entries.is_empty() {
+                        ui.label(egui::RichText::new("Nothing matches").color(Color32::GRAY));
+                    }
+                    
+                    ui.add_space(10.0);
+                    ui.label(
+                        egui::RichText::new("↑↓ to choose, Enter to run, Esc to close")
+                            .font(FontId::proportional(11.0))
+                            .color(Color32::GRAY)
+                    );
+                });
+            });
+        
+        let highlighted = palette.highlighted;
+        if let Some((_, command)) = clicked.or(enter.then_some(highlighted)).and_then(|i| entries.get(i)) {
+            self.command_palette = None;
+            self.run_palette_command(ctx, *command);
+        } else if escape {
+            self.command_palette = None;
+        }
+    }
+    
+    fn run_palette_command(&mut self, ctx: &egui::Context, command: PaletteCommand) {
+        match command {
+            PaletteCommand::Switch(index) => {
+                self.request_switch(ctx, index);
+            }
+            PaletteCommand::ChangeDirectory => self.open_directory_dialog(),
+            PaletteCommand::Refresh => self.load_profiles(ctx),
+            PaletteCommand::RescanImages => self.rescan_images(ctx),
+            PaletteCommand::PasteCookie => self.paste_cookie_from_clipboard(),
+            PaletteCommand::VerifyAll => {
+                if self.verify_receiver.is_none() {
+                    self.start_verification(ctx);
+                }
+            }
+            PaletteCommand::Settings => self.show_settings = true,
+            PaletteCommand::MiniMode => self.set_mini_mode(ctx, true),
+            PaletteCommand::SwitchPrevious => self.switch_to_previous(ctx),
+        }
+    }
+    
+    fn format_size(bytes: u64) -> String {
+        if bytes < 1024 {
+            format!("{} B", bytes)
+        } else if bytes < 1024 * 1024 {
+            format!("{:.1} KB", bytes as f64 / 1024.0)
+        } else {
+            format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+        }
+    }
+    
+    fn apply_legacy_renames(&mut self, ctx: &egui::Context) {
+        if self.blocked_by_read_only("Renaming files") {
+            return;
+        }
+        let Some(renames) = self.legacy_renames.take() else {
+            return;
+        };
+        
+        let mut renamed = 0;
+        for rename in renames.iter().filter(|r| !r.conflict) {
+            match fs::rename(self.cookie_directory.join(&rename.from), self.cookie_directory.join(&rename.to)) {
+                Ok(()) => {
+                    log::info!("Renamed legacy {} to {}", rename.from, rename.to);
+                    renamed += 1;
+                }
+                Err(e) => self.push_toast(ToastKind::Error, format!("Failed to rename {}: {}", rename.from, e)),
+            }
+        }
+        
+        let selected = self.selected_profile_name();
+        self.reload_profiles_keeping_selection(ctx, selected);
+        self.push_toast(ToastKind::Success, format!("Renamed {} legacy cookie file(s)", renamed));
+    }
+    
+    fn apply_batch_rename(&mut self, ctx: &egui::Context, renames: &[BatchRename]) {
+        if self.blocked_by_read_only("Renaming files") {
+            return;
+        }
+        let mut selected = self.selected_profile_name();
+        let mut renamed = 0;
+        for rename in renames {
+            let (Some(old_name), Some(new_name)) = (core::profile_name_from_file(&rename.from), core::profile_name_from_file(&rename.to)) else {
+                continue;
+            };
+            match fs::rename(self.cookie_directory.join(&rename.from), self.cookie_directory.join(&rename.to)) {
+                Ok(()) => {
+                    log::info!("Batch renamed {} to {}", rename.from, rename.to);
+                    self.carry_over_profile(&old_name, &new_name);
+                    if selected.as_deref() == Some(old_name.as_str()) {
+                        selected = Some(new_name);
+                    }
+                    renamed += 1;
+                }
+                Err(e) => self.push_toast(ToastKind::Error, format!("Failed to rename {}: {}", rename.from, e)),
+            }
+        }
+        
+        self.reload_profiles_keeping_selection(ctx, selected);
+        self.push_toast(ToastKind::Success, format!("Renamed {} cookie file(s)", renamed));
+    }
+    
+    // Profiles in other known data directories (and recently used ones) that aren't this one
+    fn find_other_installs(current: &Path, config: &Config) -> Vec<ForeignProfile> {
+        let existing = core::scan_cookie_files(current, &config.live_cookie_file(current)).unwrap_or_default();
+        let current = current.canonicalize().unwrap_or_else(|_| current.to_path_buf());
+        
+        let mut seen = vec![current];
+        let mut found = Vec::new();
+        let candidates = OTHER_INSTALL_DIRECTORIES
+            .iter()
+            .map(|path| path.to_string())
+            .chain(core::load_directory_history().into_iter().map(|entry| entry.path));
+        for candidate in candidates {
+            let Ok(directory) = core::expand_path(&candidate).canonicalize() else {
+                continue;
+            };
+            if seen.contains(&directory) {
+                continue;
+            }
+            seen.push(directory.clone());
+            
+            for file in core::scan_cookie_files(&directory, &config.live_cookie_file(&directory)).unwrap_or_default() {
+                let collides = existing.iter().any(|e| e.profile_name.eq_ignore_ascii_case(&file.profile_name));
+                found.push(ForeignProfile {
+                    directory: directory.clone(),
+                    file_name: file.file_name,
+                    profile_name: file.profile_name,
+                    collides,
+                    selected: !collides,
+                });
+            }
+        }
+        
+        found
+    }
+    
+    fn import_other_installs(&mut self, ctx: &egui::Context) {
+        if self.blocked_by_read_only("Importing profiles") {
+            return;
+        }
+        let Some(found) = self.other_installs.take() else {
+            return;
+        };
+        
+        let mut imported = 0;
+        let mut skipped = Vec::new();
+        for profile in found.iter().filter(|p| p.selected) {
+            // Checked again here in case two installs both have a profile of the same name
+            if core::scan_cookie_files(&self.cookie_directory, &self.live_file())
+                .unwrap_or_default()
+                .iter()
+                .any(|file| file.profile_name.eq_ignore_ascii_case(&profile.profile_name))
+            {
+                skipped.push(core::format_profile_name(&profile.profile_name));
+                continue;
+            }
+            
+            let target = self.cookie_directory.join(&profile.file_name);
+            match fs::copy(profile.directory.join(&profile.file_name), &target) {
+                Ok(_) => {
+                    log::info!("Imported {} from {}", profile.file_name.to_string_lossy(), profile.directory.display());
+                    self.record_checksum(&profile.profile_name, &target);
+                    let image = core::profile_image_path(&profile.directory, &profile.profile_name);
+                    let target_image = core::profile_image_path(&self.cookie_directory, &profile.profile_name);
+                    if image.exists() && !target_image.exists() {
+                        fs::copy(&image, &target_image).ok();
+                    }
+                    imported += 1;
+                }
+                Err(e) => self.push_toast(ToastKind::Error, format!("Failed to import {}: {}", profile.file_name.to_string_lossy(), e)),
+            }
+        }
+        
+        let selected = self.selected_profile_name();
+        self.reload_profiles_keeping_selection(ctx, selected);
+        if !skipped.is_empty() {
+            self.push_toast(ToastKind::Warning, format!("Skipped {}: a profile with that name already exists", skipped.join(", ")));
+        }
+        self.push_toast(ToastKind::Success, format!("Imported {} profile(s)", imported));
+    }
+    
+    // Encode the profile's token once per opening of the details window
+    fn profile_qr_code(&self, profile: &Profile) -> Result<QrModules, String> {
+        let token = core::read_cookie_text(&self.cookie_directory.join(&profile.cookie_file))
+            .map_err(|e| format!("Could not read {}: {}", profile.cookie_file.to_string_lossy(), e))?;
+        let token = core::parse_roblosecurity(&token).ok_or_else(|| format!("No {} in this profile", ROBLOSECURITY))?;
+        
+        let code = qrcode::QrCode::with_error_correction_level(token.as_bytes(), qrcode::EcLevel::L).map_err(|e| match e {
+            qrcode::types::QrError::DataTooLong => "The cookie is too long for a single QR code".to_string(),
+            other => format!("Could not encode the cookie: {}", other),
+        })?;
+        
+        let dark = code.to_colors().into_iter().map(|color| color == qrcode::Color::Dark).collect();
+        Ok(QrModules { width: code.width(), dark })
+    }
+    
+    // The first key pressed while recording, taken before anything else can act on it
+    fn record_hotkey(&mut self, ctx: &egui::Context, name: &str) {
+        let pressed = ctx.input_mut(|i| {
+            let index = i.events.iter().position(|event| matches!(event, egui::Event::Key { pressed: true, repeat: false, .. }))?;
+            match i.events.remove(index) {
+                egui::Event::Key { key, modifiers, .. } => Some(Chord::from_input(modifiers, key)),
+                _ => None,
+            }
+        });
+        
+        match pressed {
+            Some(chord) if chord == Chord::from_input(egui::Modifiers::NONE, egui::Key::Escape) => self.recording_hotkey = None,
+            Some(chord) => self.assign_hotkey(ctx, name, Some(chord)),
+            None => {}
+        }
+    }
+    
+    fn assign_hotkey(&mut self, ctx: &egui::Context, name: &str, chord: Option<Chord>) {
+        self.recording_hotkey = None;
+        if self.blocked_by_read_only("Changing hotkeys") {
+            return;
+        }
+        if let Some(chord) = chord {
+            let global = self.config.quick_switch_hotkey_enabled && Chord::parse(&self.config.quick_switch_hotkey) == Some(chord);
+            let problem = if !chord.is_usable() {
+                Some("needs Ctrl or Alt, unless it's a function key".to_string())
+            } else if global {
+                Some("is already the quick switch hotkey".to_string())
+            } else {
+                Self::chord_conflict(chord, &self.profiles, name).map(|owner| format!("is already used by {}", owner))
+            };
+            if let Some(problem) = problem {
+                self.push_toast(ToastKind::Warning, format!("{} {}", chord, problem));
+                return;
+            }
+        }
+        
+        self.profile_configs_mut().entry(name.to_string()).or_default().hotkey = chord.map(|chord| chord.to_string());
+        self.save_profile_configs();
+        let selected = self.selected_profile_name();
+        self.reload_profiles_keeping_selection(ctx, selected);
+        if let Some(chord) = chord {
+            self.push_toast(ToastKind::Success, format!("{} now switches to {}", chord, core::format_profile_name(name)));
+        }
+    }
+    
+    // Switch when a key press matches a profile's own hotkey, unless something modal has the keyboard
+    fn handle_profile_hotkeys(&mut self, ctx: &egui::Context) {
+        if self.recording_hotkey.is_some() || self.command_palette.is_some() || self.quick_switch_open || self.inline_dialog_open() {
+            return;
+        }
+        
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::P)) {
+            self.switch_to_previous(ctx);
+            return;
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::R)) {
+            self.rescan_images(ctx);
+            return;
+        }
+        if self.profiles.iter().all(|p| p.hotkey.is_none()) {
+            return;
+        }
+        
+        let index = ctx.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Key { key, pressed: true, repeat: false, modifiers, .. } => {
+                    let chord = Chord::from_input(*modifiers, *key);
+                    self.profiles.iter().position(|p| p.hotkey == Some(chord))
+                }
+                _ => None,
+            })
+        });
+        if let Some(index) = index {
+            self.request_switch(ctx, index);
+        }
+    }
+    
+    fn draw_profile_details(&mut self, ctx: &egui::Context) {
+        let Some(name) = self.detail_profile.clone() else {
+            self.detail_qr = None;
+            return;
+        };
+        let Some(profile) = self.find_profile(&name).cloned() else {
+            self.detail_profile = None;
+            return;
+        };
+        
+        if self.detail_qr.as_ref().is_none_or(|(qr_name, _)| *qr_name != name) {
+            self.detail_qr = Some((name.clone(), self.profile_qr_code(&profile)));
+        }
+        
+        let mut open = true;
+        let mut record_hotkey = false;
+        let mut clear_hotkey = false;
+        let mut crop_edit = None;
+        egui::Window::new(format!("{} details", profile.display_name))
+            .id(egui::Id::new("profile_details"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("profile_details_grid").num_columns(2).spacing([12.0, 4.0]).show(ui, |ui| {
+                    ui.label("File");
+                    ui.label(profile.cookie_file.to_string_lossy());
+                    ui.end_row();
+                    
+                    if let Some(username) = &profile.username {
+                        ui.label("Roblox user");
+                        ui.label(format!("{} ✔", username));
+                        ui.end_row();
+                    }
+                    
+                    ui.label("Size");
+                    // Cookie files are normally a few KB; anything much bigger was probably appended to by mistake
+                    if profile.file_size > LARGE_COOKIE_FILE_BYTES {
+                        ui.colored_label(Color32::YELLOW, format!("{} (unusually large)", Self::format_size(profile.file_size)));
+                    } else {
+                        ui.label(Self::format_size(profile.file_size));
+                    }
+                    ui.end_row();
+                    
+                    if !profile.aliases.is_empty() {
+                        ui.label("Aliases");
+                        ui.label(profile.aliases.join(", "));
+                        ui.end_row();
+                    }
+                    
+                    if let Some(remind_at) = profile.remind_at {
+                        ui.label("Reminder");
+                        if Self::reminder_due(&profile) {
+                            ui.colored_label(Color32::YELLOW, format!("Due since {}", Self::format_timestamp(remind_at)));
+                        } else {
+                            ui.label(Self::format_timestamp(remind_at));
+                        }
+                        ui.end_row();
+                    }
+                    
+                    if profile.modified_externally {
+                        ui.label("Integrity");
+                        ui.colored_label(Color32::from_rgb(255, 160, 60), "Modified externally since it was last accepted");
+                        ui.end_row();
+                    }
+                    
+                    if let Some(health) = self.cookie_health.get(&profile.cookie_file) {
+                        ui.label("Cookie");
+                        ui.label(health.description());
+                        ui.end_row();
+                    }
+                    
+                    ui.label("Hotkey");
+                    ui.horizontal(|ui| {
+                        if self.recording_hotkey.as_ref() == Some(&name) {
+                            ui.colored_label(Color32::YELLOW, "Press a key… (Esc cancels)");
+                            return;
+                        }
+                        ui.label(profile.hotkey.map_or("None".to_string(), |chord| chord.to_string()));
+                        if ui.small_button("⌨ Record").on_hover_text("Ctrl or Alt plus a key, or a function key").clicked() {
+                            record_hotkey = true;
+                        }
+                        if profile.hotkey.is_some() && ui.small_button("Clear").clicked() {
+                            clear_hotkey = true;
+                        }
+                    });
+                    ui.end_row();
+                    
+                    if !profile.notes.is_empty() {
+                        ui.label("Notes");
+                        ui.label(&profile.notes);
+                        ui.end_row();
+                    }
+                    
+                    if let Some(login) = &profile.last_login {
+                        ui.label("Last login");
+                        ui.label(Self::describe_login(login));
+                        ui.end_row();
+                    }
+                });
+                
+                if let Some(texture) = &profile.image {
+                    ui.separator();
+                    ui.label(egui::RichText::new("Avatar").strong());
+                    
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::Image::new((texture.id(), Vec2::splat(AVATAR_EDITOR_SIZE)))
+                                .uv(Self::avatar_uv(&profile.crop, texture))
+                                .rounding(8.0)
+                        );
+                        ui.add_enabled_ui(!self.read_only(), |ui| {
+                            ui.vertical(|ui| {
+                                let mut crop = profile.crop;
+                                let sliders = [
+                                    ui.add(egui::Slider::new(&mut crop.zoom, 1.0..=MAX_AVATAR_ZOOM).text("Zoom")),
+                                    ui.add(egui::Slider::new(&mut crop.offset[0], -1.0..=1.0).text("Horizontal")),
+                                    ui.add(egui::Slider::new(&mut crop.offset[1], -1.0..=1.0).text("Vertical")),
+                                ];
+                                let mut changed = sliders.iter().any(|slider| slider.changed());
+                                let mut settled = sliders.iter().any(|slider| slider.drag_stopped() || (slider.changed() && !slider.dragged()));
+                                if ui.add_enabled(crop != AvatarCrop::default(), egui::Button::new("Centre")).clicked() {
+                                    crop = AvatarCrop::default();
+                                    changed = true;
+                                    settled = true;
+                                }
+                                if changed || settled {
+                                    crop_edit = Some((crop, settled));
+                                }
+                            });
+                        });
+                    });
+                }
+                
+                ui.separator();
+                ui.label(egui::RichText::new("QR code").strong());
+                
+                match self.detail_qr.as_ref().map(|(_, qr)| qr) {
+                    Some(Ok(qr)) => {
+                        // Light border of 4 modules, as scanners expect
+                        let module = (240.0 / qr.width as f32).max(1.5);
+                        let side = (qr.width + 8) as f32 * module;
+                        let (rect, response) = ui.allocate_exact_size(Vec2::splat(side), egui::Sense::click_and_drag());
+                        
+                        // It encodes the session token, so only show it while held
+                        if response.is_pointer_button_down_on() {
+                            ui.painter().rect_filled(rect, Rounding::ZERO, Color32::WHITE);
+                            for (i, _) in qr.dark.iter().enumerate().filter(|(_, dark)| **dark) {
+                                let (x, y) = ((i % qr.width + 4) as f32, (i / qr.width + 4) as f32);
+                                let min = rect.min + Vec2::new(x, y) * module;
+                                ui.painter().rect_filled(Rect::from_min_size(min, Vec2::splat(module)), Rounding::ZERO, Color32::BLACK);
+                            }
+                        } else {
+                            ui.painter().rect_filled(rect, Rounding::same(4.0), Color32::from_rgb(45, 62, 80));
+                            ui.painter().text(
+                                rect.center(),
+                                Align2::CENTER_CENTER,
+                                "Hold to reveal",
+                                FontId::proportional(12.0),
+                                Color32::LIGHT_GRAY,
+                            );
+                        }
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::YELLOW, e);
+                    }
+                    None => {}
+                }
+            });
+        
+        if let Some((crop, save)) = crop_edit {
+            self.set_avatar_crop(&name, crop, save);
+        }
+        
+        if record_hotkey {
+            self.recording_hotkey = Some(name.clone());
+        } else if clear_hotkey {
+            self.assign_hotkey(ctx, &name, None);
+        } else if self.recording_hotkey.as_ref() == Some(&name) {
+            self.record_hotkey(ctx, &name);
+        }
+        
+        if !open {
+            self.detail_profile = None;
+            self.recording_hotkey = None;
+        }
+    }
+    
+    fn draw_vault_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.vault_dialog.take() else {
+            return;
+        };
+        let title = match &dialog {
+            VaultDialog::Lock { .. } => "Lock the other profiles",
+            VaultDialog::Unlock { .. } => "Unlock profiles",
+        };
+        
+        let mut open = true;
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                match &mut dialog {
+                    VaultDialog::Lock { keep, password, confirm } => {
+                        ui.label(format!(
+                            "Every profile except {} is encrypted into .sober-vault and its file overwritten. \
+                             Only this password brings them back.",
+                            core::format_profile_name(keep)
+                        ));
+                        ui.horizontal(|ui| {
+                            ui.label("Password");
+                            ui.add(egui::TextEdit::singleline(password).password(true));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Again");
+                            ui.add(egui::TextEdit::singleline(confirm).password(true));
+                        });
+                        let matching = !password.is_empty() && password == confirm;
+                        if !confirm.is_empty() && password != confirm {
+                            ui.colored_label(Color32::YELLOW, "The passwords don't match");
+                        }
+                        ui.horizontal(|ui| {
+                            confirmed = ui.add_enabled(matching, egui::Button::new("🔒 Lock")).clicked();
+                            cancelled = ui.button("Not now").clicked();
+                        });
+                    }
+                    VaultDialog::Unlock { password } => {
+                        ui.label("Enter the password the other profiles were locked with.");
+                        let response = ui.add(egui::TextEdit::singleline(password).password(true));
+                        let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        ui.horizontal(|ui| {
+                            confirmed = ui.add_enabled(!password.is_empty(), egui::Button::new("🔓 Unlock")).clicked()
+                                || (entered && !password.is_empty());
+                            cancelled = ui.button("Cancel").clicked();
+                        });
+                    }
+                }
+            });
+        
+        if !open || cancelled {
+            return;
+        }
+        if !confirmed {
+            self.vault_dialog = Some(dialog);
+            return;
+        }
+        if self.blocked_by_read_only("Locking profiles") {
+            return;
+        }
+        
+        let result = match &dialog {
+            VaultDialog::Lock { keep, password, .. } => match self.find_profile(keep).map(|p| p.cookie_file.clone()) {
+                Some(cookie_file) => core::lock_profiles(&self.cookie_directory, &cookie_file, password)
+                    .map(|count| format!("Locked {} files away; unlock them in Settings → Shared machine", count)),
+                None => Err(AppError::ProfileNotFound(keep.clone())),
+            },
+            VaultDialog::Unlock { password } => {
+                core::unlock_profiles(&self.cookie_directory, password).map(|count| format!("Restored {} files", count))
+            }
+        };
+        match result {
+            Ok(message) => {
+                let selected = self.selected_profile_name();
+                self.reload_profiles_keeping_selection(ctx, selected);
+                self.push_toast(ToastKind::Success, message);
+            }
+            // Let them try again
+            Err(AppError::WrongPassword) => {
+                self.push_toast(ToastKind::Error, "Wrong password");
+                self.vault_dialog = Some(VaultDialog::Unlock { password: String::new() });
+            }
+            Err(e) => self.push_toast(ToastKind::Error, e.to_string()),
+        }
+    }
+    
+    fn draw_profile_comparison(&mut self, ctx: &egui::Context) {
+        let Some(mut comparison) = self.comparison.take() else {
+            return;
+        };
+        let Some(first) = self.find_profile(&comparison.first).cloned() else {
+            return;
+        };
+        
+        let mut open = true;
+        egui::Window::new("Compare profiles")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} with", first.display_name));
+                    let selected = comparison
+                        .second
+                        .as_deref()
+                        .and_then(|name| self.find_profile(name))
+                        .map_or("Choose a profile…".to_string(), |p| p.display_name.clone());
+                    egui::ComboBox::from_id_source("compare_with").selected_text(selected).show_ui(ui, |ui| {
+                        for profile in self.profiles.iter().filter(|p| p.name != first.name) {
+                            if ui.selectable_label(comparison.second.as_ref() == Some(&profile.name), &profile.display_name).clicked() {
+                                comparison.second = Some(profile.name.clone());
+                                comparison.result = None;
+                            }
+                        }
+                    });
+                });
+                
+                let Some(second) = comparison.second.as_deref().and_then(|name| self.find_profile(name)) else {
+                    return;
+                };
+                let first_path = self.cookie_directory.join(&first.cookie_file);
+                let second_path = self.cookie_directory.join(&second.cookie_file);
+                let result = comparison.result.get_or_insert_with(|| {
+                    core::compare_cookie_files(&first_path, &second_path).map_err(|e| e.to_string())
+                });
+                
+                ui.add_space(6.0);
+                match result {
+                    // Two profiles holding the same session are usually a mistake
+                    Ok(verdict @ (CookieComparison::Identical | CookieComparison::SameToken)) => {
+                        ui.colored_label(Color32::YELLOW, verdict.describe());
+                    }
+                    Ok(verdict) => {
+                        ui.label(verdict.describe());
+                    }
+                    Err(e) => {
+                        ui.colored_label(Color32::LIGHT_RED, format!("Could not compare: {}", e));
                     }
                 }
+                ui.add_space(6.0);
                 
-                // Sort alphabetically
-                cookie_files.sort_by(|a, b| a.0.cmp(&b.0));
-                
-                // Create profiles
-                for (i, (profile_name, cookie_file)) in cookie_files.into_iter().enumerate() {
-                    let display_name = Self::format_profile_name(&profile_name);
-                    let emoji = Self::get_profile_emoji(i);
+                let modified = |path: &Path| {
+                    fs::metadata(path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map_or("unknown".to_string(), |d| Self::format_timestamp(d.as_secs()))
+                };
+                egui::Grid::new("profile_comparison_grid").num_columns(3).spacing([12.0, 4.0]).show(ui, |ui| {
+                    ui.label("");
+                    ui.label(egui::RichText::new(&first.display_name).strong());
+                    ui.label(egui::RichText::new(&second.display_name).strong());
+                    ui.end_row();
                     
-                    // Try to load profile-specific image from cookie directory
-                    let image_path = self.cookie_directory.join(format!("{}.png", profile_name.to_lowercase()));
-                    let image = Self::load_image_from_path(ctx, image_path.to_str().unwrap_or(""));
+                    ui.label("File");
+                    ui.label(first.cookie_file.to_string_lossy());
+                    ui.label(second.cookie_file.to_string_lossy());
+                    ui.end_row();
                     
-                    let profile = Profile {
-                        name: profile_name.clone(),
-                        cookie_file,
-                        display_name,
-                        emoji,
-                        image,
-                    };
+                    ui.label("Size");
+                    ui.label(Self::format_size(first.file_size));
+                    ui.label(Self::format_size(second.file_size));
+                    ui.end_row();
                     
-                    self.profiles.push(profile);
-                }
-                
-                if self.profiles.is_empty() {
-                    self.error_message = Some(format!(
-                        "No cookie files found in {}. Looking for files named 'cookies_*.txt' or 'cookies_*'.",
-                        self.cookie_directory.display()
-                    ));
-                }
-            }
-            Err(e) => {
-                self.error_message = Some(format!("Error scanning directory {}: {}", self.cookie_directory.display(), e));
-            }
+                    ui.label("Modified");
+                    ui.label(modified(&first_path));
+                    ui.label(modified(&second_path));
+                    ui.end_row();
+                });
+            });
+        
+        if open {
+            self.comparison = Some(comparison);
         }
     }
     
-    fn format_profile_name(name: &str) -> String {
-        // Convert snake_case or kebab-case to Title Case
-        name.replace('_', " ")
-            .replace('-', " ")
-            .split_whitespace()
-            .map(|word| {
-                let mut chars = word.chars();
-                match chars.next() {
-                    None => String::new(),
-                    Some(first) => first.to_uppercase().chain(chars.as_str().to_lowercase().chars()).collect(),
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(" ")
-    }
-    
-    fn get_profile_emoji(index: usize) -> String {
-        let emojis = ["🦆", "🐱", "🐶", "🐸", "🐨", "🦊", "🐰", "🐼", "🦁", "🐯"];
-        emojis.get(index % emojis.len()).unwrap_or(&"👤").to_string()
-    }
-    
-    fn load_image_from_path(ctx: &egui::Context, path: &str) -> Option<TextureHandle> {
-        match image::open(path) {
-            Ok(img) => {
-                let rgba = img.to_rgba8();
-                let size = [rgba.width() as usize, rgba.height() as usize];
-                let pixels = rgba.as_flat_samples();
-                
-                let color_image = ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
-                Some(ctx.load_texture(path, color_image, TextureOptions::default()))
-            }
-            Err(_) => {
-                println!("Could not load image: {}", path);
-                None
-            }
-        }
+    // Painted by hand, so the accessible name has to be attached explicitly
+    fn draw_title_bar_button(ui: &mut egui::Ui, icon: &str, label: &str, font_size: f32, hover_color: Color32) -> egui::Response {
+        let button_size = Vec2::new(32.0, 28.0);
+        let (rect, response) = ui.allocate_exact_size(button_size, egui::Sense::click());
+        response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, label));
+        
+        let bg_color = if response.hovered() {
+            hover_color
+        } else {
+            Color32::TRANSPARENT
+        };
+        
+        ui.painter().rect_filled(rect, Rounding::same(0.0), bg_color);
+        ui.painter().text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            icon,
+            FontId::proportional(font_size),
+            Color32::WHITE,
+        );
+        
+        response
     }
     
-    fn copy_cookie_file(&mut self, profile_index: usize) {
-        if let Some(profile) = self.profiles.get(profile_index) {
-            let source_path = self.cookie_directory.join(&profile.cookie_file);
-            let target_path = self.cookie_directory.join("cookies");
-            
-            match fs::copy(&source_path, &target_path) {
-                Ok(_) => {
-                    println!("Successfully copied {} to {}", source_path.display(), target_path.display());
-                    self.error_message = Some(format!("✅ Switched to {} profile", profile.display_name));
-                }
-                Err(e) => {
-                    let error_msg = format!("Failed to copy {}: {}", source_path.display(), e);
-                    println!("{}", error_msg);
-                    self.error_message = Some(error_msg);
-                }
+    // Hand the drag to the compositor, or follow the pointer ourselves when it doesn't honor StartDrag
+    fn drag_window(&self, ctx: &egui::Context, response: &egui::Response) {
+        if !self.config.manual_window_drag {
+            if response.is_pointer_button_down_on() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
             }
+            return;
         }
-    }
-    
-    fn apply_directory_change(&mut self, ctx: &egui::Context) {
-        let new_path = Self::expand_path(&self.temp_directory_input);
         
-        if new_path.exists() && new_path.is_dir() {
-            self.cookie_directory = new_path;
-            self.save_directory();
-            self.load_profiles(ctx);
-            self.selected_profile = None;
-            self.show_directory_dialog = false;
-            self.error_message = Some("✅ Directory changed successfully".to_string());
-        } else {
-            self.error_message = Some("❌ Directory does not exist or is not a directory".to_string());
+        let delta = response.drag_delta();
+        if !response.dragged() || delta == Vec2::ZERO {
+            return;
+        }
+        if let Some(outer) = ctx.input(|i| i.viewport().outer_rect) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(outer.min + delta));
         }
     }
     
     fn draw_custom_title_bar(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::TopBottomPanel::top("title_bar")
             .exact_height(40.0)
-            .frame(egui::Frame::none().fill(Color32::from_rgb(32, 47, 64)).inner_margin(0.0))
+            .frame(egui::Frame::none().fill(self.config.theme.panel_color()).inner_margin(0.0))
             .show(ctx, |ui| {
                 let title_bar_rect = ui.max_rect();
-                let title_bar_response = ui.interact(title_bar_rect, egui::Id::new("title_bar"), egui::Sense::click());
-                
-                if title_bar_response.is_pointer_button_down_on() {
-                    ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
-                }
+                let title_bar_response = ui.interact(title_bar_rect, egui::Id::new("title_bar"), egui::Sense::click_and_drag());
+                self.drag_window(ctx, &title_bar_response);
                 
                 ui.vertical_centered(|ui| {
                     ui.add_space(8.0);
@@ -274,7 +5347,7 @@ impl SoberApp {
                         } else {
                             let (rect, _) = ui.allocate_exact_size(Vec2::new(24.0, 24.0), egui::Sense::hover());
                             ui.painter().circle_filled(rect.center(), 12.0, Color32::WHITE);
-                            ui.painter().circle_filled(rect.center(), 8.0, Color32::from_rgb(32, 47, 64));
+                            ui.painter().circle_filled(rect.center(), 8.0, self.config.theme.panel_color());
                         }
                         
                         ui.add_space(10.0);
@@ -288,50 +5361,51 @@ impl SoberApp {
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             ui.add_space(8.0);
                             
-                            // Refresh button
-                            let refresh_button_size = Vec2::new(32.0, 28.0);
-                            let (refresh_rect, refresh_response) = ui.allocate_exact_size(refresh_button_size, egui::Sense::click());
-                            
-                            let refresh_bg_color = if refresh_response.hovered() {
-                                Color32::from_rgb(70, 120, 180)
-                            } else {
-                                Color32::TRANSPARENT
-                            };
-                            
-                            ui.painter().rect_filled(refresh_rect, Rounding::same(0.0), refresh_bg_color);
-                            ui.painter().text(
-                                refresh_rect.center(),
-                                Align2::CENTER_CENTER,
-                                "↻",
-                                FontId::proportional(16.0),
-                                Color32::WHITE,
-                            );
-                            
-                            if refresh_response.clicked() {
-                                self.load_profiles(ctx);
+                            // Refresh button; Shift+click only reloads the images
+                            if Self::draw_title_bar_button(ui, "↻", "Refresh", 16.0, self.config.theme.accent_color())
+                                .on_hover_text("Shift+click or Ctrl+Shift+R reloads only the images")
+                                .clicked()
+                            {
+                                if ui.input(|i| i.modifiers.shift) {
+                                    self.rescan_images(ctx);
+                                } else {
+                                    self.load_profiles(ctx);
+                                }
                             }
                             
                             // Close button
-                            let close_button_size = Vec2::new(32.0, 28.0);
-                            let (close_rect, close_response) = ui.allocate_exact_size(close_button_size, egui::Sense::click());
+                            if Self::draw_title_bar_button(ui, "✕", "Close", 14.0, Color32::from_rgb(196, 43, 28)).clicked() {
+                                if self.tray.is_some() {
+                                    self.hidden_to_tray = true;
+                                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+                                } else {
+                                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                                }
+                            }
                             
-                            let close_bg_color = if close_response.hovered() {
-                                Color32::from_rgb(196, 43, 28)
-                            } else {
-                                Color32::TRANSPARENT
-                            };
+                            // Settings button
+                            if Self::draw_title_bar_button(ui, "⚙", "Settings", 15.0, self.config.theme.accent_color()).clicked() {
+                                self.show_settings = !self.show_settings;
+                            }
                             
-                            ui.painter().rect_filled(close_rect, Rounding::same(0.0), close_bg_color);
-                            ui.painter().text(
-                                close_rect.center(),
-                                Align2::CENTER_CENTER,
-                                "✕",
-                                FontId::proportional(14.0),
-                                Color32::WHITE,
-                            );
+                            // Pin button, underlined while the window is pinned
+                            let pin_label = if self.config.pin_on_top { "Unpin window" } else { "Pin window on top" };
+                            let pin = Self::draw_title_bar_button(ui, "📌", pin_label, 14.0, self.config.theme.accent_color());
+                            if self.config.pin_on_top {
+                                let underline = Rect::from_min_max(pin.rect.left_bottom() - Vec2::new(0.0, 3.0), pin.rect.right_bottom());
+                                ui.painter().rect_filled(underline, Rounding::ZERO, self.config.theme.accent_color());
+                            }
+                            if pin.on_hover_text(pin_label).clicked() {
+                                self.config.pin_on_top = !self.config.pin_on_top;
+                                ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(self.window_level()));
+                                if let Err(e) = self.save_config() {
+                                    self.push_toast(ToastKind::Error, format!("Failed to save settings: {}", e));
+                                }
+                            }
                             
-                            if close_response.clicked() {
-                                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                            // Mini mode button
+                            if Self::draw_title_bar_button(ui, "🗕", "Mini mode", 14.0, self.config.theme.accent_color()).clicked() {
+                                self.set_mini_mode(ctx, true);
                             }
                         });
                     });
@@ -339,18 +5413,516 @@ impl SoberApp {
             });
     }
     
+    fn window_level(&self) -> egui::WindowLevel {
+        if self.config.mini_mode || self.config.pin_on_top {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        }
+    }
+    
+    fn set_mini_mode(&mut self, ctx: &egui::Context, mini: bool) {
+        if self.config.mini_mode == mini {
+            return;
+        }
+        
+        self.config.mini_mode = mini;
+        if let Err(e) = self.save_config() {
+            self.push_toast(ToastKind::Error, format!("Failed to save settings: {}", e));
+        }
+        self.apply_window_mode(ctx);
+    }
+    
+    // Resize, move and (un)pin the window for the current mode
+    fn apply_window_mode(&mut self, ctx: &egui::Context) {
+        self.window_mode_changed = Instant::now();
+        
+        let (geometry, default_size) = if self.config.mini_mode {
+            let count = self.profiles.len().max(1) as f32;
+            let width = MINI_PADDING * 2.0 + count * (MINI_AVATAR_SIZE + MINI_PADDING) + 32.0;
+            (self.config.mini_window, Vec2::new(width, MINI_AVATAR_SIZE + MINI_PADDING * 2.0))
+        } else {
+            (self.config.full_window, Vec2::new(520.0, 360.0))
+        };
+        
+        let size = geometry.map_or(default_size, |g| Vec2::from(g.size));
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+        if let Some(geometry) = geometry {
+            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(Pos2::from(geometry.pos)));
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(self.window_level()));
+    }
+    
+    // Track the window so each mode reopens where it was left; saved on exit
+    fn record_window_geometry(&mut self, ctx: &egui::Context) {
+        if self.window_mode_changed.elapsed() < WINDOW_MODE_SETTLE {
+            return;
+        }
+        
+        let (outer, inner) = ctx.input(|i| (i.viewport().outer_rect, i.viewport().inner_rect));
+        let (Some(outer), Some(inner)) = (outer, inner) else {
+            return;
+        };
+        
+        let geometry = Some(WindowGeometry {
+            pos: [outer.min.x, outer.min.y],
+            size: [inner.width(), inner.height()],
+        });
+        if self.config.mini_mode {
+            self.config.mini_window = geometry;
+        } else {
+            self.config.full_window = geometry;
+        }
+    }
+    
+    fn draw_read_only_banner(&self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("read_only_banner")
+            .frame(egui::Frame::none().fill(Color32::from_rgb(120, 90, 30)).inner_margin(4.0))
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(
+                        egui::RichText::new("🔒 Read-only mode: switching, edits and settings changes are disabled")
+                            .color(Color32::WHITE)
+                    );
+                });
+            });
+    }
+    
+    fn draw_session_warning(&mut self, ctx: &egui::Context) {
+        let Some(warning) = &self.session_warning else {
+            return;
+        };
+        let mut dismissed = false;
+        egui::TopBottomPanel::top("session_warning")
+            .frame(egui::Frame::none().fill(Color32::from_rgb(150, 40, 40)).inner_margin(4.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(format!("⚠ {}", warning)).color(Color32::WHITE).strong());
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        dismissed = ui.small_button("✕").on_hover_text("Dismiss").clicked();
+                    });
+                });
+            });
+        if dismissed {
+            self.session_warning = None;
+        }
+    }
+    
+    // Just the avatars and a button back to the full window; the background drags the window
+    fn draw_mini_mode(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(self.config.theme.panel_color()).inner_margin(MINI_PADDING))
+            .show(ctx, |ui| {
+                let drag = ui.interact(ui.max_rect(), egui::Id::new("mini_drag"), egui::Sense::click_and_drag());
+                self.drag_window(ctx, &drag);
+                
+                ui.horizontal_centered(|ui| {
+                    ui.spacing_mut().item_spacing.x = MINI_PADDING;
+                    
+                    for index in 0..self.profiles.len() {
+                        let profile = self.profiles[index].clone();
+                        let is_selected = self.selected_profile == Some(index);
+                        
+                        let response = self
+                            .draw_profile_avatar(ui, &profile, is_selected, MINI_AVATAR_SIZE, false)
+                            .on_hover_ui(|ui| self.draw_hover_card(ui, &profile));
+                        if response.clicked() {
+                            if self.config.developer_mode && ui.input(|i| i.modifiers.shift) {
+                                self.switch_and_copy_token(ctx, index);
+                            } else {
+                                self.request_switch(ctx, index);
+                            }
+                        }
+                    }
+                    
+                    if Self::draw_title_bar_button(ui, "🗖", "Full window", 14.0, self.config.theme.accent_color()).clicked() {
+                        self.set_mini_mode(ctx, false);
+                    }
+                });
+            });
+        
+        // Confirmations (e.g. switching away from a locked profile) need the full window
+        if self.profile_dialog.is_some() {
+            self.set_mini_mode(ctx, false);
+        }
+    }
+    
+    // Everything worth knowing about a profile at a glance; only built while its avatar is hovered
+    fn draw_hover_card(&self, ui: &mut egui::Ui, profile: &Profile) {
+        ui.set_max_width(220.0);
+        
+        if let Some(texture) = &profile.image {
+            ui.add(egui::Image::new((texture.id(), Vec2::splat(HOVER_CARD_IMAGE_SIZE))).uv(Self::avatar_uv(&profile.crop, texture)));
+        } else {
+            ui.label(egui::RichText::new(&profile.emoji).font(FontId::proportional(HOVER_CARD_IMAGE_SIZE * 0.4)));
+        }
+        
+        ui.label(egui::RichText::new(&profile.display_name).strong());
+        ui.label(
+            egui::RichText::new(profile.cookie_file.to_string_lossy())
+                .font(FontId::proportional(10.0))
+                .color(Color32::GRAY)
+        );
+        ui.add_space(4.0);
+        
+        // The history is capped, so the count only covers recent switches
+        let mut switches = self.config.switch_history.iter().filter(|record| record.profile == profile.name);
+        match switches.next() {
+            Some(last) => {
+                let count = 1 + switches.count();
+                ui.label(format!("Last used {}", Self::format_timestamp(last.switched_at)));
+                ui.label(format!("Switched to {} time{} recently", count, if count == 1 { "" } else { "s" }));
+            }
+            None => {
+                ui.label("Not used recently");
+            }
+        }
+        if let Some(added_at) = profile.added_at {
+            ui.label(format!("Added {}", core::days_ago(added_at, core::unix_now())));
+        }
+        if let Some(chord) = profile.hotkey {
+            ui.label(format!("Hotkey: {}", chord));
+        }
+        // The overlay numbers the first nine profiles in grid order
+        if self.config.quick_switch_hotkey_enabled {
+            if let Some(position) = self.profiles.iter().take(9).position(|p| p.name == profile.name) {
+                ui.label(format!("Quick switch: {}, then {}", self.config.quick_switch_hotkey, position + 1));
+            }
+        }
+        
+        match self.cookie_health.get(&profile.cookie_file) {
+            Some(health) => {
+                let color = match health {
+                    CookieHealth::Valid => Color32::LIGHT_GREEN,
+                    CookieHealth::Unreadable(_) => Color32::LIGHT_RED,
+                    _ => Color32::YELLOW,
+                };
+                ui.colored_label(color, format!("Cookie: {}", health.description()));
+            }
+            None => {
+                ui.label(egui::RichText::new("Cookie not verified yet").color(Color32::GRAY));
+            }
+        }
+        
+        if let Some(login) = &profile.last_login {
+            ui.label(Self::describe_login(login));
+        }
+        if !profile.notes.is_empty() {
+            ui.separator();
+            ui.label(&profile.notes);
+        }
+    }
+    
+    fn draw_onboarding(&mut self, ui: &mut egui::Ui) {
+        let live_exists = self.live_exists;
+        
+        ui.vertical_centered(|ui| {
+            ui.group(|ui| {
+                ui.set_max_width(440.0);
+                ui.vertical(|ui| {
+                    ui.label(
+                        egui::RichText::new("Welcome")
+                            .font(FontId::proportional(16.0))
+                            .color(Color32::WHITE)
+                            .strong()
+                    );
+                    ui.add_space(8.0);
+                    
+                    ui.label(
+                        "Sober keeps your Roblox login in a file called 'cookies'. Each profile here is a saved \
+                         copy of that file named cookies_<name>.txt, and clicking a profile copies it over the \
+                         live file so Sober starts logged in as that account."
+                    );
+                    ui.add_space(8.0);
+                    
+                    ui.label("Sober's files are expected in:");
+                    let (icon, color) = if self.cookie_directory.is_dir() {
+                        ("✔", Color32::LIGHT_GREEN)
+                    } else {
+                        ("✖ not found:", Color32::LIGHT_RED)
+                    };
+                    ui.colored_label(color, format!("{} {}", icon, self.cookie_directory.display()));
+                    ui.add_space(10.0);
+                    
+                    ui.horizontal(|ui| {
+                        if ui.button("📁 Browse…").clicked() {
+                            self.open_directory_dialog();
+                        }
+                        
+                        if ui
+                            .add_enabled(live_exists, egui::Button::new("💾 Save current session as a profile"))
+                            .on_disabled_hover_text("Log in once in Sober to create the cookies file")
+                            .clicked()
+                        {
+                            self.capture_live_session();
+                        }
+                    });
+                    ui.add_space(6.0);
+                    
+                    if ui.button("✅ Get started").clicked() {
+                        self.show_onboarding = false;
+                        self.config.onboarding_complete = true;
+                        if let Err(e) = self.save_config() {
+                            self.push_toast(ToastKind::Error, format!("Failed to save settings: {}", e));
+                        }
+                    }
+                });
+            });
+        });
+    }
+    
+    fn draw_profile_menu(ui: &mut egui::Ui, profile: &Profile) -> Option<ProfileAction> {
+        let mut action = None;
+        
+        if ui.button("▶ Switch").clicked() {
+            action = Some(ProfileAction::Switch);
+        }
+        if ui
+            .button("📡 Switch in every directory…")
+            .on_hover_text("Applies this profile in each recently used directory that has a profile of the same name")
+            .clicked()
+        {
+            action = Some(ProfileAction::SwitchEverywhere);
+        }
+        for (index, game) in profile.games.iter().enumerate() {
+            if ui.button(format!("🎮 {}", game.label)).on_hover_text("Switch and join this place in Sober").clicked() {
+                action = Some(ProfileAction::LaunchGame(index));
+            }
+        }
+        if ui.button("ℹ Details…").clicked() {
+            action = Some(ProfileAction::ShowDetails);
+        }
+        
+        ui.separator();
+        
+        if ui.button("✏ Rename…").clicked() {
+            action = Some(ProfileAction::Rename);
+        }
+        if ui.button("🏷 Aliases…").clicked() {
+            action = Some(ProfileAction::EditAliases);
+        }
+        if ui.button("🔤 Nickname…").clicked() {
+            action = Some(ProfileAction::EditNickname);
+        }
+        if ui.button("🎮 Games…").clicked() {
+            action = Some(ProfileAction::EditGames);
+        }
+        if ui.button("⏰ Remind me…").clicked() {
+            action = Some(ProfileAction::SetReminder);
+        }
+        if Self::reminder_due(profile) && ui.button("💤 Snooze reminder a day").clicked() {
+            action = Some(ProfileAction::SnoozeReminder);
+        }
+        if profile.remind_at.is_some() && ui.button("🔕 Clear reminder").clicked() {
+            action = Some(ProfileAction::ClearReminder);
+        }
+        if ui.button("📝 Notes…").clicked() {
+            action = Some(ProfileAction::EditNotes);
+        }
+        let favorite_label = if profile.favorite { "☆ Unfavorite" } else { "⭐ Favorite" };
+        if ui.button(favorite_label).clicked() {
+            action = Some(ProfileAction::ToggleFavorite);
+        }
+        let lock_label = if profile.locked { "🔓 Unlock" } else { "🔒 Lock" };
+        if ui.button(lock_label).clicked() {
+            action = Some(ProfileAction::ToggleLock);
+        }
+        if ui.button("🎨 Color tag…").clicked() {
+            action = Some(ProfileAction::EditColorTag);
+        }
+        if profile.color_tag.is_some() && ui.button("⬜ Clear color tag").clicked() {
+            action = Some(ProfileAction::ClearColorTag);
+        }
+        if profile.modified_externally
+            && ui
+                .button("✅ Accept current file")
+                .on_hover_text("The cookie file changed outside the app; trust it as it is now")
+                .clicked()
+        {
+            action = Some(ProfileAction::AcceptChecksum);
+        }
+        if ui.button("⧉ Duplicate").clicked() {
+            action = Some(ProfileAction::Duplicate);
+        }
+        if ui.button("⚖ Compare with…").clicked() {
+            action = Some(ProfileAction::Compare);
+        }
+        if ui.button("📤 Export…").clicked() {
+            action = Some(ProfileAction::Export);
+        }
+        
+        ui.separator();
+        
+        if ui
+            .button("📦 Archive")
+            .on_hover_text("Hides the profile from the grid; its file moves to the archived folder")
+            .clicked()
+        {
+            action = Some(ProfileAction::Archive);
+        }
+        if ui.button("🗑 Delete…").clicked() {
+            action = Some(ProfileAction::Delete);
+        }
+        
+        action
+    }
+    
+    fn inline_dialog_open(&self) -> bool {
+        self.show_directory_dialog || self.pasted_cookie.is_some() || self.import_dialog.is_some() || self.profile_dialog.is_some()
+    }
+    
+    // A focused button or field handles Enter itself, so Tab to Cancel then Enter still cancels
+    fn take_dialog_keys(ctx: &egui::Context) -> DialogKeys {
+        let unfocused = ctx.memory(|m| m.focused().is_none());
+        ctx.input_mut(|i| DialogKeys {
+            confirm: unfocused && i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            cancel: i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+        })
+    }
+    
+    // Keyboard focus starts in a dialog's first field and comes back to it if lost
+    fn focus_dialog_field(ui: &egui::Ui, response: &egui::Response) {
+        if ui.memory(|m| m.focused().is_none()) {
+            response.request_focus();
+        }
+    }
+    
+    fn draw_profile_dialog(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, keys: DialogKeys) {
+        let Some(dialog) = &mut self.profile_dialog else {
+            return;
+        };
+        
+        let mut color = None;
+        let (title, input, hint) = match dialog {
+            ProfileDialog::Rename { name, input } => (format!("Rename {}:", core::format_profile_name(name)), Some(input), "new_name"),
+            ProfileDialog::Aliases { name, input } => (format!("Aliases for {} (comma separated):", core::format_profile_name(name)), Some(input), "e.g., m, main2"),
+            ProfileDialog::Nickname { name, input } => (
+                format!("Nickname shown on {}'s avatar (up to {} characters):", core::format_profile_name(name), MAX_NICKNAME_LENGTH),
+                Some(input),
+                "empty uses the initials",
+            ),
+            ProfileDialog::Games { name, input } => (
+                format!("Games to launch as {} (Label = place ID or game link, separated by ;):", core::format_profile_name(name)),
+                Some(input),
+                "e.g., Obby = 1818; Adopt Me = https://www.roblox.com/games/920587237",
+            ),
+            ProfileDialog::Notes { name, input } => (format!("Notes for {}:", core::format_profile_name(name)), Some(input), "Shown when hovering the avatar"),
+            ProfileDialog::Reminder { name, input } => (format!("Remind me about {} in (days):", core::format_profile_name(name)), Some(input), "e.g., 7"),
+            ProfileDialog::ColorTag { name, color: tag } => {
+                color = Some(tag);
+                (format!("Color tag for {}:", core::format_profile_name(name)), None, "")
+            }
+            ProfileDialog::Export { name, input } => (format!("Export {} cookie file to:", core::format_profile_name(name)), Some(input), "e.g., ~/cookies_backup.txt"),
+            ProfileDialog::ConfirmDelete { name } => (
+                format!("Move {} to the trash? It can be restored from Settings → Recently deleted.", core::format_profile_name(name)),
+                None,
+                "",
+            ),
+            ProfileDialog::ConfirmSwitch { name, locked_name: Some(locked_name) } => (
+                format!(
+                    "🔒 You have locked profile {} active — switch to {} anyway?",
+                    core::format_profile_name(locked_name),
+                    core::format_profile_name(name)
+                ),
+                None,
+                "",
+            ),
+            ProfileDialog::ConfirmBroadcast { name, directories } => (
+                format!(
+                    "Replace the live cookies in all {} of these directories with {}?\n{}",
+                    directories.len(),
+                    core::format_profile_name(name),
+                    directories.iter().map(|d| format!("• {}", d.display())).collect::<Vec<_>>().join("\n")
+                ),
+                None,
+                "",
+            ),
+            ProfileDialog::ForceSwitch { name } => (
+                format!(
+                    "Sober is running and won't notice the switch until it restarts. Close Sober first, or switch to {} anyway?",
+                    core::format_profile_name(name)
+                ),
+                None,
+                "",
+            ),
+            ProfileDialog::ConfirmSwitch { name, locked_name: None } => {
+                (format!("Switch to {}?", core::format_profile_name(name)), None, "")
+            }
+        };
+        
+        let mut confirmed = false;
+        let mut cancelled = false;
+        
+        ui.add_space(10.0);
+        ui.vertical_centered(|ui| {
+            ui.group(|ui| {
+                ui.set_min_width(400.0);
+                ui.vertical(|ui| {
+                    ui.label(
+                        egui::RichText::new(title)
+                            .font(FontId::proportional(12.0))
+                            .color(Color32::WHITE)
+                    );
+                    
+                    if let Some(input) = input {
+                        ui.add_space(5.0);
+                        
+                        let response = ui.add(
+                            egui::TextEdit::singleline(input)
+                                .desired_width(380.0)
+                                .hint_text(hint)
+                        );
+                        Self::focus_dialog_field(ui, &response);
+                        
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            confirmed = true;
+                        }
+                    }
+                    
+                    if let Some(color) = color {
+                        ui.add_space(5.0);
+                        ui.color_edit_button_srgb(color);
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    ui.horizontal(|ui| {
+                        if ui.button("✅ Confirm").clicked() {
+                            confirmed = true;
+                        }
+                        
+                        if ui.button("❌ Cancel").clicked() {
+                            cancelled = true;
+                        }
+                    });
+                });
+            });
+        });
+        
+        if cancelled || keys.cancel {
+            self.profile_dialog = None;
+            self.launch_place = None;
+        } else if confirmed || keys.confirm {
+            self.apply_profile_dialog(ctx);
+        }
+    }
+    
     fn draw_profile_avatar(
         &self,
         ui: &mut egui::Ui,
         profile: &Profile,
         is_selected: bool,
         size: f32,
+        show_name: bool,
     ) -> egui::Response {
         let avatar_size = Vec2::new(size, size);
         let (rect, response) = ui.allocate_exact_size(avatar_size, egui::Sense::click());
+        response.widget_info(|| {
+            egui::WidgetInfo::selected(egui::WidgetType::Button, is_selected, format!("Switch to {}", profile.display_name))
+        });
         
         let bg_color = if is_selected {
-            Color32::from_rgb(70, 120, 180)
+            self.config.theme.accent_color()
         } else if response.hovered() {
             Color32::from_rgb(60, 80, 110)
         } else {
@@ -358,26 +5930,48 @@ impl SoberApp {
         };
         
         let border_color = if is_selected {
-            Color32::from_rgb(100, 150, 220)
+            self.config.theme.accent_border()
         } else {
             Color32::from_rgb(70, 90, 120)
         };
         
+        let radius = size * self.config.theme.avatar_rounding.clamp(0.0, 0.5);
+        let border_width = self.config.theme.avatar_border_width.clamp(0.0, MAX_AVATAR_BORDER_WIDTH);
+        
         // Draw background
-        ui.painter().rect_filled(rect, Rounding::same(8.0), bg_color);
+        ui.painter().rect_filled(rect, Rounding::same(radius), bg_color);
+        
+        // Color tag stripe along the top edge, inside the rounded corners
+        if let Some([r, g, b]) = profile.color_tag {
+            let stripe = Rect::from_min_size(rect.min, Vec2::new(size, 6.0));
+            ui.painter().rect_filled(
+                stripe,
+                Rounding { nw: radius, ne: radius, sw: 0.0, se: 0.0 },
+                Color32::from_rgb(r, g, b),
+            );
+        }
         
         // Draw border
-        ui.painter().rect_stroke(rect, Rounding::same(8.0), Stroke::new(2.0, border_color));
+        if border_width > 0.0 {
+            ui.painter().rect_stroke(rect, Rounding::same(radius), Stroke::new(border_width, border_color));
+        }
         
-        // Draw image or emoji
-        if let Some(texture) = &profile.image {
+        // Draw image or emoji; animated avatars ask for a repaint when their next frame is due
+        let texture = match &profile.animation {
+            Some(animation) => {
+                let (frame, next) = animation.frame_at(ui.input(|i| i.time));
+                ui.ctx().request_repaint_after(next);
+                Some(frame)
+            }
+            None => profile.image.as_ref(),
+        };
+        if let Some(texture) = texture {
+            // Rounded like the tile so round avatars don't show square corners
             let image_rect = Rect::from_center_size(rect.center(), Vec2::new(size * 0.8, size * 0.8));
-            ui.painter().image(
-                texture.id(),
-                image_rect,
-                Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
-                Color32::WHITE,
-            );
+            egui::Image::new((texture.id(), image_rect.size()))
+                .uv(Self::avatar_uv(&profile.crop, texture))
+                .rounding(radius * 0.8)
+                .paint_at(ui, image_rect);
         } else {
             ui.painter().text(
                 rect.center(),
@@ -386,21 +5980,136 @@ impl SoberApp {
                 FontId::proportional(size * 0.4),
                 Color32::WHITE,
             );
+            if self.avatar_loading(&profile.name) {
+                let spinner = (size * 0.2).max(10.0);
+                let spinner_rect = Rect::from_center_size(rect.right_top() + Vec2::new(-spinner, spinner), Vec2::splat(spinner));
+                ui.put(spinner_rect, egui::Spinner::new().size(spinner));
+            }
         }
         
-        // Draw name below
-        let name_rect = Rect::from_center_size(
-            Pos2::new(rect.center().x, rect.bottom() + 12.0),
-            Vec2::new(size + 20.0, 20.0),
-        );
+        // Result of the last post-launch login check
+        if let Some(login) = &profile.last_login {
+            let color = if login.ok { Color32::LIGHT_GREEN } else { Color32::LIGHT_RED };
+            ui.painter().circle_filled(rect.left_bottom() + Vec2::new(10.0, -10.0), 4.0, color);
+        }
         
-        ui.painter().text(
-            name_rect.center(),
-            Align2::CENTER_CENTER,
-            &profile.display_name,
-            FontId::proportional(10.0),
-            Color32::LIGHT_GRAY,
-        );
+        // Hotkey hint, next to the login dot when there is one
+        if let Some(chord) = profile.hotkey.filter(|_| self.config.theme.hotkey_hints) {
+            let indent = if profile.last_login.is_some() { 18.0 } else { 5.0 };
+            ui.painter().text(
+                rect.left_bottom() + Vec2::new(indent, -4.0),
+                Align2::LEFT_BOTTOM,
+                chord.to_string(),
+                FontId::proportional(9.0),
+                Color32::from_white_alpha(110),
+            );
+        }
+        
+        // Validity badge from the last verification
+        if let Some(health) = self.cookie_health.get(&profile.cookie_file) {
+            let (icon, color) = match health {
+                CookieHealth::Valid => ("✔", Color32::LIGHT_GREEN),
+                CookieHealth::Unreadable(_) => ("✖", Color32::LIGHT_RED),
+                _ => ("⚠", Color32::YELLOW),
+            };
+            ui.painter().text(
+                rect.right_bottom() + Vec2::new(-10.0, -10.0),
+                Align2::CENTER_CENTER,
+                icon,
+                FontId::proportional(12.0),
+                color,
+            );
+        }
+        
+        // Reminder badge
+        if Self::reminder_due(profile) {
+            ui.painter().text(
+                rect.center_top() + Vec2::new(0.0, 10.0),
+                Align2::CENTER_CENTER,
+                "⏰",
+                FontId::proportional(12.0),
+                Color32::YELLOW,
+            );
+        }
+        
+        // Tamper badge: the file no longer matches its recorded checksum
+        if profile.modified_externally {
+            ui.painter().text(
+                rect.center_bottom() + Vec2::new(0.0, -10.0),
+                Align2::CENTER_CENTER,
+                "✎",
+                FontId::proportional(12.0),
+                Color32::from_rgb(255, 160, 60),
+            );
+        }
+        
+        // Favorite badge
+        if profile.favorite {
+            ui.painter().text(
+                rect.right_top() + Vec2::new(-10.0, 10.0),
+                Align2::CENTER_CENTER,
+                "⭐",
+                FontId::proportional(12.0),
+                Color32::GOLD,
+            );
+        }
+        
+        // Lock badge
+        if profile.locked {
+            ui.painter().text(
+                rect.left_top() + Vec2::new(10.0, 10.0),
+                Align2::CENTER_CENTER,
+                "🔒",
+                FontId::proportional(12.0),
+                Color32::WHITE,
+            );
+        }
+        
+        // Nickname over the avatar instead of the name below it
+        let overlay_offset = size * 0.2;
+        let overlay_center = match self.config.theme.name_placement {
+            NamePlacement::Below => None,
+            NamePlacement::OverlayTop => Some(rect.center_top() + Vec2::new(0.0, overlay_offset)),
+            NamePlacement::OverlayBottom => Some(rect.center_bottom() - Vec2::new(0.0, overlay_offset)),
+        };
+        if let Some(center) = overlay_center {
+            let nickname = if profile.nickname.is_empty() { core::profile_initials(&profile.display_name) } else { profile.nickname.clone() };
+            let galley = ui.painter().layout_no_wrap(nickname, FontId::proportional((size * 0.16).max(9.0)), Color32::WHITE);
+            let pill = Rect::from_center_size(center, galley.size() + Vec2::new(8.0, 2.0));
+            if self.config.theme.name_pill {
+                ui.painter().rect_filled(pill, Rounding::same(pill.height() / 2.0), Color32::from_black_alpha(170));
+            }
+            ui.painter().galley(pill.center() - galley.size() / 2.0, galley, Color32::WHITE);
+        }
+        
+        // Draw name below, cut off or wrapped to fit the column; the hover card has the full name
+        if show_name && overlay_center.is_none() {
+            let badge_room = if profile.username.is_some() { 12.0 } else { 0.0 };
+            let mut job = egui::text::LayoutJob::simple(profile.display_name.clone(), FontId::proportional(10.0), Color32::LIGHT_GRAY, size + 20.0 - badge_room);
+            job.halign = egui::Align::Center;
+            job.wrap.max_rows = match self.config.theme.name_overflow {
+                NameOverflow::Ellipsis => 1,
+                NameOverflow::Wrap => 2,
+            };
+            job.wrap.break_anywhere = job.wrap.max_rows == 1;
+            let galley = ui.fonts(|fonts| fonts.layout_job(job));
+            
+            let origin = Pos2::new(rect.center().x, rect.bottom() + 5.0);
+            let text_rect = galley.rect.translate(origin.to_vec2());
+            let first_row_height = galley.rows.first().map_or(text_rect.height(), |row| row.height());
+            ui.painter().galley(origin, galley, Color32::LIGHT_GRAY);
+            
+            // Verified badge: the name came from Roblox rather than the file name
+            if profile.username.is_some() {
+                ui.painter().text(
+                    text_rect.right_top() + Vec2::new(2.0, first_row_height / 2.0),
+                    Align2::LEFT_CENTER,
+                    "✔",
+                    FontId::proportional(9.0),
+                    Color32::from_rgb(90, 170, 255),
+                );
+            }
+        }
         
         response
     }
@@ -408,22 +6117,44 @@ impl SoberApp {
 
 impl eframe::App for SoberApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        let mut visuals = egui::Visuals::dark();
-        visuals.widgets.noninteractive.bg_stroke = Stroke::NONE;
-        visuals.widgets.inactive.bg_stroke = Stroke::NONE;
-        visuals.widgets.hovered.bg_stroke = Stroke::NONE;
-        visuals.widgets.active.bg_stroke = Stroke::NONE;
-        visuals.widgets.open.bg_stroke = Stroke::NONE;
-        visuals.panel_fill = Color32::from_rgb(32, 47, 64);
-        ctx.set_visuals(visuals);
+        // Everything visual is derived from the config each frame, so edits apply immediately
+        self.poll_filesystem(ctx);
+        self.count_idle_frames(ctx);
+        ctx.set_visuals(self.config.theme.visuals());
+        
+        self.poll_verification();
+        self.poll_avatars(ctx);
+        self.poll_login_check(ctx);
+        self.poll_identity_check();
+        self.poll_session_check();
+        self.poll_links(ctx);
+        self.poll_tray(ctx);
+        self.record_window_geometry(ctx);
+        self.handle_profile_hotkeys(ctx);
+        
+        if self.config.mini_mode {
+            self.draw_mini_mode(ctx);
+            self.draw_quick_switch(ctx);
+            self.draw_toasts(ctx);
+            return;
+        }
         
         self.draw_custom_title_bar(ctx, frame);
+        if self.read_only() {
+            self.draw_read_only_banner(ctx);
+        }
+        self.draw_session_warning(ctx);
         
-        let bg_color = Color32::from_rgb(32, 47, 64);
+        let bg_color = self.config.theme.panel_color();
+        let modal = self.inline_dialog_open();
+        let overlay_open = self.command_palette.is_some() || self.quick_switch_open;
+        let dialog_keys = if modal && !overlay_open { Self::take_dialog_keys(ctx) } else { DialogKeys::default() };
         
         egui::CentralPanel::default()
             .frame(egui::Frame::none().fill(bg_color).inner_margin(0.0))
             .show(ctx, |ui| {
+                self.draw_background(ui);
+                
                 ui.spacing_mut().item_spacing.y = 0.0;
                 ui.add_space(25.0);
                 
@@ -434,7 +6165,7 @@ impl eframe::App for SoberApp {
                     } else {
                         let (rect, _) = ui.allocate_exact_size(Vec2::new(32.0, 32.0), egui::Sense::hover());
                         ui.painter().circle_filled(rect.center(), 16.0, Color32::WHITE);
-                        ui.painter().circle_filled(rect.center(), 12.0, Color32::from_rgb(32, 47, 64));
+                        ui.painter().circle_filled(rect.center(), 12.0, self.config.theme.panel_color());
                     }
                 });
                 
@@ -451,104 +6182,288 @@ impl eframe::App for SoberApp {
                 
                 ui.add_space(20.0);
                 
-                // Error message display
-                if let Some(error) = &self.error_message.clone() {
-                    ui.vertical_centered(|ui| {
-                        let color = if error.starts_with("✅") {
-                            Color32::LIGHT_GREEN
-                        } else {
-                            Color32::LIGHT_RED
-                        };
+                // While an inline dialog is open the rest can't be clicked or tabbed into
+                ui.add_enabled_ui(!modal, |ui| {
+                    // Profile selection
+                    if self.show_onboarding {
+                        self.draw_onboarding(ui);
+                    } else if self.profiles.is_empty() {
+                        ui.vertical_centered(|ui| {
+                            ui.label(
+                                egui::RichText::new("No profiles found")
+                                    .font(FontId::proportional(16.0))
+                                    .color(Color32::GRAY)
+                            );
+                            ui.add_space(10.0);
+                            match &self.scan_error {
+                                // Sober only creates its data directory on first launch
+                                Some(AppError::DirectoryNotFound(_)) => {
+                                    ui.label(
+                                        egui::RichText::new(format!("{} doesn't exist yet", self.cookie_directory.display()))
+                                            .font(FontId::proportional(12.0))
+                                            .color(Color32::YELLOW)
+                                    );
+                                    ui.add_space(5.0);
+                                    if ui.button("📁 Create directory").clicked() {
+                                        self.create_cookie_directory(ctx);
+                                    }
+                                }
+                                Some(error) => {
+                                    ui.label(
+                                        egui::RichText::new(error.to_string())
+                                            .font(FontId::proportional(12.0))
+                                            .color(Color32::LIGHT_RED)
+                                    );
+                                }
+                                None => {
+                                    ui.label(
+                                        egui::RichText::new("Create 'cookies_*.txt' files to auto-generate profiles")
+                                            .font(FontId::proportional(12.0))
+                                            .color(Color32::DARK_GRAY)
+                                    );
+                                }
+                            }
+                        });
+                    } else {
+                        // Search box filters by name, display name and aliases
+                        ui.vertical_centered(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.search_query)
+                                    .desired_width(200.0)
+                                    .hint_text("🔍 Search profiles or aliases")
+                            );
+                        });
+                        ui.add_space(15.0);
                         
-                        ui.label(
-                            egui::RichText::new(error)
-                                .font(FontId::proportional(12.0))
-                                .color(color)
-                        );
-                    });
-                    ui.add_space(10.0);
-                }
-                
-                // Profile selection
-                if self.profiles.is_empty() {
-                    ui.vertical_centered(|ui| {
-                        ui.label(
-                            egui::RichText::new("No profiles found")
-                                .font(FontId::proportional(16.0))
-                                .color(Color32::GRAY)
-                        );
-                        ui.add_space(10.0);
-                        ui.label(
-                            egui::RichText::new("Create 'cookies_*.txt' files to auto-generate profiles")
-                                .font(FontId::proportional(12.0))
-                                .color(Color32::DARK_GRAY)
-                        );
-                    });
-                } else {
-                    // Dynamic profile layout
-                    ui.vertical_centered(|ui| {
-                        let profiles_per_row = 3;
-                        let avatar_size = 80.0;
-                        let spacing = 20.0;
+                        let visible: Vec<usize> = (0..self.profiles.len())
+                            .filter(|&i| Self::profile_matches(&self.profiles[i], &self.search_query))
+                            .collect();
+                        
+                        if visible.is_empty() {
+                            ui.vertical_centered(|ui| {
+                                ui.label(
+                                    egui::RichText::new(format!("No profiles match '{}'", self.search_query.trim()))
+                                        .font(FontId::proportional(12.0))
+                                        .color(Color32::GRAY)
+                                );
+                            });
+                            ui.add_space(15.0);
+                        }
                         
-                        // Clone profiles to avoid borrowing issues
-                        let profiles_clone = self.profiles.clone();
+                        // Context menu actions run after the grid so they can't reshuffle it mid-draw
+                        let mut pending_action = None;
+                        let show_grid = ui.available_width() >= DROPDOWN_ONLY_WIDTH;
                         
-                        for chunk in profiles_clone.chunks(profiles_per_row) {
-                            ui.horizontal(|ui| {
-                                let row_width = chunk.len() as f32 * avatar_size + (chunk.len() - 1) as f32 * spacing;
-                                let available_width = ui.available_width();
-                                let start_offset = (available_width - row_width) / 2.0;
-                                ui.add_space(start_offset);
+                        // Dynamic profile layout
+                        if show_grid {
+                            ui.vertical_centered(|ui| {
+                                let avatar_size = 80.0;
+                                let spacing = 20.0;
+                                let alignment = self.config.grid_alignment;
+                                let profiles_per_row = match alignment {
+                                    GridAlignment::Center => 3,
+                                    // As many as fit between a margin of one spacing on either side
+                                    GridAlignment::Left => (((ui.available_width() - spacing) / (avatar_size + spacing)) as usize).max(1),
+                                };
                                 
-                                for (i, profile) in chunk.iter().enumerate() {
-                                    let global_index = self.profiles.iter().position(|p| p.name == profile.name).unwrap();
-                                    let is_selected = self.selected_profile == Some(global_index);
-                                    
-                                    let response = self.draw_profile_avatar(ui, profile, is_selected, avatar_size);
-                                    
-                                    if response.clicked() {
-                                        if self.selected_profile == Some(global_index) {
-                                            self.selected_profile = None;
-                                        } else {
-                                            self.selected_profile = Some(global_index);
-                                            self.copy_cookie_file(global_index);
+                                for chunk in visible.chunks(profiles_per_row) {
+                                    ui.horizontal(|ui| {
+                                        let start_offset = match alignment {
+                                            GridAlignment::Center => {
+                                                let row_width = chunk.len() as f32 * avatar_size + (chunk.len() - 1) as f32 * spacing;
+                                                (ui.available_width() - row_width) / 2.0
+                                            }
+                                            GridAlignment::Left => spacing,
+                                        };
+                                        ui.add_space(start_offset);
+                                        
+                                        for (i, &global_index) in chunk.iter().enumerate() {
+                                            // Clone the profile to avoid borrowing issues
+                                            let profile = self.profiles[global_index].clone();
+                                            let is_selected = self.armed_profile.or(self.selected_profile) == Some(global_index);
+                                            
+                                            let response = self
+                                                .draw_profile_avatar(ui, &profile, is_selected, avatar_size, true)
+                                                .on_hover_ui(|ui| self.draw_hover_card(ui, &profile));
+                                            
+                                            if response.clicked() && self.config.developer_mode && ui.input(|i| i.modifiers.shift) {
+                                                self.switch_and_copy_token(ctx, global_index);
+                                            } else if response.clicked() {
+                                                if self.selected_profile == Some(global_index) && self.armed_profile.is_none() {
+                                                    // A locked profile stays selected so the guard can't be sidestepped
+                                                    if !profile.locked {
+                                                        self.selected_profile = None;
+                                                    }
+                                                } else {
+                                                    self.click_profile(ctx, global_index);
+                                                }
+                                            }
+                                            
+                                            response.context_menu(|ui| {
+                                                if let Some(action) = Self::draw_profile_menu(ui, &profile) {
+                                                    pending_action = Some((global_index, action));
+                                                    ui.close_menu();
+                                                }
+                                            });
+                                            
+                                            if i < chunk.len() - 1 {
+                                                ui.add_space(spacing);
+                                            }
                                         }
-                                    }
-                                    
-                                    if i < chunk.len() - 1 {
-                                        ui.add_space(spacing);
-                                    }
+                                    });
+                                    // Overlaid names leave nothing below the avatars to make room for
+                                    ui.add_space(match (self.config.theme.name_placement, self.config.theme.name_overflow) {
+                                        // Room for the second line
+                                        (NamePlacement::Below, NameOverflow::Wrap) => 27.0,
+                                        (NamePlacement::Below, NameOverflow::Ellipsis) => 15.0,
+                                        _ => 8.0,
+                                    });
                                 }
                             });
+                        }
+                        
+                        if !visible.is_empty() && (!show_grid || self.config.profile_dropdown) {
+                            self.draw_profile_dropdown(ui, ctx, &visible);
                             ui.add_space(15.0);
                         }
-                    });
-                }
+                        
+                        if let Some((index, action)) = pending_action {
+                            self.handle_profile_action(ctx, index, action);
+                        }
+                    }
+                });
+                
+                self.draw_profile_dialog(ui, ctx, dialog_keys);
                 
                 ui.add_space(20.0);
                 
-                // Directory selection button
-                ui.vertical_centered(|ui| {
-                    ui.horizontal(|ui| {
+                ui.add_enabled_ui(!modal, |ui| {
+                    // Directory selection button
+                    ui.vertical_centered(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new("Cookie Directory:")
+                                    .font(FontId::proportional(12.0))
+                                    .color(Color32::LIGHT_GRAY)
+                            );
+                            
+                            let pinned = self.config.directory_pinned;
+                            if ui
+                                .add_enabled(!pinned, egui::Button::new("📁 Change Directory"))
+                                .on_disabled_hover_text("The directory is pinned; click 📌 to unpin it")
+                                .clicked()
+                            {
+                                self.open_directory_dialog();
+                            }
+                            let pin_hint = if pinned { "Unpin the directory" } else { "Pin the directory so it can't be changed by accident" };
+                            if ui.selectable_label(pinned, "📌").on_hover_text(pin_hint).clicked() {
+                                self.set_directory_pinned(!pinned);
+                            }
+                            
+                            if ui.button("📋 Paste Cookie").clicked() {
+                                self.paste_cookie_from_clipboard();
+                            }
+                            
+                            if ui.button("📥 Import File").clicked() {
+                                self.import_dialog = Some(ImportDialog::default());
+                            }
+                            
+                            if ui
+                                .button("🍪 Import Cookie-Editor JSON")
+                                .on_hover_text("In the Cookie-Editor extension on roblox.com, choose Export → JSON and save it to a file")
+                                .clicked()
+                            {
+                                self.import_dialog = Some(ImportDialog { cookie_editor: true, ..ImportDialog::default() });
+                            }
+                            
+                            let previous = self.previous_profile().and_then(|index| self.profiles.get(index));
+                            let previous_hint = previous.map(|p| format!("Switch back to {} (Ctrl+P)", p.display_name)).unwrap_or_default();
+                            if ui
+                                .add_enabled(previous.is_some(), egui::Button::new("⇄ Previous"))
+                                .on_hover_text(previous_hint)
+                                .on_disabled_hover_text("Nothing to switch back to yet")
+                                .clicked()
+                            {
+                                self.switch_to_previous(ctx);
+                            }
+                            
+                            if self.scratch_session.is_some() {
+                                if ui.button("↩ Restore kept session").clicked() {
+                                    self.restore_scratch_session();
+                                }
+                            } else if ui
+                                .add_enabled(self.live_exists, egui::Button::new("⏸ Keep session"))
+                                .on_hover_text("Holds the live session in memory only, so you can try another login and come back to it")
+                                .on_disabled_hover_text("There is no live cookies file to keep")
+                                .clicked()
+                            {
+                                self.keep_scratch_session();
+                            }
+                            
+                            let verifying = self.verify_receiver.is_some();
+                            let verify_label = if verifying { "⏳ Verifying…" } else { "🩺 Verify All" };
+                            if ui.add_enabled(!verifying, egui::Button::new(verify_label)).clicked() {
+                                self.start_verification(ctx);
+                            }
+                        });
+                        
+                        // Show current directory
                         ui.label(
-                            egui::RichText::new("Cookie Directory:")
-                                .font(FontId::proportional(12.0))
-                                .color(Color32::LIGHT_GRAY)
+                            egui::RichText::new(format!("📂 {}", self.cookie_directory.display()))
+                                .font(FontId::proportional(10.0))
+                                .color(Color32::DARK_GRAY)
                         );
+                        let mut discard_scratch = false;
+                        if let Some(scratch) = &self.scratch_session {
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    egui::RichText::new(" temporary session kept ")
+                                        .font(FontId::proportional(9.0))
+                                        .color(Color32::WHITE)
+                                        .background_color(Color32::from_rgb(110, 80, 150))
+                                )
+                                .on_hover_text(format!(
+                                    "Kept {} from {}; only in memory, so it's lost when the app closes",
+                                    Self::format_timestamp(scratch.kept_at),
+                                    scratch.directory.display()
+                                ));
+                                discard_scratch = ui.small_button("✖").on_hover_text("Forget the kept session").clicked();
+                            });
+                        }
+                        if discard_scratch {
+                            self.scratch_session = None;
+                        }
+                        if self.profiles_locked {
+                            ui.label(
+                                egui::RichText::new(" locked ")
+                                    .font(FontId::proportional(9.0))
+                                    .color(Color32::WHITE)
+                                    .background_color(Color32::from_rgb(150, 90, 40))
+                            )
+                            .on_hover_text("Other profiles are locked away; unlock them in Settings → Shared machine");
+                        }
+                        if self.flatpak_directory {
+                            ui.label(
+                                egui::RichText::new(" flatpak ")
+                                    .font(FontId::proportional(9.0))
+                                    .color(Color32::WHITE)
+                                    .background_color(Color32::from_rgb(74, 108, 160))
+                            )
+                            .on_hover_text(
+                                "This is Sober's flatpak sandbox. A running Sober writes its session back on exit, \
+                                 so close it before switching profiles."
+                            );
+                        }
                         
-                        if ui.button("📁 Change Directory").clicked() {
-                            self.show_directory_dialog = true;
-                            self.temp_directory_input = self.cookie_directory.to_string_lossy().to_string();
+                        let mut store_in_directory = self.directory_config.is_some();
+                        if ui
+                            .checkbox(&mut store_in_directory, "Keep profile settings in this directory")
+                            .on_hover_text(format!("Stores aliases and other profile settings in {} instead of the global config", DIRECTORY_CONFIG_FILE))
+                            .changed()
+                        {
+                            self.set_directory_config_enabled(store_in_directory);
                         }
                     });
-                    
-                    // Show current directory
-                    ui.label(
-                        egui::RichText::new(format!("📂 {}", self.cookie_directory.display()))
-                            .font(FontId::proportional(10.0))
-                            .color(Color32::DARK_GRAY)
-                    );
                 });
                 
                 // Directory dialog
@@ -569,33 +6484,211 @@ impl eframe::App for SoberApp {
                                 let response = ui.add(
                                     egui::TextEdit::singleline(&mut self.temp_directory_input)
                                         .desired_width(380.0)
-                                        .hint_text("e.g., ~/.var/app/org.vinegarhq.Sober/data/sober/")
+                                        .hint_text(format!("e.g., {}", DEFAULT_COOKIE_DIRECTORY))
                                 );
+                                Self::focus_dialog_field(ui, &response);
                                 
                                 if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
                                     self.apply_directory_change(ctx);
                                 }
                                 
+                                if !self.recent_directories.is_empty() {
+                                    ui.add_space(5.0);
+                                    
+                                    let entries = self.recent_directories.clone();
+                                    let labels: Vec<String> = entries
+                                        .iter()
+                                        .map(|entry| {
+                                            let count = match self.directory_profile_count(&entry.path) {
+                                                Some(1) => "1 profile".to_string(),
+                                                Some(count) => format!("{} profiles", count),
+                                                None => "unavailable".to_string(),
+                                            };
+                                            if entry.used_at == 0 {
+                                                format!("{}  ({})", entry.path, count)
+                                            } else {
+                                                format!("{}  ({}, {})", entry.path, count, Self::format_timestamp(entry.used_at))
+                                            }
+                                        })
+                                        .collect();
+                                    
+                                    egui::ComboBox::from_id_source("recent_directories")
+                                        .selected_text("🕘 Recent directories")
+                                        .width(380.0)
+                                        .show_ui(ui, |ui| {
+                                            for (entry, label) in entries.iter().zip(labels) {
+                                                if ui.selectable_label(self.temp_directory_input == entry.path, label).clicked() {
+                                                    self.temp_directory_input = entry.path.clone();
+                                                }
+                                            }
+                                        });
+                                }
+                                
                                 ui.add_space(10.0);
                                 
                                 ui.horizontal(|ui| {
-                                    if ui.button("✅ Apply").clicked() {
+                                    if ui.button("✅ Apply").clicked() || dialog_keys.confirm {
                                         self.apply_directory_change(ctx);
                                     }
                                     
-                                    if ui.button("❌ Cancel").clicked() {
+                                    if ui.button("❌ Cancel").clicked() || dialog_keys.cancel {
                                         self.show_directory_dialog = false;
                                     }
                                     
                                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                         if ui.button("🔄 Reset to Default").clicked() {
-                                            self.temp_directory_input = "~/.var/app/org.vinegarhq.Sober/data/sober/".to_string();
+                                            let default = core::default_cookie_directory();
+                                            self.temp_directory_input = if default == core::expand_path(DEFAULT_COOKIE_DIRECTORY) {
+                                                DEFAULT_COOKIE_DIRECTORY.to_string()
+                                            } else {
+                                                default.to_string_lossy().into_owned()
+                                            };
+                                        }
+                                    });
+                                });
+                            });
+                        });
+                    });
+                }
+                
+                // Pasted cookie naming dialog
+                if self.pasted_cookie.is_some() {
+                    ui.add_space(10.0);
+                    ui.vertical_centered(|ui| {
+                        ui.group(|ui| {
+                            ui.set_min_width(400.0);
+                            ui.vertical(|ui| {
+                                ui.label(
+                                    egui::RichText::new("Valid cookie found. Name the new profile:")
+                                        .font(FontId::proportional(12.0))
+                                        .color(Color32::WHITE)
+                                );
+                                
+                                ui.add_space(5.0);
+                                
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut self.pasted_profile_name)
+                                        .desired_width(380.0)
+                                        .hint_text("e.g., main or alt_1")
+                                );
+                                Self::focus_dialog_field(ui, &response);
+                                
+                                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                    self.save_pasted_cookie(ctx);
+                                }
+                                
+                                ui.add_space(10.0);
+                                
+                                ui.horizontal(|ui| {
+                                    if ui.button("✅ Save").clicked() || dialog_keys.confirm {
+                                        self.save_pasted_cookie(ctx);
+                                    }
+                                    
+                                    if ui.button("❌ Cancel").clicked() || dialog_keys.cancel {
+                                        self.pasted_cookie = None;
+                                    }
+                                });
+                            });
+                        });
+                    });
+                }
+                
+                // Import from file dialog
+                if let Some(dialog) = self.import_dialog.as_mut() {
+                    let mut import = false;
+                    let mut cancel = false;
+                    ui.add_space(10.0);
+                    ui.vertical_centered(|ui| {
+                        ui.group(|ui| {
+                            ui.set_min_width(400.0);
+                            ui.vertical(|ui| {
+                                if dialog.confirm_move {
+                                    ui.label(
+                                        egui::RichText::new(format!(
+                                            "Move {} into the cookie directory? The original file will be removed.",
+                                            core::expand_path(dialog.source.trim()).display()
+                                        ))
+                                        .font(FontId::proportional(12.0))
+                                        .color(Color32::WHITE)
+                                    );
+                                    
+                                    ui.add_space(10.0);
+                                    
+                                    ui.horizontal(|ui| {
+                                        if ui.button("✅ Move").clicked() || dialog_keys.confirm {
+                                            import = true;
+                                        }
+                                        
+                                        if ui.button("↩ Back").clicked() || dialog_keys.cancel {
+                                            dialog.confirm_move = false;
                                         }
                                     });
+                                    return;
+                                }
+                                
+                                let (source_label, source_hint) = if dialog.cookie_editor {
+                                    ("Cookie-Editor JSON export to import:", "e.g., ~/Downloads/roblox_cookies.json")
+                                } else {
+                                    ("Cookie file to import:", "e.g., ~/Downloads/cookies.txt")
+                                };
+                                ui.label(
+                                    egui::RichText::new(source_label)
+                                        .font(FontId::proportional(12.0))
+                                        .color(Color32::WHITE)
+                                );
+                                
+                                ui.add_space(5.0);
+                                
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut dialog.source)
+                                        .desired_width(380.0)
+                                        .hint_text(source_hint)
+                                );
+                                Self::focus_dialog_field(ui, &response);
+                                
+                                ui.add_space(5.0);
+                                
+                                ui.label(
+                                    egui::RichText::new("Name the new profile:")
+                                        .font(FontId::proportional(12.0))
+                                        .color(Color32::WHITE)
+                                );
+                                
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut dialog.name)
+                                        .desired_width(380.0)
+                                        .hint_text("e.g., main or alt_1")
+                                );
+                                
+                                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                    import = true;
+                                }
+                                
+                                if !dialog.cookie_editor {
+                                    ui.checkbox(&mut dialog.move_source, "Move instead of copy")
+                                        .on_hover_text("Removes the original file once it has been imported");
+                                }
+                                
+                                ui.add_space(10.0);
+                                
+                                ui.horizontal(|ui| {
+                                    if ui.button("✅ Import").clicked() || dialog_keys.confirm {
+                                        import = true;
+                                    }
+                                    
+                                    if ui.button("❌ Cancel").clicked() || dialog_keys.cancel {
+                                        cancel = true;
+                                    }
                                 });
                             });
                         });
                     });
+                    
+                    if cancel {
+                        self.import_dialog = None;
+                    } else if import {
+                        self.import_cookie_file(ctx);
+                    }
                 }
                 
                 ui.add_space(15.0);
@@ -622,6 +6715,22 @@ impl eframe::App for SoberApp {
                     }
                 });
             });
+        
+        self.draw_settings_window(ctx);
+        self.draw_profile_details(ctx);
+        self.draw_profile_comparison(ctx);
+        self.draw_vault_dialog(ctx);
+        self.draw_quick_switch(ctx);
+        self.draw_command_palette(ctx);
+        self.draw_toasts(ctx);
+    }
+    
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Window geometry is only tracked in memory while running
+        self.config.last_snapshot = Some(DirectorySnapshot::capture(&self.cookie_directory, &self.live_file()));
+        if let Err(e) = self.save_config() {
+            log::error!("Failed to save settings on exit: {}", e);
+        }
     }
 }
 
@@ -632,4 +6741,22 @@ eframe = "0.27"
 egui = "0.27"
 image = "0.24"
 dirs = "5.0"
+serde = { version = "1.0", features = ["derive"] }
+serde_json = "1.0"
+arboard = { version = "3", default-features = false }
+log = { version = "0.4", features = ["std"] }
+chrono = "0.4"
+global-hotkey = "0.8"
+flate2 = "1"
+ureq = "2"
+qrcode = { version = "0.14", default-features = false }
+sha2 = "0.10"
+ksni = { version = "0.3", default-features = false, features = ["blocking", "async-io"] }
+notify-rust = "4"
+chacha20poly1305 = "0.10"
+pbkdf2 = "0.12"
+sysinfo = { version = "0.39", default-features = false, features = ["system"] }
+
+[dev-dependencies]
+tempfile = "3"
 */